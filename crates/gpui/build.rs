@@ -298,6 +298,7 @@ mod windows {
             "underline",
             "monochrome_sprite",
             "polychrome_sprite",
+            "surface",
         ];
 
         let rust_binding_path = format!("{}/shaders_bytes.rs", out_dir);
@@ -10,6 +10,7 @@ mod surface;
 mod svg;
 mod text;
 mod uniform_list;
+mod video;
 
 pub use anchored::*;
 pub use animation::*;
@@ -23,3 +24,4 @@ pub use surface::*;
 pub use svg::*;
 pub use text::*;
 pub use uniform_list::*;
+pub use video::*;
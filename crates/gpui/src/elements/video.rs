@@ -0,0 +1,778 @@
+use crate::{
+    Alignment, AlphaMode, App, Bounds, ContentMask, Element, ElementId, FramePacer,
+    GlobalElementId, Hsla, InspectorElementId, IntoElement, LayoutId, ObjectFit, Pixels, Rgba,
+    Rotation, ScaleFilter, Size, Style, StyleRefinement, Styled, VideoFrame, VideoFrameError,
+    Window, fill, point, px, transparent_black,
+    video::{PartialUpload, VideoCacheKey},
+};
+use refineable::Refineable;
+use std::time::{Duration, Instant};
+use util::ResultExt as _;
+
+/// The largest side of the frame that [`LetterboxStyle::BlurredFill`] blurs,
+/// in pixels. The frame is downscaled to this size first, which keeps the blur
+/// cheap and, once scaled back up, softens it further.
+const BLURRED_FILL_SIZE: u32 = 48;
+
+/// The blur radius of [`LetterboxStyle::BlurredFill`], in downscaled pixels.
+const BLURRED_FILL_RADIUS: u32 = 4;
+
+/// How long the [`LetterboxStyle::BlurredFill`] of a frame that can't be told
+/// apart from the next one, such as a hardware frame, is kept before it's
+/// blurred again from the frame on screen.
+const BLURRED_FILL_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The number of frames pulled ahead from a [`Video`]'s source, which the
+/// pacer reorders into presentation order.
+const SOURCE_REORDER_CAPACITY: usize = 8;
+
+/// How far a frame from a [`Video`]'s source may fall behind the playback
+/// clock before it's skipped.
+const SOURCE_LATE_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// A video element, which paints a [`VideoFrame`] within its bounds.
+///
+/// Packed CPU frames drawn at less than half their size are painted from the
+/// nearest level of their mip chain, as described in
+/// [`VideoFrame::generate_mip_chain`], to avoid aliasing. An element with an
+/// [id](Video::id) keeps that level between paints, so a frame painted again
+/// isn't downsampled again.
+pub struct Video {
+    id: Option<ElementId>,
+    frame: VideoFrame,
+    object_fit: ObjectFit,
+    letterbox: LetterboxStyle,
+    dirty_rect: Option<(u32, u32, u32, u32)>,
+    paused: bool,
+    playback_rate: f32,
+    source: Option<Box<dyn Iterator<Item = VideoFrame>>>,
+    style: StyleRefinement,
+}
+
+/// What a [`Video`] element paints in the parts of its bounds the frame
+/// doesn't cover, such as the bars around a letterboxed frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LetterboxStyle {
+    /// Fill the bars with a solid color.
+    Color(Hsla),
+    /// Fill the bars with a blurred copy of the frame scaled to cover the
+    /// element, as in many media players and call UIs.
+    ///
+    /// An element with an [id](Video::id) blurs each frame once rather than
+    /// on every paint. Frames that live on the GPU are read back to be
+    /// blurred, at most a few times a second.
+    BlurredFill,
+}
+
+impl Default for LetterboxStyle {
+    fn default() -> Self {
+        LetterboxStyle::Color(transparent_black())
+    }
+}
+
+/// Create a new video element that paints the given frame.
+pub fn video(frame: VideoFrame) -> Video {
+    Video {
+        id: None,
+        frame,
+        object_fit: ObjectFit::Contain,
+        letterbox: LetterboxStyle::default(),
+        dirty_rect: None,
+        paused: false,
+        playback_rate: 1.0,
+        source: None,
+        style: Default::default(),
+    }
+}
+
+impl Video {
+    /// Give this element an id, so that it keeps the frame it painted last
+    /// and its playback state until the next paint, as [`Self::dirty_rect`],
+    /// [`Self::paused`], [`Self::playback_rate`] and [`Self::source`] require.
+    pub fn id(mut self, id: impl Into<ElementId>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Hint that only the `(x, y, width, height)` region of the frame, in
+    /// pixels, differs from the frame this element painted last, as found by
+    /// [`VideoFrame::diff_bounds`], such as for a mostly static screen share.
+    ///
+    /// The renderer then updates just that region of the texture it uploaded
+    /// the last frame to, instead of uploading the whole frame. The whole
+    /// frame is still uploaded if the element has no [id](Self::id), or if the
+    /// last frame's texture is no longer cached or differs in size or pixel
+    /// format. When the frame is painted from a level of its mip chain, the
+    /// region is scaled down to that level.
+    pub fn dirty_rect(mut self, rect: (u32, u32, u32, u32)) -> Self {
+        self.dirty_rect = Some(rect);
+        self
+    }
+
+    /// Pause or resume playback.
+    ///
+    /// `paused(true)` freezes the element on the last frame supplied to it:
+    /// frames passed to [`video`] on later renders are ignored, and frames
+    /// from its [source](Self::source) are held back, until playback resumes.
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    /// Set the speed of the playback clock that paces the frames from the
+    /// element's [source](Self::source), such as `0.25` for slow motion.
+    /// Defaults to `1.0`, and rates below zero are treated as zero.
+    pub fn playback_rate(mut self, rate: f32) -> Self {
+        self.playback_rate = rate;
+        self
+    }
+
+    /// Play the frames of `source` in place of the frame passed to [`video`],
+    /// which stays on screen until the first frame from the source is due.
+    ///
+    /// The element pulls frames from the source as it paints, reorders them
+    /// by PTS with a [`FramePacer`], and shows each one once the playback
+    /// clock, which starts at the PTS of the first frame, reaches it. Frames
+    /// without a PTS are skipped. Once the source runs out, its last frame
+    /// stays on screen. Sources passed on later renders are dropped until the
+    /// current one runs out, so passing one on every render doesn't restart
+    /// playback.
+    pub fn source(mut self, source: impl Iterator<Item = VideoFrame> + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Set how the frame is fitted into the element's bounds when their aspect
+    /// ratios differ. [`ObjectFit::Contain`] letterboxes the frame and
+    /// [`ObjectFit::Cover`] crops it to the element's bounds.
+    pub fn object_fit(mut self, object_fit: ObjectFit) -> Self {
+        self.object_fit = object_fit;
+        self
+    }
+
+    /// Set what is painted around the frame when it doesn't cover the
+    /// element's bounds. Defaults to a transparent [`LetterboxStyle::Color`].
+    pub fn letterbox(mut self, letterbox: LetterboxStyle) -> Self {
+        self.letterbox = letterbox;
+        self
+    }
+
+    /// Composite this element on the CPU as it would appear when painted into
+    /// `bounds`, such as for snapshot tests that run without a GPU.
+    ///
+    /// Returns a BGRA frame the size of `bounds` in pixels, with the frame
+    /// rotated by its display rotation, fitted with the element's object fit
+    /// and blended over its letterbox. Scaling is bilinear, so the pixels are
+    /// deterministic across platforms but may differ slightly from what a GPU
+    /// samples. Corner radii aren't applied.
+    pub fn render_to_bgra(&self, bounds: Bounds<Pixels>) -> VideoFrame {
+        let (width, height) = (
+            pixel_length(bounds.size.width),
+            pixel_length(bounds.size.height),
+        );
+        let frame = self.frame.to_bgra();
+        let frame = match self.frame.display_rotation() {
+            Rotation::None => frame,
+            rotation => frame.rotate(rotation).log_err().unwrap_or(frame),
+        };
+        let transparent = || VideoFrame::solid_color(width, height, [0; 4]);
+        if frame.width == 0 || frame.height == 0 {
+            return transparent();
+        }
+
+        let mut canvas = match self.letterbox {
+            LetterboxStyle::Color(color) => {
+                let Rgba { r, g, b, a } = Rgba::from(color);
+                let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                VideoFrame::solid_color(width, height, [b, g, r, a].map(channel))
+            }
+            LetterboxStyle::BlurredFill => blurred_backdrop(&frame)
+                .and_then(|backdrop| fit_to_canvas(&backdrop, width, height, ObjectFit::Cover))
+                .log_err()
+                .unwrap_or_else(transparent),
+        };
+        if let Some(fitted) = fit_to_canvas(&frame, width, height, self.object_fit).log_err() {
+            canvas.composite(&fitted, 0, 0).log_err();
+        }
+        canvas
+    }
+}
+
+/// Blur a copy of `frame` downscaled to [`BLURRED_FILL_SIZE`], converted to
+/// BGRA, for [`LetterboxStyle::BlurredFill`].
+fn blurred_backdrop(frame: &VideoFrame) -> Result<VideoFrame, VideoFrameError> {
+    let mut backdrop = frame
+        .downscale_to_fit(BLURRED_FILL_SIZE, BLURRED_FILL_SIZE, ScaleFilter::Bilinear)
+        .to_bgra();
+    let (width, height) = backdrop.size();
+    backdrop.blur_region(0, 0, width, height, BLURRED_FILL_RADIUS)?;
+    Ok(backdrop)
+}
+
+/// Crop the middle of `backdrop` to the aspect ratio of `size` once it's
+/// rotated by its display rotation, as [`ObjectFit::Cover`] would show it in
+/// bounds of that size.
+fn crop_to_cover(backdrop: &VideoFrame, size: Size<Pixels>) -> Result<VideoFrame, VideoFrameError> {
+    let swaps_dimensions = backdrop.display_rotation().swaps_dimensions();
+    let (width, height) = if swaps_dimensions {
+        (backdrop.height, backdrop.width)
+    } else {
+        (backdrop.width, backdrop.height)
+    };
+    let aspect_ratio = f32::from(size.width) / f32::from(size.height).max(f32::EPSILON);
+    let visible = |length: f32, max_length: u32| (length.round() as u32).clamp(1, max_length);
+    let (visible_width, visible_height) = (
+        visible(height as f32 * aspect_ratio, width),
+        visible(width as f32 / aspect_ratio, height),
+    );
+    let (crop_width, crop_height) = if swaps_dimensions {
+        (visible_height, visible_width)
+    } else {
+        (visible_width, visible_height)
+    };
+    backdrop.crop(
+        (backdrop.width - crop_width) / 2,
+        (backdrop.height - crop_height) / 2,
+        crop_width,
+        crop_height,
+    )
+}
+
+/// Round a length to whole pixels, keeping at least one.
+fn pixel_length(length: Pixels) -> u32 {
+    (f32::from(length).round() as u32).max(1)
+}
+
+/// Scale a packed frame as `object_fit` places it within a `width` by
+/// `height` canvas, cropping what falls outside, and center it on an
+/// otherwise transparent canvas.
+fn fit_to_canvas(
+    frame: &VideoFrame,
+    width: u32,
+    height: u32,
+    object_fit: ObjectFit,
+) -> Result<VideoFrame, VideoFrameError> {
+    let canvas = Bounds::new(
+        point(px(0.), px(0.)),
+        crate::size(px(width as f32), px(height as f32)),
+    );
+    let fitted =
+        object_fit.get_bounds(canvas, crate::size(frame.width.into(), frame.height.into()));
+    let (fitted_width, fitted_height) = (
+        pixel_length(fitted.size.width),
+        pixel_length(fitted.size.height),
+    );
+    let (visible_width, visible_height) = (fitted_width.min(width), fitted_height.min(height));
+    frame
+        .scale(fitted_width, fitted_height, ScaleFilter::Bilinear)?
+        .crop(
+            (fitted_width - visible_width) / 2,
+            (fitted_height - visible_height) / 2,
+            visible_width,
+            visible_height,
+        )?
+        .pad_to(width, height, [0; 4], Alignment::Center)
+}
+
+impl Element for Video {
+    type RequestLayoutState = ();
+    type PrepaintState = ();
+
+    fn id(&self) -> Option<ElementId> {
+        self.id.clone()
+    }
+
+    fn source_location(&self) -> Option<&'static core::panic::Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        let layout_id = window.request_layout(style, [], cx);
+        (layout_id, ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        _bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Self::PrepaintState {
+    }
+
+    fn paint(
+        &mut self,
+        global_id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        bounds: Bounds<Pixels>,
+        _: &mut Self::RequestLayoutState,
+        _: &mut Self::PrepaintState,
+        window: &mut Window,
+        _: &mut App,
+    ) {
+        let now = Instant::now();
+        window.with_optional_element_state::<VideoState, _>(global_id, |state, window| {
+            let mut state = state
+                .map(|state| state.unwrap_or_else(|| VideoState::new(self.frame.clone(), now)));
+            let (frame, previous) = match state.as_mut() {
+                Some(state) => {
+                    let elapsed = now.saturating_duration_since(state.last_paint);
+                    state.last_paint = now;
+                    if state.advance(self, elapsed) {
+                        window.request_animation_frame();
+                    }
+                    let previous = state
+                        .painted
+                        .take()
+                        .filter(|_| !self.paused && state.position.is_none());
+                    (state.shown.clone(), previous)
+                }
+                None => (self.frame.clone(), None),
+            };
+            match state.as_mut() {
+                Some(state) => {
+                    state.painted =
+                        self.paint_frame(&frame, previous, &mut state.cache, now, bounds, window);
+                }
+                None => {
+                    let mut cache = PaintCache::default();
+                    self.paint_frame(&frame, None, &mut cache, now, bounds, window);
+                }
+            }
+            ((), state)
+        });
+    }
+}
+
+impl Video {
+    /// Paint `frame` and its letterbox, updating the upload of `previous` in
+    /// place when a dirty rect is set. Returns the frame that was painted,
+    /// which may be a level of its mip chain.
+    fn paint_frame(
+        &self,
+        frame: &VideoFrame,
+        previous: Option<VideoFrame>,
+        cache: &mut PaintCache,
+        now: Instant,
+        bounds: Bounds<Pixels>,
+        window: &mut Window,
+    ) -> Option<VideoFrame> {
+        if frame.width == 0 || frame.height == 0 {
+            return None;
+        }
+        let (width, height) = if frame.display_rotation().swaps_dimensions() {
+            (frame.height, frame.width)
+        } else {
+            (frame.width, frame.height)
+        };
+        let size = crate::size(width.into(), height.into());
+        let frame_bounds = self.object_fit.get_bounds(bounds, size);
+        let mut style = Style::default();
+        style.refine(&self.style);
+        let corner_radii = style.corner_radii.to_pixels(window.rem_size());
+        let scale_factor = window.scale_factor();
+        let draw_width = (f32::from(frame_bounds.size.width) * scale_factor).ceil() as u32;
+        let draw_height = (f32::from(frame_bounds.size.height) * scale_factor).ceil() as u32;
+        let mip_level = if frame.display_rotation().swaps_dimensions() {
+            frame.mip_level_index_for(draw_height, draw_width)
+        } else {
+            frame.mip_level_index_for(draw_width, draw_height)
+        };
+        let painted = cache.mip_level(frame, mip_level);
+        let partial_upload = self.dirty_rect.zip(previous).map(|(dirty_rect, previous)| {
+            let (x, y, width, height) = mip_dirty_rect(dirty_rect, mip_level, painted.size());
+            PartialUpload {
+                previous,
+                x,
+                y,
+                width,
+                height,
+            }
+        });
+        window.with_content_mask(Some(ContentMask { bounds }), |window| {
+            if frame_bounds != bounds {
+                match self.letterbox {
+                    LetterboxStyle::Color(color) => {
+                        if !color.is_transparent() {
+                            window.paint_quad(fill(bounds, color).corner_radii(
+                                corner_radii.clone().clamp_radii_for_quad_size(bounds.size),
+                            ));
+                        }
+                    }
+                    LetterboxStyle::BlurredFill => {
+                        if let Some(backdrop) = cache.backdrop(frame, &painted, bounds.size, now) {
+                            window.paint_video_surface(
+                                bounds,
+                                corner_radii.clone().clamp_radii_for_quad_size(bounds.size),
+                                backdrop,
+                            );
+                        }
+                    }
+                }
+            }
+            let corner_radii = corner_radii.clamp_radii_for_quad_size(frame_bounds.size);
+            window.paint_video_surface_with_partial_upload(
+                frame_bounds,
+                corner_radii,
+                painted.clone(),
+                partial_upload,
+            );
+        });
+        Some(painted)
+    }
+}
+
+/// Scale a dirty rect of a frame down to level `mip_level` of its mip chain,
+/// which is `size` pixels, covering every pixel of the level that averages a
+/// pixel of the rect.
+fn mip_dirty_rect(
+    (x, y, width, height): (u32, u32, u32, u32),
+    mip_level: u32,
+    (level_width, level_height): (u32, u32),
+) -> (u32, u32, u32, u32) {
+    let scale = 1 << mip_level;
+    let (left, top) = (x / scale, y / scale);
+    let right = x.saturating_add(width).div_ceil(scale).min(level_width);
+    let bottom = y.saturating_add(height).div_ceil(scale).min(level_height);
+    (
+        left,
+        top,
+        right.saturating_sub(left),
+        bottom.saturating_sub(top),
+    )
+}
+
+/// What painting a [`Video`] derives from its frame, kept between the
+/// paints of an element with an id so that painting the same frame again
+/// doesn't derive it again.
+#[derive(Default)]
+struct PaintCache {
+    mip_level: Option<CachedMipLevel>,
+    backdrop: Option<CachedBackdrop>,
+}
+
+/// Identifies the frame that a cached paint was derived from: its pixels, and
+/// the alpha mode and display rotation that the derived frames carry over.
+#[derive(PartialEq)]
+struct PaintSource {
+    pixels: VideoCacheKey,
+    alpha_mode: AlphaMode,
+    display_rotation: Rotation,
+}
+
+impl PaintSource {
+    fn new(frame: &VideoFrame) -> Option<Self> {
+        Some(Self {
+            pixels: frame.cache_key()?,
+            alpha_mode: frame.alpha_mode(),
+            display_rotation: frame.display_rotation(),
+        })
+    }
+}
+
+/// A level of a frame's mip chain, with the frame it was downsampled from.
+struct CachedMipLevel {
+    source: PaintSource,
+    index: u32,
+    level: VideoFrame,
+}
+
+/// The [`LetterboxStyle::BlurredFill`] of a frame, cropped to the size of
+/// the element, or `None` if the frame couldn't be blurred.
+struct CachedBackdrop {
+    /// The frame that was blurred, if it can be told apart from later ones.
+    source: Option<PaintSource>,
+    size: Size<Pixels>,
+    created: Instant,
+    backdrop: Option<VideoFrame>,
+}
+
+impl PaintCache {
+    /// Get level `index` of `frame`'s mip chain, downsampling the frame only
+    /// if the level isn't cached.
+    fn mip_level(&mut self, frame: &VideoFrame, index: u32) -> VideoFrame {
+        if index == 0 {
+            return frame.clone();
+        }
+        let source = PaintSource::new(frame);
+        if let Some(cached) = &self.mip_level
+            && source.as_ref() == Some(&cached.source)
+            && cached.index == index
+        {
+            return cached.level.clone();
+        }
+        let level = frame.mip_level(index);
+        self.mip_level = source.map(|source| CachedMipLevel {
+            source,
+            index,
+            level: level.clone(),
+        });
+        level
+    }
+
+    /// Get the [`LetterboxStyle::BlurredFill`] of `frame` for an element of
+    /// `size`, blurring `painted`, the frame or a level of its mip chain,
+    /// only if the backdrop of `frame` isn't cached.
+    ///
+    /// Frames without a [`VideoCacheKey`] can't be told apart, so their
+    /// backdrop is blurred again once it's older than
+    /// [`BLURRED_FILL_REFRESH_INTERVAL`].
+    fn backdrop(
+        &mut self,
+        frame: &VideoFrame,
+        painted: &VideoFrame,
+        size: Size<Pixels>,
+        now: Instant,
+    ) -> Option<VideoFrame> {
+        let source = PaintSource::new(frame);
+        if let Some(cached) = &self.backdrop
+            && cached.size == size
+            && match (&source, &cached.source) {
+                (Some(source), Some(cached_source)) => source == cached_source,
+                (None, None) => {
+                    now.saturating_duration_since(cached.created) < BLURRED_FILL_REFRESH_INTERVAL
+                }
+                _ => false,
+            }
+        {
+            return cached.backdrop.clone();
+        }
+        let backdrop = blurred_backdrop(painted)
+            .and_then(|backdrop| crop_to_cover(&backdrop, size))
+            .log_err();
+        self.backdrop = Some(CachedBackdrop {
+            source,
+            size,
+            created: now,
+            backdrop: backdrop.clone(),
+        });
+        backdrop
+    }
+}
+
+/// What a [`Video`] with an id keeps between paints.
+struct VideoState {
+    /// The frame on screen.
+    shown: VideoFrame,
+    /// The frame painted for it, which may be a level of its mip chain.
+    painted: Option<VideoFrame>,
+    cache: PaintCache,
+    source: Option<Box<dyn Iterator<Item = VideoFrame>>>,
+    /// Frames pulled from the source that aren't due yet.
+    pacer: FramePacer,
+    /// The playback clock, in the time base of the frames' PTS, which starts
+    /// once the first frame is pulled from a source.
+    position: Option<Duration>,
+    last_paint: Instant,
+}
+
+impl VideoState {
+    fn new(frame: VideoFrame, now: Instant) -> Self {
+        Self {
+            shown: frame,
+            painted: None,
+            cache: PaintCache::default(),
+            source: None,
+            pacer: FramePacer::new(SOURCE_REORDER_CAPACITY, SOURCE_LATE_THRESHOLD),
+            position: None,
+            last_paint: now,
+        }
+    }
+
+    /// Advance playback by `elapsed` and pick the frame to show, returning
+    /// whether more frames may come from the source, so that the element
+    /// should paint again.
+    fn advance(&mut self, video: &mut Video, elapsed: Duration) -> bool {
+        if self.source.is_none()
+            && let Some(source) = video.source.take()
+        {
+            self.source = Some(source);
+            self.pacer.clear();
+            self.position = None;
+        }
+        if video.paused {
+            return false;
+        }
+        if self.source.is_none() && self.position.is_none() {
+            self.shown = video.frame.clone();
+            return false;
+        }
+
+        if let Some(position) = self.position.as_mut() {
+            *position = position.saturating_add(
+                Duration::try_from_secs_f32(elapsed.as_secs_f32() * video.playback_rate)
+                    .unwrap_or_default(),
+            );
+        }
+        if let Some(source) = self.source.as_mut() {
+            let mut exhausted = false;
+            while self.pacer.queued_frames() < SOURCE_REORDER_CAPACITY {
+                let Some(frame) = source.next() else {
+                    exhausted = true;
+                    break;
+                };
+                if let Some(pts) = frame.pts() {
+                    self.position.get_or_insert(pts);
+                }
+                self.pacer.push(frame).log_err();
+            }
+            if exhausted {
+                self.source = None;
+            }
+        }
+        if let Some(position) = self.position {
+            while let Some(frame) = self.pacer.pace(position) {
+                self.shown = frame;
+            }
+        }
+        self.source.is_some() || self.pacer.queued_frames() > 0
+    }
+}
+
+impl IntoElement for Video {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Styled for Video {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PixelFormat, VideoColorSpace, black, size};
+
+    #[test]
+    fn test_render_to_bgra() {
+        let red = [0, 0, 255, 255];
+        let bounds = Bounds::new(point(px(10.), px(20.)), size(px(4.), px(4.)));
+        let rendered = video(VideoFrame::solid_color(4, 2, red))
+            .letterbox(LetterboxStyle::Color(black()))
+            .render_to_bgra(bounds);
+        assert_eq!(rendered.size(), (4, 4));
+        let black = [0, 0, 0, 255];
+        let rows: Vec<_> = rendered.rows().unwrap().map(<[u8]>::to_vec).collect();
+        assert_eq!(
+            rows,
+            [
+                black.repeat(4),
+                red.repeat(4),
+                red.repeat(4),
+                black.repeat(4)
+            ]
+        );
+
+        let covered = video(VideoFrame::solid_color(4, 2, red))
+            .object_fit(ObjectFit::Cover)
+            .letterbox(LetterboxStyle::Color(black()))
+            .render_to_bgra(bounds);
+        assert_eq!(covered.as_bytes().unwrap(), red.repeat(16));
+
+        let rotated =
+            video(VideoFrame::solid_color(4, 2, red).with_display_rotation(Rotation::Cw90))
+                .render_to_bgra(bounds);
+        let transparent = [0; 4];
+        let rows: Vec<_> = rotated.rows().unwrap().map(<[u8]>::to_vec).collect();
+        let row = [transparent, red, red, transparent].concat();
+        assert_eq!(rows, [row.clone(), row.clone(), row.clone(), row]);
+    }
+
+    #[test]
+    fn test_playback() {
+        let frame_at = |milliseconds: u64| {
+            VideoFrame::solid_color(1, 1, [0, 0, 0, 255])
+                .with_pts(Duration::from_millis(milliseconds))
+        };
+        let shown_pts = |state: &VideoState| state.shown.pts().map(|pts| pts.as_millis() as u64);
+        let poster = VideoFrame::solid_color(1, 1, [0, 0, 0, 255]);
+
+        let mut element = video(poster.clone())
+            .id("video")
+            .source([0, 80, 40, 120].into_iter().map(frame_at))
+            .playback_rate(0.5);
+        let mut state = VideoState::new(poster.clone(), Instant::now());
+        assert!(state.advance(&mut element, Duration::ZERO));
+        assert_eq!(shown_pts(&state), Some(0));
+        assert!(state.advance(&mut element, Duration::from_millis(40)));
+        assert_eq!(shown_pts(&state), Some(0));
+        assert!(state.advance(&mut element, Duration::from_millis(40)));
+        assert_eq!(shown_pts(&state), Some(40));
+
+        element.paused = true;
+        assert!(!state.advance(&mut element, Duration::from_secs(1)));
+        assert_eq!(shown_pts(&state), Some(40));
+        element.paused = false;
+        assert!(!state.advance(&mut element, Duration::from_millis(160)));
+        assert_eq!(shown_pts(&state), Some(120));
+
+        let mut state = VideoState::new(poster, Instant::now());
+        state.advance(&mut video(frame_at(1)), Duration::ZERO);
+        assert_eq!(shown_pts(&state), Some(1));
+        state.advance(&mut video(frame_at(2)).paused(true), Duration::ZERO);
+        assert_eq!(shown_pts(&state), Some(1));
+    }
+
+    #[test]
+    fn test_mip_level_is_cached() {
+        let frame = VideoFrame::solid_color(8, 8, [0, 0, 255, 255]);
+        let mut cache = PaintCache::default();
+        let level = cache.mip_level(&frame, 2);
+        assert_eq!(level.size(), (2, 2));
+        assert!(cache.mip_level(&frame, 2).cache_key() == level.cache_key());
+        assert!(cache.mip_level(&frame, 1).cache_key() != level.cache_key());
+        assert!(cache.mip_level(&frame, 0).cache_key() == frame.cache_key());
+
+        let other = VideoFrame::solid_color(8, 8, [0, 0, 255, 255]);
+        assert!(cache.mip_level(&other, 1).cache_key() != cache.mip_level(&frame, 1).cache_key());
+
+        assert_eq!(mip_dirty_rect((3, 0, 2, 8), 0, (8, 8)), (3, 0, 2, 8));
+        assert_eq!(mip_dirty_rect((3, 0, 2, 8), 1, (4, 4)), (1, 0, 2, 4));
+        assert_eq!(mip_dirty_rect((5, 5, 9, 9), 2, (2, 2)), (1, 1, 1, 1));
+    }
+
+    #[test]
+    fn test_backdrop_is_blurred_once_per_frame() {
+        let frame = VideoFrame::from_nv12(
+            vec![0; 16],
+            vec![128; 8],
+            4,
+            4,
+            4,
+            4,
+            VideoColorSpace::Bt601,
+        );
+        let mut cache = PaintCache::default();
+        let now = Instant::now();
+        let element_size = size(px(8.), px(4.));
+        let backdrop = cache
+            .backdrop(&frame, &frame, element_size, now)
+            .expect("small planar frames are converted before blurring");
+        assert_eq!(backdrop.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(backdrop.size(), (4, 2));
+        let later = now + Duration::from_secs(1);
+        let cached = cache.backdrop(&frame, &frame, element_size, later).unwrap();
+        assert!(cached.cache_key() == backdrop.cache_key());
+
+        let rotated = frame.clone().with_display_rotation(Rotation::Cw90);
+        let backdrop = cache
+            .backdrop(&rotated, &rotated, element_size, now)
+            .unwrap();
+        assert_eq!(backdrop.size(), (2, 4));
+    }
+}
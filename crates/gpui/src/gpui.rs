@@ -113,7 +113,7 @@ pub use text_system::*;
 #[cfg(any(test, feature = "test-support"))]
 pub use util::smol_timeout;
 pub use util::{FutureExt, Timeout, arc_cow::ArcCow};
-pub use video::VideoFrame;
+pub use video::*;
 pub use view::*;
 pub use window::*;
 
@@ -514,6 +514,11 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn on_close(&self, callback: Box<dyn FnOnce()>);
     fn on_appearance_changed(&self, callback: Box<dyn FnMut()>);
     fn draw(&self, scene: &Scene);
+    fn capture_frame(&self, _scene: &Scene) -> anyhow::Result<crate::VideoFrame> {
+        Err(anyhow::anyhow!(
+            "capturing window contents isn't supported on this platform"
+        ))
+    }
     fn completed_frame(&self) {}
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas>;
 
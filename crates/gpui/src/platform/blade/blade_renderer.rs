@@ -3,9 +3,10 @@
 
 use super::{BladeAtlas, BladeContext};
 use crate::{
-    Background, Bounds, DevicePixels, GpuSpecs, MonochromeSprite, Path, Point, PolychromeSprite,
-    PrimitiveBatch, Quad, ScaledPixels, Scene, Shadow, Size, Underline,
+    Background, Bounds, Corners, DevicePixels, GpuSpecs, MonochromeSprite, Path, Point,
+    PolychromeSprite, PrimitiveBatch, Quad, ScaledPixels, Scene, Shadow, Size, Underline,
     get_gamma_correction_ratios,
+    video::{SurfacePixels, SurfaceUpload, VideoCache, VideoStat, record_stat},
 };
 use blade_graphics as gpu;
 use blade_util::{BufferBelt, BufferBeltDescriptor};
@@ -42,11 +43,32 @@ impl From<Bounds<ScaledPixels>> for PodBounds {
     }
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct PodCorners {
+    top_left: f32,
+    top_right: f32,
+    bottom_right: f32,
+    bottom_left: f32,
+}
+
+impl From<Corners<ScaledPixels>> for PodCorners {
+    fn from(corners: Corners<ScaledPixels>) -> Self {
+        Self {
+            top_left: corners.top_left.0,
+            top_right: corners.top_right.0,
+            bottom_right: corners.bottom_right.0,
+            bottom_left: corners.bottom_left.0,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct SurfaceParams {
     bounds: PodBounds,
     content_mask: PodBounds,
+    corner_radii: PodCorners,
 }
 
 #[derive(blade_macros::ShaderData)]
@@ -103,6 +125,8 @@ struct ShaderPolySpritesData {
 struct ShaderSurfacesData {
     globals: GlobalParams,
     surface_locals: SurfaceParams,
+    surface_rotation: u32,
+    surface_limited_range: u32,
     t_y: gpu::TextureView,
     t_cb_cr: gpu::TextureView,
     s_surface: gpu::Sampler,
@@ -112,6 +136,8 @@ struct ShaderSurfacesData {
 struct ShaderSurfacesBgraData {
     globals: GlobalParams,
     surface_bgra_locals: SurfaceParams,
+    surface_rotation: u32,
+    surface_alpha_mode: u32,
     t_bgra: gpu::TextureView,
     s_bgra: gpu::Sampler,
 }
@@ -345,12 +371,14 @@ pub struct BladeSurfaceConfig {
     pub transparent: bool,
 }
 
-/// Cache for video surface textures to avoid recreation on every frame.
-struct VideoTextureCache {
+/// The maximum number of bytes of video frames to keep uploaded.
+const VIDEO_TEXTURE_CACHE_CAPACITY: usize = 256 * 1024 * 1024;
+
+/// The texture a CPU video frame was uploaded to.
+#[derive(Clone, Copy)]
+struct VideoTexture {
     texture: gpu::Texture,
     texture_view: gpu::TextureView,
-    width: u32,
-    height: u32,
 }
 
 //Note: we could see some of these fields moved into `BladeContext`
@@ -374,8 +402,8 @@ pub struct BladeRenderer {
     path_intermediate_msaa_texture: Option<gpu::Texture>,
     path_intermediate_msaa_texture_view: Option<gpu::TextureView>,
     rendering_parameters: RenderingParameters,
-    /// Cache for video surface textures (BGRA path)
-    video_texture_cache: Option<VideoTextureCache>,
+    /// Textures of recently painted CPU video frames (BGRA path)
+    video_texture_cache: VideoCache<VideoTexture>,
 }
 
 impl BladeRenderer {
@@ -462,7 +490,7 @@ impl BladeRenderer {
             path_intermediate_msaa_texture,
             path_intermediate_msaa_texture_view,
             rendering_parameters,
-            video_texture_cache: None,
+            video_texture_cache: VideoCache::new(VIDEO_TEXTURE_CACHE_CAPACITY),
         })
     }
 
@@ -674,6 +702,18 @@ impl BladeRenderer {
         if let Some(msaa_view) = self.path_intermediate_msaa_texture_view {
             self.gpu.destroy_texture_view(msaa_view);
         }
+        self.destroy_video_textures(self.video_texture_cache.clear());
+    }
+
+    fn destroy_video_textures(&self, textures: Vec<VideoTexture>) {
+        for VideoTexture {
+            texture,
+            texture_view,
+        } in textures
+        {
+            self.gpu.destroy_texture_view(texture_view);
+            self.gpu.destroy_texture(texture);
+        }
     }
 
     pub fn draw(&mut self, scene: &Scene) {
@@ -685,6 +725,99 @@ impl BladeRenderer {
             self.surface.acquire_frame()
         };
         self.command_encoder.init_texture(frame.texture());
+        let evicted_video_textures = self.encode_scene(scene, frame.texture_view());
+        self.command_encoder.present(frame);
+        self.submit(evicted_video_textures);
+    }
+
+    /// Render `scene` into an offscreen texture the size of the surface and
+    /// read it back as a BGRA frame, for capturing the window's contents.
+    ///
+    /// This blocks until the GPU has finished rendering.
+    pub fn capture(&mut self, scene: &Scene) -> anyhow::Result<crate::VideoFrame> {
+        let gpu::Extent { width, height, .. } = self.surface_config.size;
+        anyhow::ensure!(width > 0 && height > 0, "window has no drawable area");
+        let format = self.surface.info().format;
+        let swap_red_and_blue = match format {
+            gpu::TextureFormat::Bgra8Unorm | gpu::TextureFormat::Bgra8UnormSrgb => false,
+            gpu::TextureFormat::Rgba8Unorm | gpu::TextureFormat::Rgba8UnormSrgb => true,
+            _ => anyhow::bail!("can't capture a surface in the {format:?} format"),
+        };
+
+        let texture = self.gpu.create_texture(gpu::TextureDesc {
+            name: "capture",
+            format,
+            size: gpu::Extent {
+                width,
+                height,
+                depth: 1,
+            },
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: gpu::TextureDimension::D2,
+            usage: gpu::TextureUsage::TARGET | gpu::TextureUsage::COPY,
+            external: None,
+        });
+        let texture_view = self.gpu.create_texture_view(
+            texture,
+            gpu::TextureViewDesc {
+                name: "capture view",
+                format,
+                dimension: gpu::ViewDimension::D2,
+                subresources: &Default::default(),
+            },
+        );
+        let bytes_per_row = width * 4;
+        let buffer = self.gpu.create_buffer(gpu::BufferDesc {
+            name: "capture readback",
+            size: bytes_per_row as u64 * height as u64,
+            memory: gpu::Memory::Shared,
+        });
+
+        self.command_encoder.start();
+        self.atlas.before_frame(&mut self.command_encoder);
+        self.command_encoder.init_texture(texture);
+        let evicted_video_textures = self.encode_scene(scene, texture_view);
+        {
+            let mut transfer = self.command_encoder.transfer("capture readback");
+            transfer.copy_texture_to_buffer(
+                gpu::TexturePiece {
+                    texture,
+                    mip_level: 0,
+                    array_layer: 0,
+                    origin: [0, 0, 0],
+                },
+                buffer.into(),
+                bytes_per_row,
+                gpu::Extent {
+                    width,
+                    height,
+                    depth: 1,
+                },
+            );
+        }
+        self.submit(evicted_video_textures);
+        self.wait_for_gpu();
+
+        let len = bytes_per_row as usize * height as usize;
+        let pixels = unsafe { std::slice::from_raw_parts(buffer.data(), len) }.to_vec();
+        self.gpu.destroy_buffer(buffer);
+        self.gpu.destroy_texture_view(texture_view);
+        self.gpu.destroy_texture(texture);
+
+        let frame = if swap_red_and_blue {
+            crate::VideoFrame::try_from_rgba(pixels, width, height)?.swap_rb()
+        } else {
+            crate::VideoFrame::try_from_bgra(pixels, width, height)?
+        };
+        Ok(frame)
+    }
+
+    /// Record the passes that render `scene` into `target`, returning the
+    /// video textures evicted from the cache to destroy once they're done.
+    fn encode_scene(&mut self, scene: &Scene, target: gpu::TextureView) -> Vec<VideoTexture> {
+        let mut evicted_video_textures = self.video_texture_cache.remove_stale();
 
         let globals = GlobalParams {
             viewport_size: [
@@ -702,7 +835,7 @@ impl BladeRenderer {
             "main",
             gpu::RenderTargetSet {
                 colors: &[gpu::RenderTarget {
-                    view: frame.texture_view(),
+                    view: target,
                     init_op: gpu::InitOp::Clear(gpu::TextureColor::TransparentBlack),
                     finish_op: gpu::FinishOp::Store,
                 }],
@@ -752,7 +885,7 @@ impl BladeRenderer {
                         "main",
                         gpu::RenderTargetSet {
                             colors: &[gpu::RenderTarget {
-                                view: frame.texture_view(),
+                                view: target,
                                 init_op: gpu::InitOp::Load,
                                 finish_op: gpu::FinishOp::Store,
                             }],
@@ -853,17 +986,19 @@ impl BladeRenderer {
                     for surface in surfaces {
                         match &surface.frame_data {
                             #[cfg(target_os = "macos")]
-                            crate::PaintSurfaceData::CoreVideo(image_buffer) => {
+                            crate::PaintSurfaceData::CoreVideo { buffer: image_buffer, color_range } => {
+                                let pixel_format = image_buffer.get_pixel_format();
+                                if pixel_format != core_video::pixel_buffer::kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
+                                    && pixel_format != core_video::pixel_buffer::kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange
+                                {
+                                    log::warn!("unsupported CoreVideo pixel format for surface: {pixel_format:#x}");
+                                    continue;
+                                }
                                 let mut encoder = pass.with(&self.pipelines.surfaces);
                                 let (t_y, t_cb_cr) = unsafe {
                                     use core_foundation::base::TCFType as _;
                                     use std::ptr;
 
-                                    assert_eq!(
-                                        image_buffer.get_pixel_format(),
-                                        core_video::pixel_buffer::kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
-                                    );
-
                                     let y_texture = self
                                         .core_video_texture_cache
                                         .create_texture_from_image(
@@ -921,7 +1056,10 @@ impl BladeRenderer {
                                         surface_locals: SurfaceParams {
                                             bounds: surface.bounds.into(),
                                             content_mask: surface.content_mask.bounds.into(),
+                                            corner_radii: surface.corner_radii.clone().into(),
                                         },
+                                        surface_rotation: surface.rotation as u32,
+                                        surface_limited_range: u32::from(*color_range == crate::ColorRange::Limited),
                                         t_y,
                                         t_cb_cr,
                                         s_surface: self.atlas_sampler,
@@ -930,107 +1068,141 @@ impl BladeRenderer {
 
                                 encoder.draw(0, 4, 0, 1);
                             }
-                            crate::PaintSurfaceData::Bgra { buffer, width, height } => {
-                                let width = *width;
-                                let height = *height;
-
-                                if width == 0 || height == 0 || buffer.is_empty() {
+                            crate::PaintSurfaceData::Cpu { frame: video_frame, partial_upload } => {
+                                let (width, height) = video_frame.size();
+                                if width == 0 || height == 0 {
                                     continue;
                                 }
 
-                                // Check if we need to recreate the texture (size changed or doesn't exist)
-                                let needs_new_texture = match &self.video_texture_cache {
-                                    Some(cache) => cache.width != width || cache.height != height,
-                                    None => true,
-                                };
-
-                                if needs_new_texture {
-                                    // Destroy old texture if it exists
-                                    if let Some(old_cache) = self.video_texture_cache.take() {
-                                        self.gpu.destroy_texture_view(old_cache.texture_view);
-                                        self.gpu.destroy_texture(old_cache.texture);
-                                    }
-
-                                    // Create new texture for BGRA data
-                                    // Use Bgra8Unorm (not Srgb) - video frames are already in correct color space
-                                    // and don't need sRGB gamma decoding (which would make them too dark)
-                                    let texture = self.gpu.create_texture(gpu::TextureDesc {
-                                        name: "video surface bgra",
-                                        format: gpu::TextureFormat::Bgra8Unorm,
-                                        size: gpu::Extent {
-                                            width,
-                                            height,
-                                            depth: 1,
-                                        },
-                                        array_layer_count: 1,
-                                        mip_level_count: 1,
-                                        sample_count: 1,
-                                        dimension: gpu::TextureDimension::D2,
-                                        usage: gpu::TextureUsage::COPY | gpu::TextureUsage::RESOURCE,
-                                        external: None,
-                                    });
-                                    let texture_view = self.gpu.create_texture_view(
-                                        texture,
-                                        gpu::TextureViewDesc {
-                                            name: "video surface bgra view",
-                                            format: gpu::TextureFormat::Bgra8Unorm,
-                                            dimension: gpu::ViewDimension::D2,
-                                            subresources: &Default::default(),
-                                        },
-                                    );
-
-                                    self.video_texture_cache = Some(VideoTextureCache {
-                                        texture,
-                                        texture_view,
-                                        width,
-                                        height,
-                                    });
-                                }
-
-                                // Get texture info for rendering
-                                let (texture, texture_view) = match &self.video_texture_cache {
-                                    Some(cache) => (cache.texture, cache.texture_view),
+                                // Reuse the texture of a frame that has already been uploaded,
+                                // converting the frame only when it hasn't been
+                                let texture_view = match self
+                                    .video_texture_cache
+                                    .prepare_upload(video_frame, partial_upload.as_ref())
+                                {
                                     None => continue,
+                                    Some(SurfaceUpload::Cached(cached)) => cached.texture_view,
+                                    Some(SurfaceUpload::Upload { key, pixels, update }) => {
+                                        let SurfacePixels { buffer, width, height, stride, format } = pixels;
+                                        // Update the texture of the frame painted in this one's place
+                                        // when only part of it changed
+                                        let (video_texture, region) = match update {
+                                            Some((cached, upload)) => (cached, Some(upload)),
+                                            None => {
+                                                let texture_format = match format {
+                                                    crate::PixelFormat::Rgba8 => gpu::TextureFormat::Rgba8Unorm,
+                                                    crate::PixelFormat::Rgba16Float => gpu::TextureFormat::Rgba16Float,
+                                                    _ => gpu::TextureFormat::Bgra8Unorm,
+                                                };
+
+                                                // Use Bgra8Unorm (not Srgb) - video frames are already in correct color space
+                                                // and don't need sRGB gamma decoding (which would make them too dark)
+                                                let texture = self.gpu.create_texture(gpu::TextureDesc {
+                                                    name: "video surface bgra",
+                                                    format: texture_format,
+                                                    size: gpu::Extent {
+                                                        width,
+                                                        height,
+                                                        depth: 1,
+                                                    },
+                                                    array_layer_count: 1,
+                                                    mip_level_count: 1,
+                                                    sample_count: 1,
+                                                    dimension: gpu::TextureDimension::D2,
+                                                    usage: gpu::TextureUsage::COPY | gpu::TextureUsage::RESOURCE,
+                                                    external: None,
+                                                });
+                                                let texture_view = self.gpu.create_texture_view(
+                                                    texture,
+                                                    gpu::TextureViewDesc {
+                                                        name: "video surface bgra view",
+                                                        format: texture_format,
+                                                        dimension: gpu::ViewDimension::D2,
+                                                        subresources: &Default::default(),
+                                                    },
+                                                );
+                                                self.command_encoder.init_texture(texture);
+                                                (VideoTexture { texture, texture_view }, None)
+                                            }
+                                        };
+                                        let VideoTexture { texture, texture_view } = video_texture;
+
+                                        // Drop the render pass to do texture upload
+                                        drop(pass);
+
+                                        // Upload pixel data to the texture via transfer pass
+                                        {
+                                            let mut transfer = self.command_encoder.transfer("video upload");
+                                            let (pixels, origin, extent) = match &region {
+                                                Some(upload) => {
+                                                    let bytes_per_pixel = match format {
+                                                        crate::PixelFormat::Rgba16Float => 8,
+                                                        _ => 4,
+                                                    };
+                                                    let stride = stride as usize;
+                                                    let start = upload.y as usize * stride + upload.x as usize * bytes_per_pixel;
+                                                    let end = (upload.y + upload.height - 1) as usize * stride
+                                                        + (upload.x + upload.width) as usize * bytes_per_pixel;
+                                                    (
+                                                        &buffer[start..end],
+                                                        [upload.x, upload.y, 0],
+                                                        gpu::Extent {
+                                                            width: upload.width,
+                                                            height: upload.height,
+                                                            depth: 1,
+                                                        },
+                                                    )
+                                                }
+                                                None => (
+                                                    buffer.as_slice(),
+                                                    [0, 0, 0],
+                                                    gpu::Extent {
+                                                        width,
+                                                        height,
+                                                        depth: 1,
+                                                    },
+                                                ),
+                                            };
+                                            let staging_buffer = self.instance_belt.alloc_bytes(pixels, &self.gpu);
+                                            transfer.copy_buffer_to_texture(
+                                                staging_buffer,
+                                                stride, // bytes per row
+                                                gpu::TexturePiece {
+                                                    texture,
+                                                    mip_level: 0,
+                                                    array_layer: 0,
+                                                    origin,
+                                                },
+                                                extent,
+                                            );
+                                        }
+                                        record_stat(VideoStat::TextureUpload);
+
+                                        // Recreate render pass
+                                        pass = self.command_encoder.render(
+                                            "main",
+                                            gpu::RenderTargetSet {
+                                                colors: &[gpu::RenderTarget {
+                                                    view: target,
+                                                    init_op: gpu::InitOp::Load,
+                                                    finish_op: gpu::FinishOp::Store,
+                                                }],
+                                                depth_stencil: None,
+                                            },
+                                        );
+
+                                        // Textures evicted here may still be used by this frame
+                                        evicted_video_textures.extend(self.video_texture_cache.insert(
+                                            key,
+                                            VideoTexture {
+                                                texture,
+                                                texture_view,
+                                            },
+                                            buffer.len(),
+                                        ));
+                                        texture_view
+                                    }
                                 };
-
-                                // Drop the render pass to do texture upload
-                                drop(pass);
-
-                                // Upload pixel data to the texture via transfer pass
-                                {
-                                    self.command_encoder.init_texture(texture);
-                                    let mut transfer = self.command_encoder.transfer("video upload");
-                                    let staging_buffer = self.instance_belt.alloc_bytes(buffer.as_slice(), &self.gpu);
-                                    transfer.copy_buffer_to_texture(
-                                        staging_buffer,
-                                        width * 4, // bytes per row
-                                        gpu::TexturePiece {
-                                            texture,
-                                            mip_level: 0,
-                                            array_layer: 0,
-                                            origin: [0, 0, 0],
-                                        },
-                                        gpu::Extent {
-                                            width,
-                                            height,
-                                            depth: 1,
-                                        },
-                                    );
-                                }
-
-                                // Recreate render pass and draw
-                                pass = self.command_encoder.render(
-                                    "main",
-                                    gpu::RenderTargetSet {
-                                        colors: &[gpu::RenderTarget {
-                                            view: frame.texture_view(),
-                                            init_op: gpu::InitOp::Load,
-                                            finish_op: gpu::FinishOp::Store,
-                                        }],
-                                        depth_stencil: None,
-                                    },
-                                );
-
                                 let mut encoder = pass.with(&self.pipelines.surfaces_bgra);
                                 encoder.bind(
                                     0,
@@ -1039,7 +1211,10 @@ impl BladeRenderer {
                                         surface_bgra_locals: SurfaceParams {
                                             bounds: surface.bounds.into(),
                                             content_mask: surface.content_mask.bounds.into(),
+                                            corner_radii: surface.corner_radii.clone().into(),
                                         },
+                                        surface_rotation: surface.rotation as u32,
+                                        surface_alpha_mode: surface.alpha_mode as u32,
                                         t_bgra: texture_view,
                                         s_bgra: self.atlas_sampler,
                                     },
@@ -1058,8 +1233,11 @@ impl BladeRenderer {
             }
         }
         drop(pass);
+        evicted_video_textures
+    }
 
-        self.command_encoder.present(frame);
+    /// Submit the recorded passes, once the previous submission has finished.
+    fn submit(&mut self, evicted_video_textures: Vec<VideoTexture>) {
         let sync_point = self.gpu.submit(&mut self.command_encoder);
 
         profiling::scope!("finish");
@@ -1068,6 +1246,7 @@ impl BladeRenderer {
 
         self.wait_for_gpu();
         self.last_sync_point = Some(sync_point);
+        self.destroy_video_textures(evicted_video_textures);
     }
 }
 
@@ -1252,9 +1252,12 @@ fn fs_poly_sprite(input: PolySpriteVarying) -> @location(0) vec4<f32> {
 struct SurfaceParams {
     bounds: Bounds,
     content_mask: Bounds,
+    corner_radii: Corners,
 }
 
 var<uniform> surface_locals: SurfaceParams;
+var<uniform> surface_rotation: u32;
+var<uniform> surface_limited_range: u32;
 var t_y: texture_2d<f32>;
 var t_cb_cr: texture_2d<f32>;
 var s_surface: sampler;
@@ -1272,13 +1275,24 @@ struct SurfaceVarying {
     @location(3) clip_distances: vec4<f32>,
 }
 
+// Map a position within the surface's bounds to the texture, rotating the
+// texture clockwise by `rotation` quarter turns.
+fn rotate_texture_position(unit_vertex: vec2<f32>, rotation: u32) -> vec2<f32> {
+    switch (rotation) {
+        case 1u: { return vec2<f32>(unit_vertex.y, 1.0 - unit_vertex.x); }
+        case 2u: { return vec2<f32>(1.0 - unit_vertex.x, 1.0 - unit_vertex.y); }
+        case 3u: { return vec2<f32>(1.0 - unit_vertex.y, unit_vertex.x); }
+        default: { return unit_vertex; }
+    }
+}
+
 @vertex
 fn vs_surface(@builtin(vertex_index) vertex_id: u32) -> SurfaceVarying {
     let unit_vertex = vec2<f32>(f32(vertex_id & 1u), 0.5 * f32(vertex_id & 2u));
 
     var out = SurfaceVarying();
     out.position = to_device_position(unit_vertex, surface_locals.bounds);
-    out.texture_position = unit_vertex;
+    out.texture_position = rotate_texture_position(unit_vertex, surface_rotation);
     out.clip_distances = distance_from_clip_rect(unit_vertex, surface_locals.bounds, surface_locals.content_mask);
     return out;
 }
@@ -1290,17 +1304,27 @@ fn fs_surface(input: SurfaceVarying) -> @location(0) vec4<f32> {
         return vec4<f32>(0.0);
     }
 
-    let y_cb_cr = vec4<f32>(
+    var y_cb_cr = vec4<f32>(
         textureSampleLevel(t_y, s_surface, input.texture_position, 0.0).r,
         textureSampleLevel(t_cb_cr, s_surface, input.texture_position, 0.0).rg,
         1.0);
+    if (surface_limited_range != 0u) {
+        // Expand luma from 16-235 and chroma from 16-240 to the full range.
+        y_cb_cr = vec4<f32>(
+            (y_cb_cr.x - 16.0 / 255.0) * (255.0 / 219.0),
+            (y_cb_cr.yz - 128.0 / 255.0) * (255.0 / 224.0) + 0.5,
+            1.0);
+    }
 
-    return ycbcr_to_RGB * y_cb_cr;
+    let distance = quad_sdf(input.position.xy, surface_locals.bounds, surface_locals.corner_radii);
+    return blend_color(ycbcr_to_RGB * y_cb_cr, saturate(0.5 - distance));
 }
 
 // --- surfaces (BGRA - Linux/cross-platform) --- //
 
 var<uniform> surface_bgra_locals: SurfaceParams;
+// How the texture's alpha is stored: 0 opaque, 1 straight, 2 premultiplied.
+var<uniform> surface_alpha_mode: u32;
 var t_bgra: texture_2d<f32>;
 var s_bgra: sampler;
 
@@ -1310,7 +1334,7 @@ fn vs_surface_bgra(@builtin(vertex_index) vertex_id: u32) -> SurfaceVarying {
 
     var out = SurfaceVarying();
     out.position = to_device_position(unit_vertex, surface_bgra_locals.bounds);
-    out.texture_position = unit_vertex;
+    out.texture_position = rotate_texture_position(unit_vertex, surface_rotation);
     out.clip_distances = distance_from_clip_rect(unit_vertex, surface_bgra_locals.bounds, surface_bgra_locals.content_mask);
     return out;
 }
@@ -1324,6 +1348,12 @@ fn fs_surface_bgra(input: SurfaceVarying) -> @location(0) vec4<f32> {
 
     // Sample BGRA texture and swizzle to RGBA
     // Using Bgra8Unorm (not Srgb) so we need manual swizzle
-    let sample = textureSampleLevel(t_bgra, s_bgra, input.texture_position, 0.0);
-    return vec4<f32>(sample.b, sample.g, sample.r, sample.a);
+    var sample = textureSampleLevel(t_bgra, s_bgra, input.texture_position, 0.0);
+    if (surface_alpha_mode == 0u) {
+        sample.a = 1.0;
+    } else if (surface_alpha_mode == 2u && sample.a > 0.0) {
+        sample = vec4<f32>(sample.rgb / sample.a, sample.a);
+    }
+    let distance = quad_sdf(input.position.xy, surface_bgra_locals.bounds, surface_bgra_locals.corner_radii);
+    return blend_color(vec4<f32>(sample.b, sample.g, sample.r, sample.a), saturate(0.5 - distance));
 }
@@ -1296,6 +1296,10 @@ impl PlatformWindow for WaylandWindow {
         state.renderer.draw(scene);
     }
 
+    fn capture_frame(&self, scene: &Scene) -> anyhow::Result<crate::VideoFrame> {
+        self.borrow_mut().renderer.capture(scene)
+    }
+
     fn completed_frame(&self) {
         let state = self.borrow();
         state.surface.commit();
@@ -1550,6 +1550,10 @@ impl PlatformWindow for X11Window {
         inner.renderer.draw(scene);
     }
 
+    fn capture_frame(&self, scene: &Scene) -> anyhow::Result<crate::VideoFrame> {
+        self.0.state.borrow_mut().renderer.capture(scene)
+    }
+
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
         let inner = self.0.state.borrow();
         inner.renderer.sprite_atlas().clone()
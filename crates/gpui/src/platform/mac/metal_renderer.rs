@@ -1,8 +1,9 @@
 use super::metal_atlas::MetalAtlas;
 use crate::{
-    AtlasTextureId, Background, Bounds, ContentMask, DevicePixels, MonochromeSprite, PaintSurface,
-    PaintSurfaceData, Path, Point, PolychromeSprite, PrimitiveBatch, Quad, ScaledPixels, Scene,
-    Shadow, Size, Surface, Underline, point, size,
+    AtlasTextureId, Background, Bounds, ColorRange, ContentMask, Corners, DevicePixels,
+    MonochromeSprite, PaintSurface, PaintSurfaceData, Path, Point, PolychromeSprite,
+    PrimitiveBatch, Quad, ScaledPixels, Scene, Shadow, Size, Underline, VideoFrame, point, size,
+    video::{SurfacePixels, SurfaceUpload, VideoCache, VideoStat, record_stat},
 };
 use anyhow::Result;
 use block::ConcreteBlock;
@@ -14,8 +15,12 @@ use cocoa::{
 
 use core_foundation::base::TCFType;
 use core_video::{
-    metal_texture::CVMetalTextureGetTexture, metal_texture_cache::CVMetalTextureCache,
-    pixel_buffer::kCVPixelFormatType_420YpCbCr8BiPlanarFullRange,
+    metal_texture::CVMetalTextureGetTexture,
+    metal_texture_cache::CVMetalTextureCache,
+    pixel_buffer::{
+        kCVPixelFormatType_420YpCbCr8BiPlanarFullRange,
+        kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange,
+    },
 };
 use foreign_types::{ForeignType, ForeignTypeRef};
 use metal::{
@@ -27,6 +32,9 @@ use parking_lot::Mutex;
 
 use std::{cell::Cell, ffi::c_void, mem, ptr, sync::Arc};
 
+/// The most GPU memory the uploads of CPU video frames are allowed to take.
+const VIDEO_TEXTURE_CACHE_CAPACITY: usize = 256 * 1024 * 1024;
+
 // Exported to metal
 pub(crate) type PointF = crate::Point<f32>;
 
@@ -109,6 +117,7 @@ pub(crate) struct MetalRenderer {
     monochrome_sprites_pipeline_state: metal::RenderPipelineState,
     polychrome_sprites_pipeline_state: metal::RenderPipelineState,
     surfaces_pipeline_state: metal::RenderPipelineState,
+    surfaces_bgra_pipeline_state: metal::RenderPipelineState,
     unit_vertices: metal::Buffer,
     #[allow(clippy::arc_with_non_send_sync)]
     instance_buffer_pool: Arc<Mutex<InstanceBufferPool>>,
@@ -117,6 +126,7 @@ pub(crate) struct MetalRenderer {
     path_intermediate_texture: Option<metal::Texture>,
     path_intermediate_msaa_texture: Option<metal::Texture>,
     path_sample_count: u32,
+    video_texture_cache: VideoCache<metal::Texture>,
 }
 
 #[repr(C)]
@@ -260,6 +270,14 @@ impl MetalRenderer {
             "surface_fragment",
             MTLPixelFormat::BGRA8Unorm,
         );
+        let surfaces_bgra_pipeline_state = build_pipeline_state(
+            &device,
+            &library,
+            "surfaces_bgra",
+            "surface_vertex",
+            "surface_bgra_fragment",
+            MTLPixelFormat::BGRA8Unorm,
+        );
 
         let command_queue = device.new_command_queue();
         let sprite_atlas = Arc::new(MetalAtlas::new(device.clone()));
@@ -279,6 +297,7 @@ impl MetalRenderer {
             monochrome_sprites_pipeline_state,
             polychrome_sprites_pipeline_state,
             surfaces_pipeline_state,
+            surfaces_bgra_pipeline_state,
             unit_vertices,
             instance_buffer_pool,
             sprite_atlas,
@@ -286,6 +305,7 @@ impl MetalRenderer {
             path_intermediate_texture: None,
             path_intermediate_msaa_texture: None,
             path_sample_count: PATH_SAMPLE_COUNT,
+            video_texture_cache: VideoCache::new(VIDEO_TEXTURE_CACHE_CAPACITY),
         }
     }
 
@@ -382,8 +402,12 @@ impl MetalRenderer {
         loop {
             let mut instance_buffer = self.instance_buffer_pool.lock().acquire(&self.device);
 
-            let command_buffer =
-                self.draw_primitives(scene, &mut instance_buffer, drawable, viewport_size);
+            let command_buffer = self.draw_primitives(
+                scene,
+                &mut instance_buffer,
+                drawable.texture(),
+                viewport_size,
+            );
 
             match command_buffer {
                 Ok(command_buffer) => {
@@ -428,11 +452,59 @@ impl MetalRenderer {
         }
     }
 
+    /// Render `scene` into an offscreen texture the size of the drawable and
+    /// read it back as a BGRA frame, for capturing the window's contents.
+    ///
+    /// This blocks until the GPU has finished rendering.
+    pub fn capture(&mut self, scene: &Scene) -> Result<crate::VideoFrame> {
+        let drawable_size = self.layer.drawable_size();
+        let width = drawable_size.width.ceil() as u32;
+        let height = drawable_size.height.ceil() as u32;
+        anyhow::ensure!(width > 0 && height > 0, "window has no drawable area");
+        let viewport_size: Size<DevicePixels> = size((width as i32).into(), (height as i32).into());
+
+        let texture_descriptor = metal::TextureDescriptor::new();
+        texture_descriptor.set_width(width as u64);
+        texture_descriptor.set_height(height as u64);
+        texture_descriptor.set_pixel_format(MTLPixelFormat::BGRA8Unorm);
+        texture_descriptor.set_storage_mode(metal::MTLStorageMode::Managed);
+        texture_descriptor
+            .set_usage(metal::MTLTextureUsage::RenderTarget | metal::MTLTextureUsage::ShaderRead);
+        let texture = self.device.new_texture(&texture_descriptor);
+
+        let mut instance_buffer = self.instance_buffer_pool.lock().acquire(&self.device);
+        let command_buffer =
+            self.draw_primitives(scene, &mut instance_buffer, &texture, viewport_size);
+        let command_buffer = match command_buffer {
+            Ok(command_buffer) => command_buffer,
+            Err(error) => {
+                self.instance_buffer_pool.lock().release(instance_buffer);
+                return Err(error);
+            }
+        };
+        let blit_encoder = command_buffer.new_blit_command_encoder();
+        blit_encoder.synchronize_resource(&texture);
+        blit_encoder.end_encoding();
+        command_buffer.commit();
+        command_buffer.wait_until_completed();
+        self.instance_buffer_pool.lock().release(instance_buffer);
+
+        let bytes_per_row = width as usize * 4;
+        let mut bgra = vec![0; bytes_per_row * height as usize];
+        texture.get_bytes(
+            bgra.as_mut_ptr() as *mut c_void,
+            bytes_per_row as u64,
+            metal::MTLRegion::new_2d(0, 0, width as u64, height as u64),
+            0,
+        );
+        Ok(crate::VideoFrame::try_from_bgra(bgra, width, height)?)
+    }
+
     fn draw_primitives(
         &mut self,
         scene: &Scene,
         instance_buffer: &mut InstanceBuffer,
-        drawable: &metal::MetalDrawableRef,
+        target: &metal::TextureRef,
         viewport_size: Size<DevicePixels>,
     ) -> Result<metal::CommandBuffer> {
         let command_queue = self.command_queue.clone();
@@ -440,15 +512,11 @@ impl MetalRenderer {
         let alpha = if self.layer.is_opaque() { 1. } else { 0. };
         let mut instance_offset = 0;
 
-        let mut command_encoder = new_command_encoder(
-            command_buffer,
-            drawable,
-            viewport_size,
-            |color_attachment| {
+        let mut command_encoder =
+            new_command_encoder(command_buffer, target, viewport_size, |color_attachment| {
                 color_attachment.set_load_action(metal::MTLLoadAction::Clear);
                 color_attachment.set_clear_color(metal::MTLClearColor::new(0., 0., 0., alpha));
-            },
-        );
+            });
 
         for batch in scene.batches() {
             let ok = match batch {
@@ -479,7 +547,7 @@ impl MetalRenderer {
 
                     command_encoder = new_command_encoder(
                         command_buffer,
-                        drawable,
+                        target,
                         viewport_size,
                         |color_attachment| {
                             color_attachment.set_load_action(metal::MTLLoadAction::Load);
@@ -1076,7 +1144,8 @@ impl MetalRenderer {
         viewport_size: Size<DevicePixels>,
         command_encoder: &metal::RenderCommandEncoderRef,
     ) -> bool {
-        command_encoder.set_render_pipeline_state(&self.surfaces_pipeline_state);
+        // Evicted textures are retained by the command buffers still using them.
+        self.video_texture_cache.remove_stale();
         command_encoder.set_vertex_buffer(
             SurfaceInputIndex::Vertices as u64,
             Some(&self.unit_vertices),
@@ -1090,13 +1159,21 @@ impl MetalRenderer {
 
         for surface in surfaces {
             // Handle different surface data types
-            let image_buffer = match &surface.frame_data {
-                PaintSurfaceData::CoreVideo(cv_buffer) => cv_buffer,
-                PaintSurfaceData::Bgra { .. } => {
-                    // TODO: Implement BGRA texture upload path for CPU-based frames
-                    // For now, skip BGRA surfaces on the Metal renderer
-                    // This would require creating a Metal texture and uploading the BGRA data
-                    log::warn!("BGRA surface rendering not yet implemented in Metal renderer");
+            let (image_buffer, color_range) = match &surface.frame_data {
+                PaintSurfaceData::CoreVideo {
+                    buffer,
+                    color_range,
+                } => (buffer, *color_range),
+                PaintSurfaceData::Cpu { frame, .. } => {
+                    if !self.draw_cpu_surface(
+                        surface,
+                        frame,
+                        instance_buffer,
+                        instance_offset,
+                        command_encoder,
+                    ) {
+                        return false;
+                    }
                     continue;
                 }
             };
@@ -1106,10 +1183,16 @@ impl MetalRenderer {
                 DevicePixels::from(image_buffer.get_height() as i32),
             );
 
-            assert_eq!(
-                image_buffer.get_pixel_format(),
-                kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
-            );
+            let pixel_format = image_buffer.get_pixel_format();
+            if pixel_format != kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
+                && pixel_format != kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange
+            {
+                log::warn!("unsupported CoreVideo pixel format for surface: {pixel_format:#x}");
+                continue;
+            }
+            let limited_range = u32::from(color_range == ColorRange::Limited);
+            let rotation = surface.rotation as u32;
+            command_encoder.set_render_pipeline_state(&self.surfaces_pipeline_state);
 
             let y_texture = self
                 .core_video_texture_cache
@@ -1135,7 +1218,7 @@ impl MetalRenderer {
                 .unwrap();
 
             align_offset(instance_offset);
-            let next_offset = *instance_offset + mem::size_of::<Surface>();
+            let next_offset = *instance_offset + mem::size_of::<SurfaceBounds>();
             if next_offset > instance_buffer.size {
                 return false;
             }
@@ -1145,11 +1228,26 @@ impl MetalRenderer {
                 Some(&instance_buffer.metal_buffer),
                 *instance_offset as u64,
             );
+            command_encoder.set_fragment_buffer(
+                SurfaceInputIndex::Surfaces as u64,
+                Some(&instance_buffer.metal_buffer),
+                *instance_offset as u64,
+            );
             command_encoder.set_vertex_bytes(
                 SurfaceInputIndex::TextureSize as u64,
                 mem::size_of_val(&texture_size) as u64,
                 &texture_size as *const Size<DevicePixels> as *const _,
             );
+            command_encoder.set_vertex_bytes(
+                SurfaceInputIndex::Rotation as u64,
+                mem::size_of_val(&rotation) as u64,
+                &rotation as *const u32 as *const _,
+            );
+            command_encoder.set_fragment_bytes(
+                SurfaceInputIndex::LimitedRange as u64,
+                mem::size_of_val(&limited_range) as u64,
+                &limited_range as *const u32 as *const _,
+            );
             command_encoder.set_fragment_texture(SurfaceInputIndex::YTexture as u64, unsafe {
                 let texture = CVMetalTextureGetTexture(y_texture.as_concrete_TypeRef());
                 Some(metal::TextureRef::from_ptr(texture as *mut _))
@@ -1168,6 +1266,7 @@ impl MetalRenderer {
                     SurfaceBounds {
                         bounds: surface.bounds,
                         content_mask: surface.content_mask.clone(),
+                        corner_radii: surface.corner_radii.clone(),
                     },
                 );
             }
@@ -1178,6 +1277,111 @@ impl MetalRenderer {
         true
     }
 
+    /// Draw a CPU video frame, uploading it to a texture unless the upload of
+    /// the same frame is cached.
+    ///
+    /// Uploads are never updated in place, since the frame in flight may
+    /// still be sampling them, so partial uploads are ignored.
+    fn draw_cpu_surface(
+        &mut self,
+        surface: &PaintSurface,
+        frame: &VideoFrame,
+        instance_buffer: &mut InstanceBuffer,
+        instance_offset: &mut usize,
+        command_encoder: &metal::RenderCommandEncoderRef,
+    ) -> bool {
+        let texture = match self.video_texture_cache.prepare_upload(frame, None) {
+            None => return true,
+            Some(SurfaceUpload::Cached(texture)) => texture,
+            Some(SurfaceUpload::Upload { key, pixels, .. }) => {
+                let texture = self.create_surface_texture(&pixels);
+                self.video_texture_cache
+                    .insert(key, texture.clone(), pixels.buffer.len());
+                texture
+            }
+        };
+
+        align_offset(instance_offset);
+        let next_offset = *instance_offset + mem::size_of::<SurfaceBounds>();
+        if next_offset > instance_buffer.size {
+            return false;
+        }
+
+        let texture_size = size(
+            DevicePixels::from(frame.width as i32),
+            DevicePixels::from(frame.height as i32),
+        );
+        let rotation = surface.rotation as u32;
+        let alpha_mode = surface.alpha_mode as u32;
+        command_encoder.set_render_pipeline_state(&self.surfaces_bgra_pipeline_state);
+        command_encoder.set_vertex_buffer(
+            SurfaceInputIndex::Surfaces as u64,
+            Some(&instance_buffer.metal_buffer),
+            *instance_offset as u64,
+        );
+        command_encoder.set_fragment_buffer(
+            SurfaceInputIndex::Surfaces as u64,
+            Some(&instance_buffer.metal_buffer),
+            *instance_offset as u64,
+        );
+        command_encoder.set_vertex_bytes(
+            SurfaceInputIndex::TextureSize as u64,
+            mem::size_of_val(&texture_size) as u64,
+            &texture_size as *const Size<DevicePixels> as *const _,
+        );
+        command_encoder.set_vertex_bytes(
+            SurfaceInputIndex::Rotation as u64,
+            mem::size_of_val(&rotation) as u64,
+            &rotation as *const u32 as *const _,
+        );
+        command_encoder.set_fragment_bytes(
+            SurfaceInputIndex::AlphaMode as u64,
+            mem::size_of_val(&alpha_mode) as u64,
+            &alpha_mode as *const u32 as *const _,
+        );
+        command_encoder.set_fragment_texture(SurfaceInputIndex::Texture as u64, Some(&texture));
+
+        unsafe {
+            let buffer_contents = (instance_buffer.metal_buffer.contents() as *mut u8)
+                .add(*instance_offset) as *mut SurfaceBounds;
+            ptr::write(
+                buffer_contents,
+                SurfaceBounds {
+                    bounds: surface.bounds,
+                    content_mask: surface.content_mask.clone(),
+                    corner_radii: surface.corner_radii.clone(),
+                },
+            );
+        }
+
+        command_encoder.draw_primitives(metal::MTLPrimitiveType::Triangle, 0, 6);
+        *instance_offset = next_offset;
+        true
+    }
+
+    /// Upload the converted pixels of a CPU video frame to a new texture.
+    fn create_surface_texture(&self, pixels: &SurfacePixels) -> metal::Texture {
+        let pixel_format = match pixels.format {
+            crate::PixelFormat::Rgba8 => MTLPixelFormat::RGBA8Unorm,
+            crate::PixelFormat::Rgba16Float => MTLPixelFormat::RGBA16Float,
+            _ => MTLPixelFormat::BGRA8Unorm,
+        };
+        let texture_descriptor = metal::TextureDescriptor::new();
+        texture_descriptor.set_width(pixels.width as u64);
+        texture_descriptor.set_height(pixels.height as u64);
+        texture_descriptor.set_pixel_format(pixel_format);
+        texture_descriptor.set_usage(metal::MTLTextureUsage::ShaderRead);
+        let texture = self.device.new_texture(&texture_descriptor);
+        texture.replace_region(
+            metal::MTLRegion::new_2d(0, 0, pixels.width as u64, pixels.height as u64),
+            0,
+            pixels.buffer.as_ptr() as *const _,
+            pixels.stride as u64,
+        );
+        record_stat(VideoStat::TextureUpload);
+        texture
+    }
+
     /// Get a reference to the Metal device.
     pub fn device(&self) -> &metal::Device {
         &self.device
@@ -1186,7 +1390,7 @@ impl MetalRenderer {
 
 fn new_command_encoder<'a>(
     command_buffer: &'a metal::CommandBufferRef,
-    drawable: &'a metal::MetalDrawableRef,
+    target: &'a metal::TextureRef,
     viewport_size: Size<DevicePixels>,
     configure_color_attachment: impl Fn(&RenderPassColorAttachmentDescriptorRef),
 ) -> &'a metal::RenderCommandEncoderRef {
@@ -1195,7 +1399,7 @@ fn new_command_encoder<'a>(
         .color_attachments()
         .object_at(0)
         .unwrap();
-    color_attachment.set_texture(Some(drawable.texture()));
+    color_attachment.set_texture(Some(target));
     color_attachment.set_store_action(metal::MTLStoreAction::Store);
     configure_color_attachment(color_attachment);
 
@@ -1361,6 +1565,10 @@ enum SurfaceInputIndex {
     TextureSize = 3,
     YTexture = 4,
     CbCrTexture = 5,
+    LimitedRange = 6,
+    Rotation = 7,
+    Texture = 8,
+    AlphaMode = 9,
 }
 
 #[repr(C)]
@@ -1380,4 +1588,5 @@ pub struct PathSprite {
 pub struct SurfaceBounds {
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
+    pub corner_radii: Corners<ScaledPixels>,
 }
@@ -838,11 +838,13 @@ struct SurfaceVertexOutput {
   float4 position [[position]];
   float2 texture_position;
   float clip_distance [[clip_distance]][4];
+  uint surface_id [[flat]];
 };
 
 struct SurfaceFragmentInput {
   float4 position [[position]];
   float2 texture_position;
+  uint surface_id [[flat]];
 };
 
 vertex SurfaceVertexOutput surface_vertex(
@@ -852,7 +854,8 @@ vertex SurfaceVertexOutput surface_vertex(
     constant Size_DevicePixels *viewport_size
     [[buffer(SurfaceInputIndex_ViewportSize)]],
     constant Size_DevicePixels *texture_size
-    [[buffer(SurfaceInputIndex_TextureSize)]]) {
+    [[buffer(SurfaceInputIndex_TextureSize)]],
+    constant uint *rotation [[buffer(SurfaceInputIndex_Rotation)]]) {
   float2 unit_vertex = unit_vertices[unit_vertex_id];
   SurfaceBounds surface = surfaces[surface_id];
   float4 device_position =
@@ -860,19 +863,36 @@ vertex SurfaceVertexOutput surface_vertex(
   float4 clip_distance = distance_from_clip_rect(unit_vertex, surface.bounds,
                                                  surface.content_mask.bounds);
   // We are going to copy the whole texture, so the texture position corresponds
-  // to the current vertex of the unit triangle.
+  // to the current vertex of the unit triangle, rotated clockwise by `rotation`
+  // quarter turns.
   float2 texture_position = unit_vertex;
+  switch (*rotation) {
+  case 1:
+    texture_position = float2(unit_vertex.y, 1.0 - unit_vertex.x);
+    break;
+  case 2:
+    texture_position = float2(1.0 - unit_vertex.x, 1.0 - unit_vertex.y);
+    break;
+  case 3:
+    texture_position = float2(1.0 - unit_vertex.y, unit_vertex.x);
+    break;
+  }
   return SurfaceVertexOutput{
       device_position,
       texture_position,
-      {clip_distance.x, clip_distance.y, clip_distance.z, clip_distance.w}};
+      {clip_distance.x, clip_distance.y, clip_distance.z, clip_distance.w},
+      surface_id};
 }
 
 fragment float4 surface_fragment(SurfaceFragmentInput input [[stage_in]],
+                                 constant SurfaceBounds *surfaces
+                                 [[buffer(SurfaceInputIndex_Surfaces)]],
                                  texture2d<float> y_texture
                                  [[texture(SurfaceInputIndex_YTexture)]],
                                  texture2d<float> cb_cr_texture
-                                 [[texture(SurfaceInputIndex_CbCrTexture)]]) {
+                                 [[texture(SurfaceInputIndex_CbCrTexture)]],
+                                 constant uint *limited_range
+                                 [[buffer(SurfaceInputIndex_LimitedRange)]]) {
   constexpr sampler texture_sampler(mag_filter::linear, min_filter::linear);
   const float4x4 ycbcrToRGBTransform =
       float4x4(float4(+1.0000f, +1.0000f, +1.0000f, +0.0000f),
@@ -882,8 +902,42 @@ fragment float4 surface_fragment(SurfaceFragmentInput input [[stage_in]],
   float4 ycbcr = float4(
       y_texture.sample(texture_sampler, input.texture_position).r,
       cb_cr_texture.sample(texture_sampler, input.texture_position).rg, 1.0);
+  if (*limited_range) {
+    // Expand luma from 16-235 and chroma from 16-240 to the full range.
+    ycbcr.r = (ycbcr.r - 16.0 / 255.0) * (255.0 / 219.0);
+    ycbcr.gb = (ycbcr.gb - 128.0 / 255.0) * (255.0 / 224.0) + 0.5;
+  }
+
+  SurfaceBounds surface = surfaces[input.surface_id];
+  float4 color = ycbcrToRGBTransform * ycbcr;
+  float distance =
+      quad_sdf(input.position.xy, surface.bounds, surface.corner_radii);
+  color.a *= saturate(0.5 - distance);
+  return color;
+}
+
+fragment float4 surface_bgra_fragment(SurfaceFragmentInput input [[stage_in]],
+                                      constant SurfaceBounds *surfaces
+                                      [[buffer(SurfaceInputIndex_Surfaces)]],
+                                      texture2d<float> bgra_texture
+                                      [[texture(SurfaceInputIndex_Texture)]],
+                                      constant uint *alpha_mode
+                                      [[buffer(SurfaceInputIndex_AlphaMode)]]) {
+  constexpr sampler texture_sampler(mag_filter::linear, min_filter::linear);
+  float4 color = bgra_texture.sample(texture_sampler, input.texture_position);
+  // Opaque frames ignore their alpha channel, and premultiplied ones are
+  // blended with straight alpha like everything else.
+  if (*alpha_mode == 0) {
+    color.a = 1.0;
+  } else if (*alpha_mode == 2 && color.a > 0.0) {
+    color.rgb /= color.a;
+  }
 
-  return ycbcrToRGBTransform * ycbcr;
+  SurfaceBounds surface = surfaces[input.surface_id];
+  float distance =
+      quad_sdf(input.position.xy, surface.bounds, surface.corner_radii);
+  color.a *= saturate(0.5 - distance);
+  return color;
 }
 
 float4 hsla_to_rgba(Hsla hsla) {
@@ -1518,6 +1518,10 @@ impl PlatformWindow for MacWindow {
         this.renderer.draw(scene);
     }
 
+    fn capture_frame(&self, scene: &crate::Scene) -> anyhow::Result<crate::VideoFrame> {
+        self.0.lock().renderer.capture(scene)
+    }
+
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
         self.0.lock().renderer.sprite_atlas().clone()
     }
@@ -23,6 +23,7 @@ use crate::{
     platform::windows::directx_renderer::shader_resources::{
         RawShaderBytes, ShaderModule, ShaderTarget,
     },
+    video::{SurfacePixels, SurfaceUpload, VideoCache, VideoStat, record_stat},
     *,
 };
 
@@ -55,16 +56,18 @@ pub(crate) struct DirectXRenderer {
     /// meaning we lost all the allocated gpu textures and scene resources.
     skip_draws: bool,
 
-    /// Cached video texture for surface rendering (avoids per-frame allocation)
-    video_texture_cache: Option<VideoTextureCache>,
+    /// Textures of recently painted CPU video frames, so unchanged frames aren't uploaded again
+    video_texture_cache: VideoCache<VideoTexture>,
 }
 
-/// Cached video texture to avoid per-frame allocation
-struct VideoTextureCache {
+/// The maximum number of bytes of video frames to keep uploaded.
+const VIDEO_TEXTURE_CACHE_CAPACITY: usize = 256 * 1024 * 1024;
+
+/// The texture a CPU video frame was uploaded to
+#[derive(Clone)]
+struct VideoTexture {
     texture: ID3D11Texture2D,
     srv: ID3D11ShaderResourceView,
-    width: u32,
-    height: u32,
 }
 
 /// Direct3D objects
@@ -101,6 +104,7 @@ struct DirectXRenderPipelines {
     underline_pipeline: PipelineState<Underline>,
     mono_sprites: PipelineState<MonochromeSprite>,
     poly_sprites: PipelineState<PolychromeSprite>,
+    surfaces: PipelineState<SurfaceSprite>,
 }
 
 struct DirectXGlobalElements {
@@ -185,7 +189,7 @@ impl DirectXRenderer {
             width: 1,
             height: 1,
             skip_draws: false,
-            video_texture_cache: None,
+            video_texture_cache: VideoCache::new(VIDEO_TEXTURE_CACHE_CAPACITY),
         })
     }
 
@@ -262,6 +266,7 @@ impl DirectXRenderer {
             }
 
             self.resources.take();
+            self.video_texture_cache.clear();
             if let Some(devices) = &self.devices {
                 devices.device_context.OMSetRenderTargets(None, None);
                 devices.device_context.ClearState();
@@ -323,6 +328,35 @@ impl DirectXRenderer {
             // and so likely do not have the textures anymore that are required for drawing
             return Ok(());
         }
+        self.render_scene(scene)?;
+        self.present()
+    }
+
+    /// Render `scene` into the back buffer without presenting it and read it
+    /// back as a BGRA frame, for capturing the window's contents.
+    ///
+    /// This blocks until the GPU has finished rendering. The next draw
+    /// overwrites the back buffer, so the window isn't affected.
+    pub(crate) fn capture(&mut self, scene: &Scene) -> Result<crate::VideoFrame> {
+        anyhow::ensure!(
+            !self.skip_draws,
+            "can't capture the window while recovering from a lost device"
+        );
+        self.render_scene(scene)?;
+        let render_target = self
+            .resources
+            .as_ref()
+            .context("resources missing")?
+            .render_target
+            .clone()
+            .context("missing render target")?;
+        let bgra =
+            crate::VideoFrame::from_d3d11_texture(render_target, 0, self.width, self.height)
+                .into_bgra_vec()?;
+        Ok(crate::VideoFrame::try_from_bgra(bgra, self.width, self.height)?)
+    }
+
+    fn render_scene(&mut self, scene: &Scene) -> Result<()> {
         self.pre_draw()?;
         for batch in scene.batches() {
             match batch {
@@ -355,7 +389,7 @@ impl DirectXRenderer {
                 scene.surfaces.len(),
             ))?;
         }
-        self.present()
+        Ok(())
     }
 
     pub(crate) fn resize(&mut self, new_size: Size<DevicePixels>) -> Result<()> {
@@ -650,117 +684,149 @@ impl DirectXRenderer {
         // Process each surface (usually just one for video)
         for surface in surfaces {
             // Extract frame data
-            let (srv, width, height) = match &surface.frame_data {
-                crate::PaintSurfaceData::Bgra {
-                    buffer,
-                    width,
-                    height,
+            let srv = match &surface.frame_data {
+                crate::PaintSurfaceData::Cpu {
+                    frame,
+                    partial_upload,
                 } => {
-                    let width = *width;
-                    let height = *height;
-
-                    if width == 0 || height == 0 || buffer.is_empty() {
+                    let (width, height) = frame.size();
+                    if width == 0 || height == 0 {
                         continue;
                     }
 
-                    // Check if we need to recreate the texture (size changed or doesn't exist)
-                    let needs_new_texture = match &self.video_texture_cache {
-                        Some(cache) => cache.width != width || cache.height != height,
-                        None => true,
-                    };
-
-                    if needs_new_texture {
-                        // Create a DYNAMIC texture for faster CPU→GPU updates
-                        let texture_desc = D3D11_TEXTURE2D_DESC {
-                            Width: width,
-                            Height: height,
-                            MipLevels: 1,
-                            ArraySize: 1,
-                            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
-                            SampleDesc: DXGI_SAMPLE_DESC {
-                                Count: 1,
-                                Quality: 0,
-                            },
-                            Usage: D3D11_USAGE_DYNAMIC,
-                            BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as u32,
-                            CPUAccessFlags: D3D11_CPU_ACCESS_WRITE.0 as u32,
-                            MiscFlags: 0,
-                        };
-
-                        let texture: ID3D11Texture2D = unsafe {
-                            let mut tex = None;
-                            devices
-                                .device
-                                .CreateTexture2D(&texture_desc, None, Some(&mut tex))?;
-                            tex.context("Failed to create video texture")?
-                        };
-
-                        // Create shader resource view for the texture
-                        let srv_desc = D3D11_SHADER_RESOURCE_VIEW_DESC {
-                            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
-                            ViewDimension: D3D_SRV_DIMENSION_TEXTURE2D,
-                            Anonymous: D3D11_SHADER_RESOURCE_VIEW_DESC_0 {
-                                Texture2D: D3D11_TEX2D_SRV {
-                                    MostDetailedMip: 0,
-                                    MipLevels: 1,
-                                },
-                            },
-                        };
-
-                        let srv: ID3D11ShaderResourceView = unsafe {
-                            let mut view = None;
-                            devices.device.CreateShaderResourceView(
-                                &texture,
-                                Some(&srv_desc),
-                                Some(&mut view),
-                            )?;
-                            view.context("Failed to create shader resource view")?
-                        };
-
-                        self.video_texture_cache = Some(VideoTextureCache {
-                            texture,
-                            srv,
-                            width,
-                            height,
-                        });
-                    }
-
-                    // Update the cached texture with new pixel data using Map/Unmap
-                    let cache = self.video_texture_cache.as_ref().unwrap();
-                    unsafe {
-                        let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
-                        devices.device_context.Map(
-                            &cache.texture,
-                            0,
-                            D3D11_MAP_WRITE_DISCARD,
-                            0,
-                            Some(&mut mapped),
-                        )?;
-
-                        // Copy pixel data row by row (in case pitch differs)
-                        let src_pitch = width as usize * 4;
-                        let dst_pitch = mapped.RowPitch as usize;
-                        let src = buffer.as_ptr();
-                        let dst = mapped.pData as *mut u8;
-
-                        if src_pitch == dst_pitch {
-                            // Fast path: same pitch, copy all at once
-                            std::ptr::copy_nonoverlapping(src, dst, buffer.len());
-                        } else {
-                            // Row by row copy
-                            for row in 0..height as usize {
-                                std::ptr::copy_nonoverlapping(
-                                    src.add(row * src_pitch),
-                                    dst.add(row * dst_pitch),
-                                    src_pitch,
-                                );
-                            }
+                    // Reuse the texture of a frame that has already been uploaded,
+                    // converting the frame only when it hasn't been
+                    match self
+                        .video_texture_cache
+                        .prepare_upload(frame, partial_upload.as_ref())
+                    {
+                        None => continue,
+                        Some(SurfaceUpload::Cached(cached)) => cached.srv,
+                        Some(SurfaceUpload::Upload {
+                            key,
+                            pixels,
+                            update,
+                        }) => {
+                            let SurfacePixels {
+                                buffer,
+                                width,
+                                height,
+                                stride,
+                                format,
+                            } = pixels;
+                            let (texture_format, bytes_per_pixel) = match format {
+                                crate::PixelFormat::Rgba8 => (DXGI_FORMAT_R8G8B8A8_UNORM, 4),
+                                crate::PixelFormat::Rgba16Unorm => {
+                                    (DXGI_FORMAT_R16G16B16A16_UNORM, 8)
+                                }
+                                crate::PixelFormat::Rgba16Float => {
+                                    (DXGI_FORMAT_R16G16B16A16_FLOAT, 8)
+                                }
+                                _ => (DXGI_FORMAT_B8G8R8A8_UNORM, 4),
+                            };
+
+                            // Update the texture of the frame painted in this one's place
+                            // when only part of it changed
+                            let video_texture = match update {
+                                Some((cached, upload)) => {
+                                    let region = D3D11_BOX {
+                                        left: upload.x,
+                                        top: upload.y,
+                                        front: 0,
+                                        right: upload.x + upload.width,
+                                        bottom: upload.y + upload.height,
+                                        back: 1,
+                                    };
+                                    let offset = upload.y as usize * stride as usize
+                                        + upload.x as usize * bytes_per_pixel;
+                                    unsafe {
+                                        devices.device_context.UpdateSubresource(
+                                            &cached.texture,
+                                            0,
+                                            Some(&region),
+                                            buffer.as_ptr().add(offset) as _,
+                                            stride,
+                                            0,
+                                        );
+                                    }
+                                    record_stat(VideoStat::TextureUpload);
+                                    cached
+                                }
+                                None => {
+                                    // Use a DEFAULT texture so that later frames can update a
+                                    // region of it without discarding the rest
+                                    let texture_desc = D3D11_TEXTURE2D_DESC {
+                                        Width: width,
+                                        Height: height,
+                                        MipLevels: 1,
+                                        ArraySize: 1,
+                                        Format: texture_format,
+                                        SampleDesc: DXGI_SAMPLE_DESC {
+                                            Count: 1,
+                                            Quality: 0,
+                                        },
+                                        Usage: D3D11_USAGE_DEFAULT,
+                                        BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as u32,
+                                        CPUAccessFlags: 0,
+                                        MiscFlags: 0,
+                                    };
+
+                                    let texture: ID3D11Texture2D = unsafe {
+                                        let mut tex = None;
+                                        devices.device.CreateTexture2D(
+                                            &texture_desc,
+                                            None,
+                                            Some(&mut tex),
+                                        )?;
+                                        tex.context("Failed to create video texture")?
+                                    };
+
+                                    // Create shader resource view for the texture
+                                    let srv_desc = D3D11_SHADER_RESOURCE_VIEW_DESC {
+                                        Format: texture_format,
+                                        ViewDimension: D3D_SRV_DIMENSION_TEXTURE2D,
+                                        Anonymous: D3D11_SHADER_RESOURCE_VIEW_DESC_0 {
+                                            Texture2D: D3D11_TEX2D_SRV {
+                                                MostDetailedMip: 0,
+                                                MipLevels: 1,
+                                            },
+                                        },
+                                    };
+
+                                    let srv: ID3D11ShaderResourceView = unsafe {
+                                        let mut view = None;
+                                        devices.device.CreateShaderResourceView(
+                                            &texture,
+                                            Some(&srv_desc),
+                                            Some(&mut view),
+                                        )?;
+                                        view.context("Failed to create shader resource view")?
+                                    };
+
+                                    // Fill the texture with the pixel data
+                                    unsafe {
+                                        devices.device_context.UpdateSubresource(
+                                            &texture,
+                                            0,
+                                            None,
+                                            buffer.as_ptr() as _,
+                                            stride,
+                                            0,
+                                        );
+                                    }
+                                    record_stat(VideoStat::TextureUpload);
+
+                                    VideoTexture { texture, srv }
+                                }
+                            };
+
+                            // Evicted textures are released once the device context no longer uses them
+                            let srv = video_texture.srv.clone();
+                            self.video_texture_cache
+                                .insert(key, video_texture, buffer.len());
+                            srv
                         }
-
-                        devices.device_context.Unmap(&cache.texture, 0);
                     }
-
-                    (cache.srv.clone(), width, height)
                 }
                 crate::PaintSurfaceData::D3D11 {
                     texture,
@@ -800,42 +866,24 @@ impl DirectXRenderer {
                         view.context("Failed to create SRV from D3D11 texture")?
                     };
 
-                    (srv, desc.Width, desc.Height)
+                    srv
                 }
             };
 
-            // Update the surface buffer and draw using polychrome sprite pipeline
-            // (reusing existing pipeline that can render textured quads)
-            self.pipelines.poly_sprites.update_buffer(
+            // Rotate the frame as it's drawn, rather than rotating its pixels
+            self.pipelines.surfaces.update_buffer(
                 &devices.device,
                 &devices.device_context,
-                slice::from_ref(&PolychromeSprite {
-                    order: surface.order,
-                    pad: 0,
-                    grayscale: false,
-                    opacity: 1.0,
+                slice::from_ref(&SurfaceSprite {
                     bounds: surface.bounds,
                     content_mask: surface.content_mask.clone(),
-                    corner_radii: Default::default(),
-                    tile: crate::AtlasTile {
-                        texture_id: crate::AtlasTextureId {
-                            index: 0,
-                            kind: crate::AtlasTextureKind::Polychrome,
-                        },
-                        tile_id: crate::TileId(0),
-                        padding: 0,
-                        bounds: crate::Bounds {
-                            origin: crate::point(crate::DevicePixels(0), crate::DevicePixels(0)),
-                            size: crate::size(
-                                crate::DevicePixels(width as i32),
-                                crate::DevicePixels(height as i32),
-                            ),
-                        },
-                    },
+                    corner_radii: surface.corner_radii.clone(),
+                    rotation: surface.rotation as u32,
+                    alpha_mode: surface.alpha_mode as u32,
                 }),
             )?;
 
-            self.pipelines.poly_sprites.draw_with_texture(
+            self.pipelines.surfaces.draw_with_texture(
                 &devices.device_context,
                 slice::from_ref(&Some(srv)),
                 slice::from_ref(&resources.viewport),
@@ -1016,6 +1064,13 @@ impl DirectXRenderPipelines {
             16,
             create_blend_state(device)?,
         )?;
+        let surfaces = PipelineState::new(
+            device,
+            "surface_pipeline",
+            ShaderModule::Surface,
+            4,
+            create_blend_state(device)?,
+        )?;
 
         Ok(Self {
             shadow_pipeline,
@@ -1025,6 +1080,7 @@ impl DirectXRenderPipelines {
             underline_pipeline,
             mono_sprites,
             poly_sprites,
+            surfaces,
         })
     }
 }
@@ -1221,6 +1277,19 @@ impl<T> PipelineState<T> {
     }
 }
 
+/// A video frame drawn by the surface pipeline.
+#[derive(Clone)]
+#[repr(C)]
+struct SurfaceSprite {
+    bounds: Bounds<ScaledPixels>,
+    content_mask: ContentMask<ScaledPixels>,
+    corner_radii: Corners<ScaledPixels>,
+    /// The clockwise rotation of the frame within `bounds`, in quarter turns
+    rotation: u32,
+    /// How the frame's alpha is stored, as an `AlphaMode` discriminant
+    alpha_mode: u32,
+}
+
 #[derive(Clone, Copy)]
 #[repr(C)]
 struct PathRasterizationSprite {
@@ -1631,6 +1700,7 @@ pub(crate) mod shader_resources {
         PathSprite,
         MonochromeSprite,
         PolychromeSprite,
+        Surface,
         EmojiRasterization,
     }
 
@@ -1701,6 +1771,10 @@ pub(crate) mod shader_resources {
                     ShaderTarget::Vertex => POLYCHROME_SPRITE_VERTEX_BYTES,
                     ShaderTarget::Fragment => POLYCHROME_SPRITE_FRAGMENT_BYTES,
                 },
+                ShaderModule::Surface => match target {
+                    ShaderTarget::Vertex => SURFACE_VERTEX_BYTES,
+                    ShaderTarget::Fragment => SURFACE_FRAGMENT_BYTES,
+                },
                 ShaderModule::EmojiRasterization => match target {
                     ShaderTarget::Vertex => EMOJI_RASTERIZATION_VERTEX_BYTES,
                     ShaderTarget::Fragment => EMOJI_RASTERIZATION_FRAGMENT_BYTES,
@@ -1790,6 +1864,7 @@ pub(crate) mod shader_resources {
                 ShaderModule::PathSprite => "path_sprite",
                 ShaderModule::MonochromeSprite => "monochrome_sprite",
                 ShaderModule::PolychromeSprite => "polychrome_sprite",
+                ShaderModule::Surface => "surface",
                 ShaderModule::EmojiRasterization => "emoji_rasterization",
             }
         }
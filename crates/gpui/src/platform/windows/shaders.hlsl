@@ -1180,3 +1180,75 @@ float4 polychrome_sprite_fragment(PolychromeSpriteFragmentInput input): SV_Targe
     color.a *= sprite.opacity * saturate(0.5 - distance);
     return color;
 }
+
+/*
+**
+**              Surfaces
+**
+*/
+
+struct SurfaceSprite {
+    Bounds bounds;
+    Bounds content_mask;
+    Corners corner_radii;
+    uint rotation;
+    // How the texture's alpha is stored: 0 opaque, 1 straight, 2 premultiplied.
+    uint alpha_mode;
+};
+
+struct SurfaceVertexOutput {
+    nointerpolation uint surface_id: TEXCOORD0;
+    float4 position: SV_Position;
+    float2 texture_position: POSITION;
+    float4 clip_distance: SV_ClipDistance;
+};
+
+struct SurfaceFragmentInput {
+    nointerpolation uint surface_id: TEXCOORD0;
+    float4 position: SV_Position;
+    float2 texture_position: POSITION;
+};
+
+StructuredBuffer<SurfaceSprite> surfaces: register(t1);
+
+// Map a position within the surface's bounds to the texture, rotating the
+// texture clockwise by `rotation` quarter turns.
+float2 rotate_texture_position(float2 unit_vertex, uint rotation) {
+    if (rotation == 1u) {
+        return float2(unit_vertex.y, 1.0 - unit_vertex.x);
+    } else if (rotation == 2u) {
+        return float2(1.0 - unit_vertex.x, 1.0 - unit_vertex.y);
+    } else if (rotation == 3u) {
+        return float2(1.0 - unit_vertex.y, unit_vertex.x);
+    }
+    return unit_vertex;
+}
+
+SurfaceVertexOutput surface_vertex(uint vertex_id: SV_VertexID, uint surface_id: SV_InstanceID) {
+    float2 unit_vertex = float2(float(vertex_id & 1u), 0.5 * float(vertex_id & 2u));
+    SurfaceSprite surface = surfaces[surface_id];
+    float4 device_position = to_device_position(unit_vertex, surface.bounds);
+    float4 clip_distance = distance_from_clip_rect(unit_vertex, surface.bounds,
+                                                    surface.content_mask);
+    float2 texture_position = rotate_texture_position(unit_vertex, surface.rotation);
+
+    SurfaceVertexOutput output;
+    output.position = device_position;
+    output.texture_position = texture_position;
+    output.surface_id = surface_id;
+    output.clip_distance = clip_distance;
+    return output;
+}
+
+float4 surface_fragment(SurfaceFragmentInput input): SV_Target {
+    SurfaceSprite surface = surfaces[input.surface_id];
+    float4 color = t_sprite.Sample(s_sprite, input.texture_position);
+    if (surface.alpha_mode == 0u) {
+        color.a = 1.0;
+    } else if (surface.alpha_mode == 2u && color.a > 0.0) {
+        color.rgb /= color.a;
+    }
+    float distance = quad_sdf(input.position.xy, surface.bounds, surface.corner_radii);
+    color.a *= saturate(0.5 - distance);
+    return color;
+}
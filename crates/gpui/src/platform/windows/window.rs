@@ -913,6 +913,10 @@ impl PlatformWindow for WindowsWindow {
         self.state.renderer.borrow_mut().draw(scene).log_err();
     }
 
+    fn capture_frame(&self, scene: &Scene) -> anyhow::Result<crate::VideoFrame> {
+        self.state.renderer.borrow_mut().capture(scene)
+    }
+
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
         self.state.renderer.borrow().sprite_atlas()
     }
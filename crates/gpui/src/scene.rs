@@ -658,6 +658,12 @@ pub(crate) struct PaintSurface {
     pub order: DrawOrder,
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
+    pub corner_radii: Corners<ScaledPixels>,
+    /// The clockwise rotation applied to the frame within `bounds`.
+    pub rotation: crate::Rotation,
+    /// How the frame's alpha channel is stored, which renderers account for
+    /// as they sample it rather than by rewriting its pixels.
+    pub alpha_mode: crate::AlphaMode,
     /// The video frame data to render.
     pub frame_data: PaintSurfaceData,
 }
@@ -665,15 +671,21 @@ pub(crate) struct PaintSurface {
 /// The data for a paint surface, containing video frame pixels.
 #[derive(Clone)]
 pub(crate) enum PaintSurfaceData {
-    /// BGRA pixel buffer (CPU-based, works on all platforms)
-    Bgra {
-        buffer: std::sync::Arc<Vec<u8>>,
-        width: u32,
-        height: u32,
+    /// A CPU or DMA-BUF frame, which renderers convert and upload only when
+    /// its upload isn't cached from an earlier paint (works on all platforms)
+    Cpu {
+        frame: crate::VideoFrame,
+        /// The region that changed since the frame painted in this one's place,
+        /// when only part of it did.
+        partial_upload: Option<crate::video::PartialUpload>,
     },
     /// macOS CoreVideo pixel buffer (zero-copy)
     #[cfg(target_os = "macos")]
-    CoreVideo(core_video::pixel_buffer::CVPixelBuffer),
+    CoreVideo {
+        buffer: core_video::pixel_buffer::CVPixelBuffer,
+        /// Whether the YCbCr samples need expanding from limited range
+        color_range: crate::ColorRange,
+    },
     /// Windows D3D11 texture (zero-copy)
     #[cfg(target_os = "windows")]
     D3D11 {
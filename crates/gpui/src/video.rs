@@ -1,150 +1,6147 @@
-//! Video frame types for cross-platform video rendering.
-//!
-//! This module provides a platform-agnostic video frame type that can be used
-//! to render video content efficiently across different operating systems.
-
-use std::sync::Arc;
-
-/// A video frame that can be painted to the screen.
-///
-/// This type abstracts over platform-specific video buffer types,
-/// allowing efficient video rendering on all supported platforms.
-#[derive(Clone)]
-pub struct VideoFrame {
-    pub(crate) data: VideoFrameData,
-    /// The width of the video frame in pixels.
-    pub width: u32,
-    /// The height of the video frame in pixels.
-    pub height: u32,
-}
-
-/// The inner data of a video frame.
-#[derive(Clone)]
-pub(crate) enum VideoFrameData {
-    /// A CPU buffer in BGRA format.
-    /// This is the fallback format that works on all platforms.
-    Bgra(Arc<Vec<u8>>),
-
-    /// A macOS CoreVideo pixel buffer (zero-copy path).
-    #[cfg(target_os = "macos")]
-    CoreVideo(core_video::pixel_buffer::CVPixelBuffer),
-
-    /// A Windows D3D11 texture (zero-copy path).
-    #[cfg(target_os = "windows")]
-    D3D11 {
-        texture: windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
-        subresource_index: u32,
-    },
-}
-
-impl std::fmt::Debug for VideoFrame {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("VideoFrame")
-            .field("width", &self.width)
-            .field("height", &self.height)
-            .field(
-                "data",
-                &match &self.data {
-                    VideoFrameData::Bgra(_) => "Bgra",
-                    #[cfg(target_os = "macos")]
-                    VideoFrameData::CoreVideo(_) => "CoreVideo",
-                    #[cfg(target_os = "windows")]
-                    VideoFrameData::D3D11 { .. } => "D3D11",
-                },
-            )
-            .finish()
-    }
-}
-
-impl VideoFrame {
-    /// Create a video frame from raw BGRA pixel data.
-    ///
-    /// The buffer should contain `width * height * 4` bytes in BGRA format.
-    pub fn from_bgra(buffer: Vec<u8>, width: u32, height: u32) -> Self {
-        debug_assert_eq!(
-            buffer.len(),
-            (width * height * 4) as usize,
-            "BGRA buffer size mismatch"
-        );
-        Self {
-            data: VideoFrameData::Bgra(Arc::new(buffer)),
-            width,
-            height,
-        }
-    }
-
-    /// Create a video frame from an existing Arc'd BGRA buffer.
-    ///
-    /// This avoids an extra copy when the buffer is already reference-counted.
-    pub fn from_bgra_arc(buffer: Arc<Vec<u8>>, width: u32, height: u32) -> Self {
-        debug_assert_eq!(
-            buffer.len(),
-            (width * height * 4) as usize,
-            "BGRA buffer size mismatch"
-        );
-        Self {
-            data: VideoFrameData::Bgra(buffer),
-            width,
-            height,
-        }
-    }
-
-    /// Create a video frame from a macOS CoreVideo pixel buffer.
-    ///
-    /// This provides a zero-copy path on macOS.
-    #[cfg(target_os = "macos")]
-    pub fn from_cv_pixel_buffer(buffer: core_video::pixel_buffer::CVPixelBuffer) -> Self {
-        let width = buffer.get_width() as u32;
-        let height = buffer.get_height() as u32;
-        Self {
-            data: VideoFrameData::CoreVideo(buffer),
-            width,
-            height,
-        }
-    }
-
-    /// Create a video frame from a Windows D3D11 texture.
-    ///
-    /// This provides a zero-copy path on Windows when using hardware-accelerated
-    /// video decoding with Media Foundation.
-    ///
-    /// # Arguments
-    /// * `texture` - The D3D11 texture containing the decoded video frame
-    /// * `subresource_index` - The subresource index within the texture array (usually 0)
-    /// * `width` - The width of the video frame
-    /// * `height` - The height of the video frame
-    #[cfg(target_os = "windows")]
-    pub fn from_d3d11_texture(
-        texture: windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
-        subresource_index: u32,
-        width: u32,
-        height: u32,
-    ) -> Self {
-        Self {
-            data: VideoFrameData::D3D11 {
-                texture,
-                subresource_index,
-            },
-            width,
-            height,
-        }
-    }
-
-    /// Get the size of this video frame in pixels.
-    pub fn size(&self) -> (u32, u32) {
-        (self.width, self.height)
-    }
-
-    /// Get access to the raw pixel data, if this is a CPU-backed frame.
-    ///
-    /// Returns `None` for hardware-backed frames (e.g., CoreVideo on macOS, D3D11 on Windows).
-    pub fn as_bytes(&self) -> Option<&[u8]> {
-        match &self.data {
-            VideoFrameData::Bgra(buffer) => Some(buffer.as_slice()),
-            #[cfg(target_os = "macos")]
-            VideoFrameData::CoreVideo(_) => None,
-            #[cfg(target_os = "windows")]
-            VideoFrameData::D3D11 { .. } => None,
-        }
-    }
-}
+//! Video frame types for cross-platform video rendering.
+//!
+//! This module provides a platform-agnostic video frame type that can be used
+//! to render video content efficiently across different operating systems.
+
+#[cfg(feature = "rayon")]
+mod batch;
+mod borrowed;
+#[cfg_attr(
+    all(target_os = "macos", not(feature = "macos-blade")),
+    allow(dead_code)
+)]
+mod cache;
+#[cfg(feature = "ffmpeg")]
+mod ffmpeg;
+mod fps;
+#[cfg(feature = "gstreamer")]
+mod gstreamer;
+mod history;
+mod pacer;
+mod pattern;
+mod pool;
+mod serialization;
+mod stats;
+mod tone_map;
+mod transform;
+mod view;
+mod weak;
+#[cfg(feature = "livekit")]
+mod webrtc;
+#[cfg(feature = "wgpu")]
+mod wgpu;
+
+pub use borrowed::*;
+pub(crate) use cache::{PartialUpload, SurfacePixels, SurfaceUpload, next_buffer_id};
+pub use cache::{VideoCache, VideoCacheKey};
+pub use fps::*;
+pub use history::*;
+pub use pacer::*;
+pub use pool::*;
+#[cfg(feature = "video-stats")]
+pub use stats::VideoStats;
+pub(crate) use stats::{VideoStat, record_stat};
+pub use tone_map::*;
+pub use transform::*;
+pub use view::*;
+pub use weak::*;
+
+use crate::{BackgroundExecutor, Pixels, Size, Task, px, size};
+use collections::HashMap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    hash::{Hash as _, Hasher as _},
+    sync::Arc,
+    time::Duration,
+};
+use util::ResultExt as _;
+
+/// An error produced when a [`VideoFrame`] can't be built from the given data.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VideoFrameError {
+    /// The buffer holds fewer bytes than the frame's dimensions require.
+    #[error("video frame buffer too small: expected {expected} bytes, got {actual}")]
+    BufferTooSmall {
+        /// The number of bytes the frame requires.
+        expected: usize,
+        /// The number of bytes the buffer holds.
+        actual: usize,
+    },
+    /// The frame has a width or height of zero.
+    #[error("video frame has a zero width or height")]
+    ZeroDimension,
+    /// A row stride is too small to hold a full row of pixels, or isn't
+    /// aligned to whole pixels.
+    #[error("video frame stride {stride} is invalid for rows of {row_size} bytes")]
+    InvalidStride {
+        /// The stride that was provided.
+        stride: u32,
+        /// The minimum number of bytes a row needs.
+        row_size: u32,
+    },
+    /// The operation needs CPU-accessible pixels. Hardware-backed frames can be
+    /// read back with [`VideoFrame::to_bgra`] first.
+    #[error("operation is not supported for hardware-backed video frames")]
+    UnsupportedForHardwareFrame,
+    /// The operation doesn't support frames in this pixel format.
+    #[error("operation is not supported for {0:?} video frames")]
+    UnsupportedFormat(PixelFormat),
+    /// A frame from another library uses a pixel format that has no
+    /// [`PixelFormat`] equivalent.
+    #[error("source pixel format {0} is not supported")]
+    UnsupportedSourceFormat(String),
+    /// The pixels of a frame from another library, or of a hardware-backed
+    /// frame being read back, couldn't be accessed.
+    #[error("failed to access the source frame: {0}")]
+    SourceUnavailable(String),
+    /// The operation needs the frame's presentation timestamp, but none was set.
+    #[error("video frame has no presentation timestamp")]
+    MissingPts,
+    /// A region extends past the edges of the frame.
+    #[error("region is outside the bounds of the video frame")]
+    OutOfBounds,
+    /// The frame is larger than the [`FrameLimits`] it was checked against.
+    #[error("video frame exceeds the configured size limits")]
+    ExceedsLimits,
+    /// The pixel format packs pixels in pairs, but the frame has an odd width.
+    #[error("video frame width {0} must be even")]
+    OddWidth(u32),
+}
+
+/// Upper bounds on the size of frames built from untrusted sources, such as
+/// dimensions received over IPC or the network, so that a malicious or buggy
+/// producer can't make the receiver allocate an enormous buffer.
+///
+/// See [`VideoFrame::try_from_raw_with_limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameLimits {
+    /// The largest accepted width, in pixels.
+    pub max_width: u32,
+    /// The largest accepted height, in pixels.
+    pub max_height: u32,
+    /// The largest accepted amount of pixel data, in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for FrameLimits {
+    /// 16384x16384 pixels, and the 1 GiB a BGRA frame of that size takes.
+    fn default() -> Self {
+        Self {
+            max_width: 16384,
+            max_height: 16384,
+            max_bytes: 16384 * 16384 * 4,
+        }
+    }
+}
+
+impl FrameLimits {
+    /// Check that a frame of the given size and format is within these
+    /// limits, such as before allocating a buffer to receive its pixels into.
+    ///
+    /// The data size is that of the frame [`VideoFrame::from_raw`] builds,
+    /// so RGB frames count the BGRA buffer they're expanded to.
+    pub fn check(
+        &self,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> Result<(), VideoFrameError> {
+        if width > self.max_width || height > self.max_height {
+            return Err(VideoFrameError::ExceedsLimits);
+        }
+        let pixel_count = width as u64 * height as u64;
+        let chroma_count = width.div_ceil(2) as u64 * height.div_ceil(2) as u64;
+        let bytes = match format {
+            PixelFormat::Bgra8 | PixelFormat::Rgba8 | PixelFormat::Rgb8 => pixel_count * 4,
+            PixelFormat::Nv12 | PixelFormat::I420 => pixel_count + chroma_count * 2,
+            PixelFormat::P010 => (pixel_count + chroma_count * 2) * 2,
+            PixelFormat::Yuyv => pixel_count * 2,
+            PixelFormat::Rgba16Unorm | PixelFormat::Rgba16Float => pixel_count * 8,
+        };
+        if bytes > self.max_bytes as u64 {
+            return Err(VideoFrameError::ExceedsLimits);
+        }
+        Ok(())
+    }
+}
+
+/// The layout of the pixel data in a [`VideoFrame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PixelFormat {
+    /// Packed 8-bit blue, green, red and alpha channels.
+    Bgra8,
+    /// Packed 8-bit red, green, blue and alpha channels.
+    Rgba8,
+    /// Packed 8-bit red, green and blue channels, without alpha.
+    Rgb8,
+    /// An 8-bit luma plane followed by a half-resolution plane of interleaved
+    /// Cb/Cr samples.
+    Nv12,
+    /// An 8-bit luma plane followed by separate half-resolution Cb and Cr planes.
+    I420,
+    /// Like [`PixelFormat::Nv12`], but with 16-bit little-endian samples that
+    /// hold 10-bit values in their most significant bits.
+    P010,
+    /// Packed 8-bit 4:2:2 YUV, where each pair of pixels is stored as Y0, Cb,
+    /// Y1, Cr and shares one chroma sample. The width must be even.
+    Yuyv,
+    /// Packed 16-bit unsigned normalized red, green, blue and alpha channels,
+    /// stored little-endian.
+    Rgba16Unorm,
+    /// Packed 16-bit half-float red, green, blue and alpha channels, stored
+    /// little-endian.
+    Rgba16Float,
+}
+
+/// The color space of a video frame, which determines the matrix used to
+/// convert YUV samples to RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum VideoColorSpace {
+    /// ITU-R BT.601, used by standard-definition video and most webcams.
+    Bt601,
+    /// ITU-R BT.709, used by high-definition video.
+    Bt709,
+    /// ITU-R BT.2020, used by ultra-high-definition and HDR video.
+    Bt2020,
+    /// Full-range sRGB. This is the color space of BGRA and RGBA frames.
+    #[default]
+    Srgb,
+}
+
+impl VideoColorSpace {
+    /// The luma weights of red and blue, `(Kr, Kb)`, for this color space.
+    /// sRGB shares its primaries with BT.709.
+    fn luma_coefficients(self) -> (f32, f32) {
+        match self {
+            VideoColorSpace::Bt601 => (0.299, 0.114),
+            VideoColorSpace::Bt709 | VideoColorSpace::Srgb => (0.2126, 0.0722),
+            VideoColorSpace::Bt2020 => (0.2627, 0.0593),
+        }
+    }
+
+    /// The matrix from linear RGB with this color space's primaries to CIE
+    /// XYZ, all with a D65 white point. BT.601 uses the SMPTE 170M primaries.
+    fn rgb_to_xyz(self) -> [[f32; 3]; 3] {
+        match self {
+            VideoColorSpace::Bt601 => [
+                [0.393_589, 0.365_250, 0.191_631],
+                [0.212_413, 0.701_044, 0.086_543],
+                [0.018_742, 0.111_931, 0.958_156],
+            ],
+            VideoColorSpace::Bt709 | VideoColorSpace::Srgb => [
+                [0.412_391, 0.357_584, 0.180_481],
+                [0.212_639, 0.715_169, 0.072_192],
+                [0.019_331, 0.119_195, 0.950_532],
+            ],
+            VideoColorSpace::Bt2020 => [
+                [0.636_958, 0.144_617, 0.168_881],
+                [0.262_700, 0.677_998, 0.059_302],
+                [0.0, 0.028_073, 1.060_985],
+            ],
+        }
+    }
+}
+
+/// The range of values the YUV samples of a frame span, as recorded by the
+/// source of the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ColorRange {
+    /// Limited or "TV" range, used by most decoded video: 8-bit luma spans
+    /// 16–235 and chroma 16–240, scaled up for higher bit depths.
+    Limited,
+    /// Full or "PC" range, where samples span every value of their bit depth.
+    /// RGB frames are always full range.
+    #[default]
+    Full,
+}
+
+/// How the alpha channel of a frame relates to its color channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum AlphaMode {
+    /// The alpha channel carries no information and the frame is drawn fully
+    /// opaque, as is common for camera and screen capture.
+    Opaque,
+    /// Color channels are independent of alpha.
+    #[default]
+    Straight,
+    /// Color channels have already been multiplied by alpha.
+    Premultiplied,
+}
+
+/// How much of a frame its alpha channel covers, as returned by
+/// [`VideoFrame::alpha_coverage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlphaCoverage {
+    /// Every pixel is fully opaque, so the frame can be drawn without blending.
+    FullyOpaque,
+    /// Every pixel is fully transparent, so drawing the frame can be skipped.
+    FullyTransparent,
+    /// Some pixels are translucent, or some are opaque and others transparent.
+    Mixed,
+}
+
+/// Where the pixels of a [`VideoFrame`] live, as returned by [`VideoFrame::backend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VideoFrameBackend {
+    /// A CPU buffer of packed BGRA pixels.
+    CpuBgra,
+    /// A CPU buffer of packed RGBA pixels.
+    CpuRgba,
+    /// A CPU buffer of packed RGB pixels.
+    CpuRgb,
+    /// CPU buffers of NV12 planes.
+    CpuNv12,
+    /// CPU buffers of I420 planes.
+    CpuI420,
+    /// CPU buffers of P010 planes.
+    CpuP010,
+    /// A CPU buffer of packed YUYV pixels.
+    CpuYuyv,
+    /// A CPU buffer of packed 16-bit RGBA pixels.
+    CpuRgba16,
+    /// A macOS CoreVideo pixel buffer.
+    CoreVideo,
+    /// A Windows D3D11 texture.
+    D3D11,
+    /// A Linux DMA-BUF.
+    DmaBuf,
+    /// A wgpu texture.
+    Wgpu,
+    /// The buffers of a frame decoded by FFmpeg.
+    FFmpeg,
+}
+
+/// A video frame that can be painted to the screen.
+///
+/// This type abstracts over platform-specific video buffer types,
+/// allowing efficient video rendering on all supported platforms.
+#[derive(Clone)]
+pub struct VideoFrame {
+    pub(crate) data: VideoFrameData,
+    /// The width of the video frame in pixels.
+    pub width: u32,
+    /// The height of the video frame in pixels.
+    pub height: u32,
+    pts: Option<Duration>,
+    duration: Option<Duration>,
+    color_space: VideoColorSpace,
+    color_range: ColorRange,
+    alpha_mode: AlphaMode,
+    display_rotation: Rotation,
+    metadata: Option<Arc<HashMap<String, Vec<u8>>>>,
+    on_drop: Option<Arc<DropCallback>>,
+    /// Identifies the pixels in [`VideoCacheKey`]. Clones share it, and it's
+    /// replaced whenever the pixels change.
+    buffer_id: u64,
+}
+
+/// Calls a callback when dropped, once the last frame sharing it is gone.
+struct DropCallback(Mutex<Option<Box<dyn FnOnce() + Send>>>);
+
+impl Drop for DropCallback {
+    fn drop(&mut self) {
+        if let Some(callback) = self.0.get_mut().take() {
+            callback();
+        }
+    }
+}
+
+/// The inner data of a video frame.
+///
+/// CPU buffers are kept as `Arc<Vec<u8>>` rather than `Arc<[u8]>` so that a
+/// frame can take ownership of a `Vec` without copying it, and so that a
+/// uniquely held buffer can be unwrapped back into a `Vec`, which
+/// [`VideoFramePool`] and [`VideoFrame::into_bgra_vec`] rely on. The extra
+/// indirection is paid once per plane access, not once per pixel.
+#[derive(Clone)]
+pub(crate) enum VideoFrameData {
+    /// A CPU buffer in BGRA format.
+    /// This is the fallback format that works on all platforms.
+    /// Rows are `stride` bytes apart, which may include trailing padding.
+    Bgra { buffer: Arc<Vec<u8>>, stride: u32 },
+
+    /// A CPU buffer in RGBA format, with rows `stride` bytes apart.
+    Rgba { buffer: Arc<Vec<u8>>, stride: u32 },
+
+    /// A tightly packed CPU buffer of 8-bit RGB pixels without alpha, expanded
+    /// to BGRA only when the frame is converted or painted.
+    Rgb { buffer: Arc<Vec<u8>> },
+
+    /// A CPU buffer in NV12 format, as produced by most hardware decoders.
+    /// The luma plane is full resolution, the chroma plane holds interleaved
+    /// Cb/Cr samples at half resolution in both dimensions.
+    Nv12 {
+        y_plane: Arc<Vec<u8>>,
+        uv_plane: Arc<Vec<u8>>,
+        y_stride: u32,
+        uv_stride: u32,
+    },
+
+    /// A CPU buffer in I420 format, with separate Y, U and V planes. The chroma
+    /// planes are half resolution in both dimensions, rounded up.
+    I420 {
+        y: Arc<Vec<u8>>,
+        u: Arc<Vec<u8>>,
+        v: Arc<Vec<u8>>,
+        y_stride: u32,
+        u_stride: u32,
+        v_stride: u32,
+    },
+
+    /// A CPU buffer in P010 format, laid out like NV12 but with 16-bit samples
+    /// holding 10-bit values in their high bits. Strides are in samples.
+    P010 {
+        y_plane: Arc<Vec<u16>>,
+        uv_plane: Arc<Vec<u16>>,
+        y_stride: u32,
+        uv_stride: u32,
+    },
+
+    /// A CPU buffer of packed 4:2:2 YUYV pixel pairs, with rows `stride`
+    /// bytes apart, for consumers that only accept packed YUV.
+    Yuyv { buffer: Arc<Vec<u8>>, stride: u32 },
+
+    /// A tightly packed CPU buffer of 16-bit RGBA pixels, little-endian, with
+    /// half-float channels if `float` is set and unsigned normalized ones
+    /// otherwise. This keeps the precision of HDR compositing until the frame
+    /// is painted.
+    Rgba16 { buffer: Arc<Vec<u8>>, float: bool },
+
+    /// A macOS CoreVideo pixel buffer (zero-copy path).
+    #[cfg(target_os = "macos")]
+    CoreVideo(SafeCVPixelBuffer),
+
+    /// A Windows D3D11 texture (zero-copy path).
+    #[cfg(target_os = "windows")]
+    D3D11 {
+        texture: SafeD3D11Texture,
+        subresource_index: u32,
+    },
+
+    /// A Linux DMA-BUF, as produced by PipeWire, V4L2 and VA-API (zero-copy path).
+    /// The file descriptor is closed when the last frame referencing it is dropped.
+    #[cfg(target_os = "linux")]
+    DmaBuf {
+        fd: Arc<std::os::fd::OwnedFd>,
+        fourcc: u32,
+        modifier: u64,
+        stride: u32,
+        offset: u32,
+    },
+
+    /// A wgpu texture owned by the application (zero-copy path). Only the
+    /// frame's `width` by `height` region at the texture's origin is shown.
+    #[cfg(feature = "wgpu")]
+    Wgpu(::wgpu::Texture),
+
+    /// A frame decoded by FFmpeg, holding a reference to its buffers
+    /// (zero-copy path). Only the frame's `width` by `height` region at the
+    /// origin is shown.
+    #[cfg(feature = "ffmpeg")]
+    FFmpeg(Arc<ffmpeg_next::frame::Video>),
+}
+
+impl std::fmt::Debug for VideoFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VideoFrame")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("format", &self.pixel_format())
+            .field("color_space", &self.color_space)
+            .field("bytes_per_row", &self.bytes_per_row())
+            .field("footprint", &self.memory_footprint())
+            .field("data", &format!("{:?}", self.backend()))
+            .finish()
+    }
+}
+
+/// A one-line summary of the frame's size, pixel format, color space and
+/// memory footprint, such as `VideoFrame 1920x1080 Bgra8 Srgb 8.3MB`.
+impl std::fmt::Display for VideoFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "VideoFrame {}x{} {:?} {:?} ",
+            self.width,
+            self.height,
+            self.pixel_format(),
+            self.color_space
+        )?;
+        let footprint = self.memory_footprint();
+        if footprint >= 1_000_000 {
+            write!(f, "{:.1}MB", footprint as f64 / 1_000_000.0)
+        } else if footprint >= 1_000 {
+            write!(f, "{:.1}KB", footprint as f64 / 1_000.0)
+        } else {
+            write!(f, "{footprint}B")
+        }
+    }
+}
+
+/// Frames are equal when they have the same size, the same pixel format and
+/// the same visible pixels. Row padding is not compared, and two frames with
+/// different pixel formats are never equal, even if they would look the same.
+/// Hardware-backed frames are only equal to frames sharing the same underlying
+/// buffer.
+impl PartialEq for VideoFrame {
+    fn eq(&self, other: &Self) -> bool {
+        if self.width != other.width
+            || self.height != other.height
+            || self.pixel_format() != other.pixel_format()
+        {
+            return false;
+        }
+        match (self.cpu_planes(), other.cpu_planes()) {
+            (Some(planes), Some(other_planes)) => planes
+                .iter()
+                .zip(other_planes.iter())
+                .all(|(plane, other_plane)| plane.rows().eq(other_plane.rows())),
+            _ => self.shares_hardware_buffer(other),
+        }
+    }
+}
+
+/// Create a BGRA frame from a buffer of `width * height * 4` bytes, as in
+/// [`VideoFrame::try_from_bgra`].
+impl TryFrom<(Vec<u8>, u32, u32)> for VideoFrame {
+    type Error = VideoFrameError;
+
+    fn try_from((buffer, width, height): (Vec<u8>, u32, u32)) -> Result<Self, Self::Error> {
+        Self::try_from_bgra(buffer, width, height)
+    }
+}
+
+/// Create an RGBA frame from an image without copying its pixels, as in
+/// [`VideoFrame::try_from_rgba`].
+impl TryFrom<image::RgbaImage> for VideoFrame {
+    type Error = VideoFrameError;
+
+    fn try_from(image: image::RgbaImage) -> Result<Self, Self::Error> {
+        let (width, height) = (image.width(), image.height());
+        Self::try_from_rgba(image.into_raw(), width, height)
+    }
+}
+
+impl VideoFrame {
+    fn new(data: VideoFrameData, width: u32, height: u32) -> Self {
+        let mut frame = Self {
+            data,
+            width,
+            height,
+            pts: None,
+            duration: None,
+            color_space: VideoColorSpace::default(),
+            color_range: ColorRange::default(),
+            alpha_mode: AlphaMode::default(),
+            display_rotation: Rotation::default(),
+            metadata: None,
+            on_drop: None,
+            buffer_id: next_buffer_id(),
+        };
+        match frame.pixel_format() {
+            PixelFormat::Nv12 | PixelFormat::I420 | PixelFormat::Yuyv => {
+                frame.color_space = VideoColorSpace::Bt601
+            }
+            PixelFormat::P010 => frame.color_space = VideoColorSpace::Bt2020,
+            PixelFormat::Rgb8 => frame.alpha_mode = AlphaMode::Opaque,
+            PixelFormat::Bgra8
+            | PixelFormat::Rgba8
+            | PixelFormat::Rgba16Unorm
+            | PixelFormat::Rgba16Float => {}
+        }
+        frame
+    }
+
+    /// Create a video frame from raw BGRA pixel data.
+    ///
+    /// The buffer should contain `width * height * 4` bytes in BGRA format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer doesn't match the dimensions. Use [`Self::try_from_bgra`]
+    /// for buffers that come from untrusted sources.
+    pub fn from_bgra(buffer: Vec<u8>, width: u32, height: u32) -> Self {
+        Self::try_from_bgra(buffer, width, height).expect("invalid BGRA buffer")
+    }
+
+    /// Create a video frame from raw BGRA pixel data, validating the buffer size.
+    ///
+    /// The buffer must contain at least `width * height * 4` bytes in BGRA format.
+    pub fn try_from_bgra(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        Self::try_from_bgra_arc(Arc::new(buffer), width, height)
+    }
+
+    /// Create a video frame from raw BGRA pixel data without validating the
+    /// buffer or the dimensions, for hot paths such as capture loops whose
+    /// buffers are already known to fit.
+    ///
+    /// # Safety
+    ///
+    /// `width` and `height` must be non-zero, `width * height * 4` must not
+    /// overflow a `u32`, and the buffer must contain at least
+    /// `width * height * 4` bytes. Renderers and conversions rely on this to
+    /// copy the frame's rows with unchecked pointer arithmetic, so breaking it
+    /// is undefined behavior, such as reading past the end of the buffer.
+    pub unsafe fn from_bgra_unchecked(buffer: Vec<u8>, width: u32, height: u32) -> Self {
+        Self::new(
+            VideoFrameData::Bgra {
+                buffer: Arc::new(buffer),
+                stride: width * 4,
+            },
+            width,
+            height,
+        )
+    }
+
+    /// Create a video frame from an existing Arc'd BGRA buffer.
+    ///
+    /// This avoids an extra copy when the buffer is already reference-counted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer doesn't match the dimensions. Use [`Self::try_from_bgra_arc`]
+    /// for buffers that come from untrusted sources.
+    pub fn from_bgra_arc(buffer: Arc<Vec<u8>>, width: u32, height: u32) -> Self {
+        Self::try_from_bgra_arc(buffer, width, height).expect("invalid BGRA buffer")
+    }
+
+    /// Create a video frame from an existing Arc'd BGRA buffer, validating the buffer size.
+    pub fn try_from_bgra_arc(
+        buffer: Arc<Vec<u8>>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        let stride = row_size(width, 4)?;
+        Self::try_from_bgra_arc_with_stride(buffer, width, height, stride)
+    }
+
+    /// Create a video frame from BGRA pixel data whose rows are padded.
+    ///
+    /// Each row starts `stride` bytes after the previous one, so the buffer
+    /// should contain `stride * height` bytes, with `stride >= width * 4`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer or stride doesn't match the dimensions. Use
+    /// [`Self::try_from_bgra_with_stride`] for buffers that come from untrusted sources.
+    pub fn from_bgra_with_stride(buffer: Vec<u8>, width: u32, height: u32, stride: u32) -> Self {
+        Self::try_from_bgra_with_stride(buffer, width, height, stride).expect("invalid BGRA buffer")
+    }
+
+    /// Create a video frame from padded BGRA pixel data, validating the buffer size and stride.
+    pub fn try_from_bgra_with_stride(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+        stride: u32,
+    ) -> Result<Self, VideoFrameError> {
+        Self::try_from_bgra_arc_with_stride(Arc::new(buffer), width, height, stride)
+    }
+
+    fn try_from_bgra_arc_with_stride(
+        buffer: Arc<Vec<u8>>,
+        width: u32,
+        height: u32,
+        stride: u32,
+    ) -> Result<Self, VideoFrameError> {
+        validate_packed(&buffer, width, height, stride)?;
+        Ok(Self::new(
+            VideoFrameData::Bgra { buffer, stride },
+            width,
+            height,
+        ))
+    }
+
+    /// Create a video frame from raw BGRA pixel data, calling `on_drop` once
+    /// the frame and all of its clones have been dropped.
+    ///
+    /// This lets a producer that hands out frames from a fixed set of buffers,
+    /// such as a decoder or capture ring, learn when a frame is no longer in
+    /// use. The callback runs on whichever thread drops the last reference,
+    /// which may be the main thread or a background thread, so it should be
+    /// quick and must not block. Frames derived from this one by conversion
+    /// or transformation own new pixels and don't keep the callback alive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer doesn't match the dimensions. Use
+    /// [`Self::try_from_bgra_with_drop`] for buffers that come from untrusted sources.
+    pub fn from_bgra_with_drop(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+        on_drop: impl FnOnce() + Send + 'static,
+    ) -> Self {
+        Self::try_from_bgra_with_drop(buffer, width, height, on_drop).expect("invalid BGRA buffer")
+    }
+
+    /// Create a video frame from raw BGRA pixel data that calls `on_drop` once
+    /// it's dropped, validating the buffer size.
+    ///
+    /// If the buffer is invalid, `on_drop` is dropped without being called.
+    pub fn try_from_bgra_with_drop(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+        on_drop: impl FnOnce() + Send + 'static,
+    ) -> Result<Self, VideoFrameError> {
+        Ok(Self::try_from_bgra(buffer, width, height)?.with_drop_callback(on_drop))
+    }
+
+    /// Call `on_drop` once this frame and all of its clones have been dropped.
+    fn with_drop_callback(mut self, on_drop: impl FnOnce() + Send + 'static) -> Self {
+        self.on_drop = Some(Arc::new(DropCallback(Mutex::new(Some(Box::new(on_drop))))));
+        self
+    }
+
+    /// Replace the pixels of this frame with a tightly packed BGRA buffer of
+    /// `width * height * 4` bytes, keeping its timing, color metadata,
+    /// rotation and metadata, such as for a capture source that alternates
+    /// between buffers behind a single frame handle.
+    ///
+    /// Only this handle changes: clones made before the call keep the
+    /// previous pixels, which are freed once the last of them is dropped, and
+    /// a callback from [`Self::from_bgra_with_drop`] stays with them. The new
+    /// buffer may be shared with other frames through its `Arc`, in which case
+    /// methods that modify pixels in place copy it first. The next paint
+    /// uploads the new pixels rather than reusing a cached upload.
+    ///
+    /// Returns an error and leaves the frame as it was if the buffer doesn't
+    /// match the dimensions.
+    pub fn replace_bgra(
+        &mut self,
+        buffer: Arc<Vec<u8>>,
+        width: u32,
+        height: u32,
+    ) -> Result<(), VideoFrameError> {
+        let stride = row_size(width, 4)?;
+        validate_packed(&buffer, width, height, stride)?;
+        self.data = VideoFrameData::Bgra { buffer, stride };
+        self.width = width;
+        self.height = height;
+        self.on_drop = None;
+        self.buffer_id = next_buffer_id();
+        Ok(())
+    }
+
+    /// Create a video frame from raw RGBA pixel data.
+    ///
+    /// The buffer should contain `width * height * 4` bytes in RGBA format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer doesn't match the dimensions. Use [`Self::try_from_rgba`]
+    /// for buffers that come from untrusted sources.
+    pub fn from_rgba(buffer: Vec<u8>, width: u32, height: u32) -> Self {
+        Self::try_from_rgba(buffer, width, height).expect("invalid RGBA buffer")
+    }
+
+    /// Create a video frame from raw RGBA pixel data, validating the buffer size.
+    pub fn try_from_rgba(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        Self::try_from_rgba_arc(Arc::new(buffer), width, height)
+    }
+
+    /// Create a video frame from an existing Arc'd RGBA buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer doesn't match the dimensions. Use [`Self::try_from_rgba_arc`]
+    /// for buffers that come from untrusted sources.
+    pub fn from_rgba_arc(buffer: Arc<Vec<u8>>, width: u32, height: u32) -> Self {
+        Self::try_from_rgba_arc(buffer, width, height).expect("invalid RGBA buffer")
+    }
+
+    /// Create a video frame from an existing Arc'd RGBA buffer, validating the buffer size.
+    pub fn try_from_rgba_arc(
+        buffer: Arc<Vec<u8>>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        let stride = row_size(width, 4)?;
+        validate_packed(&buffer, width, height, stride)?;
+        Ok(Self::new(
+            VideoFrameData::Rgba { buffer, stride },
+            width,
+            height,
+        ))
+    }
+
+    /// Create a video frame from packed 8-bit RGB pixel data, without alpha.
+    ///
+    /// The buffer should contain `width * height * 3` bytes. The pixels are
+    /// kept as RGB, using a quarter less memory than BGRA, and are treated as
+    /// fully opaque.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer doesn't match the dimensions. Use [`Self::try_from_rgb8`]
+    /// for buffers that come from untrusted sources.
+    pub fn from_rgb8(buffer: Vec<u8>, width: u32, height: u32) -> Self {
+        Self::try_from_rgb8(buffer, width, height).expect("invalid RGB buffer")
+    }
+
+    /// Create a video frame from packed 8-bit RGB pixel data, validating the
+    /// buffer size.
+    pub fn try_from_rgb8(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        let row_size = row_size(width, 3)?;
+        validate_plane(buffer.len(), row_size, row_size, height)?;
+        Ok(Self::new(
+            VideoFrameData::Rgb {
+                buffer: Arc::new(buffer),
+            },
+            width,
+            height,
+        ))
+    }
+
+    /// Create a video frame from packed 16-bit unsigned normalized RGBA pixel
+    /// data, validating that the buffer holds `width * height * 8` bytes.
+    ///
+    /// Each channel is a little-endian `u16`, where `u16::MAX` is full
+    /// intensity. The frame keeps all 16 bits until it's painted or converted
+    /// with [`Self::to_bgra`].
+    pub fn from_rgba16_unorm(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        Self::from_rgba16(buffer, width, height, false)
+    }
+
+    /// Create a video frame from packed 16-bit half-float RGBA pixel data,
+    /// validating that the buffer holds `width * height * 8` bytes.
+    ///
+    /// Each channel is a little-endian IEEE 754 half-precision float. Values
+    /// outside `0.0..=1.0`, such as HDR highlights, are kept until the frame is
+    /// painted or converted with [`Self::to_bgra`], which clamps them.
+    pub fn from_rgba16_float(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        Self::from_rgba16(buffer, width, height, true)
+    }
+
+    fn from_rgba16(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+        float: bool,
+    ) -> Result<Self, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        let row_size = row_size(width, 8)?;
+        validate_plane(buffer.len(), row_size, row_size, height)?;
+        Ok(Self::new(
+            VideoFrameData::Rgba16 {
+                buffer: Arc::new(buffer),
+                float,
+            },
+            width,
+            height,
+        ))
+    }
+
+    /// Create a video frame from a buffer of pixel data in the given format.
+    ///
+    /// Rows must be tightly packed. Planar formats store their planes back to
+    /// back in the buffer, luma first, with chroma planes of `(width + 1) / 2`
+    /// by `(height + 1) / 2` samples. The buffer size is validated against the
+    /// layout of the format.
+    ///
+    /// [`PixelFormat::Rgb8`] is expanded to BGRA on the way in, so
+    /// [`Self::format`] can differ from `format`; use [`Self::from_rgb8`] to keep
+    /// the pixels as RGB.
+    ///
+    /// 8-bit YUV frames are assumed to be [`VideoColorSpace::Bt601`] and P010 frames
+    /// [`VideoColorSpace::Bt2020`]; use [`Self::with_color_space`] if the source says
+    /// otherwise. P010 and 16-bit RGBA samples are read as little-endian. YUYV
+    /// frames must have an even width.
+    pub fn from_raw(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> Result<Self, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        let pixel_count = width as usize * height as usize;
+        let chroma_width = width.div_ceil(2);
+        let chroma_count = chroma_width as usize * height.div_ceil(2) as usize;
+        match format {
+            PixelFormat::Bgra8 => Self::try_from_bgra(buffer, width, height),
+            PixelFormat::Rgba8 => Self::try_from_rgba(buffer, width, height),
+            PixelFormat::Rgb8 => {
+                validate_len(buffer.len(), pixel_count.saturating_mul(3))?;
+                let mut bgra = Vec::with_capacity(pixel_count * 4);
+                for pixel in buffer.chunks_exact(3).take(pixel_count) {
+                    bgra.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+                }
+                Ok(Self::from_packed(bgra, width, height, PixelFormat::Bgra8))
+            }
+            PixelFormat::Nv12 => {
+                validate_len(buffer.len(), pixel_count + chroma_count * 2)?;
+                let mut y_plane = buffer;
+                let uv_plane = y_plane.split_off(pixel_count);
+                Self::try_from_nv12(
+                    y_plane,
+                    uv_plane,
+                    width,
+                    chroma_width * 2,
+                    width,
+                    height,
+                    VideoColorSpace::Bt601,
+                )
+            }
+            PixelFormat::I420 => {
+                validate_len(buffer.len(), pixel_count + chroma_count * 2)?;
+                let mut y = buffer;
+                let mut u = y.split_off(pixel_count);
+                let v = u.split_off(chroma_count);
+                Self::try_from_i420(
+                    y,
+                    u,
+                    v,
+                    width,
+                    chroma_width,
+                    chroma_width,
+                    width,
+                    height,
+                    VideoColorSpace::Bt601,
+                )
+            }
+            PixelFormat::P010 => {
+                validate_len(buffer.len(), (pixel_count + chroma_count * 2) * 2)?;
+                let mut y_plane = buffer
+                    .chunks_exact(2)
+                    .map(|sample| u16::from_le_bytes([sample[0], sample[1]]))
+                    .collect::<Vec<_>>();
+                let uv_plane = y_plane.split_off(pixel_count);
+                Self::try_from_p010(
+                    y_plane,
+                    uv_plane,
+                    width,
+                    chroma_width * 2,
+                    width,
+                    height,
+                    VideoColorSpace::Bt2020,
+                )
+            }
+            PixelFormat::Yuyv => {
+                let row_size = row_size(width, 2)?;
+                validate_len(buffer.len(), row_size as usize * height as usize)?;
+                let frame = Self::new(
+                    VideoFrameData::Yuyv {
+                        buffer: Arc::new(buffer),
+                        stride: row_size,
+                    },
+                    width,
+                    height,
+                );
+                frame.validate()?;
+                Ok(frame)
+            }
+            PixelFormat::Rgba16Unorm => Self::from_rgba16_unorm(buffer, width, height),
+            PixelFormat::Rgba16Float => Self::from_rgba16_float(buffer, width, height),
+        }
+    }
+
+    /// Create a video frame like [`Self::from_raw`], but reject frames larger
+    /// than `limits` with [`VideoFrameError::ExceedsLimits`] before converting
+    /// or copying the buffer.
+    ///
+    /// Use this when the dimensions come from an untrusted source. The other
+    /// constructors don't limit the frame size.
+    pub fn try_from_raw_with_limits(
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        limits: &FrameLimits,
+    ) -> Result<Self, VideoFrameError> {
+        limits.check(width, height, format)?;
+        Self::from_raw(buffer, width, height, format)
+    }
+
+    /// Create a tightly packed frame from a buffer that is known to be valid.
+    fn from_packed(buffer: Vec<u8>, width: u32, height: u32, format: PixelFormat) -> Self {
+        let buffer = Arc::new(buffer);
+        let stride = width * 4;
+        let data = match format {
+            PixelFormat::Rgba8 => VideoFrameData::Rgba { buffer, stride },
+            _ => VideoFrameData::Bgra { buffer, stride },
+        };
+        Self::new(data, width, height)
+    }
+
+    /// Create a video frame from NV12 planes.
+    ///
+    /// The luma plane should contain at least `y_stride * height` bytes and the
+    /// interleaved chroma plane at least `uv_stride * ((height + 1) / 2)` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the planes or strides don't match the dimensions. Use
+    /// [`Self::try_from_nv12`] for planes that come from untrusted sources.
+    pub fn from_nv12(
+        y_plane: Vec<u8>,
+        uv_plane: Vec<u8>,
+        y_stride: u32,
+        uv_stride: u32,
+        width: u32,
+        height: u32,
+        color_space: VideoColorSpace,
+    ) -> Self {
+        Self::try_from_nv12(
+            y_plane,
+            uv_plane,
+            y_stride,
+            uv_stride,
+            width,
+            height,
+            color_space,
+        )
+        .expect("invalid NV12 planes")
+    }
+
+    /// Create a video frame from NV12 planes, validating the plane sizes and strides.
+    pub fn try_from_nv12(
+        y_plane: Vec<u8>,
+        uv_plane: Vec<u8>,
+        y_stride: u32,
+        uv_stride: u32,
+        width: u32,
+        height: u32,
+        color_space: VideoColorSpace,
+    ) -> Result<Self, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        validate_plane(y_plane.len(), width, y_stride, height)?;
+        validate_plane(
+            uv_plane.len(),
+            row_size(width.div_ceil(2), 2)?,
+            uv_stride,
+            height.div_ceil(2),
+        )?;
+        Ok(Self::new(
+            VideoFrameData::Nv12 {
+                y_plane: Arc::new(y_plane),
+                uv_plane: Arc::new(uv_plane),
+                y_stride,
+                uv_stride,
+            },
+            width,
+            height,
+        )
+        .with_color_space(color_space))
+    }
+
+    /// Create a video frame from I420 planes.
+    ///
+    /// The luma plane should contain at least `y_stride * height` bytes. The U
+    /// and V planes hold `(width + 1) / 2` by `(height + 1) / 2` samples, so each
+    /// should contain at least its stride times `(height + 1) / 2` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the planes or strides don't match the dimensions. Use
+    /// [`Self::try_from_i420`] for planes that come from untrusted sources.
+    pub fn from_i420(
+        y: Vec<u8>,
+        u: Vec<u8>,
+        v: Vec<u8>,
+        y_stride: u32,
+        u_stride: u32,
+        v_stride: u32,
+        width: u32,
+        height: u32,
+        color_space: VideoColorSpace,
+    ) -> Self {
+        Self::try_from_i420(
+            y,
+            u,
+            v,
+            y_stride,
+            u_stride,
+            v_stride,
+            width,
+            height,
+            color_space,
+        )
+        .expect("invalid I420 planes")
+    }
+
+    /// Create a video frame from I420 planes, validating the plane sizes and strides.
+    pub fn try_from_i420(
+        y: Vec<u8>,
+        u: Vec<u8>,
+        v: Vec<u8>,
+        y_stride: u32,
+        u_stride: u32,
+        v_stride: u32,
+        width: u32,
+        height: u32,
+        color_space: VideoColorSpace,
+    ) -> Result<Self, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        let chroma_width = width.div_ceil(2);
+        let chroma_height = height.div_ceil(2);
+        validate_plane(y.len(), width, y_stride, height)?;
+        validate_plane(u.len(), chroma_width, u_stride, chroma_height)?;
+        validate_plane(v.len(), chroma_width, v_stride, chroma_height)?;
+        Ok(Self::new(
+            VideoFrameData::I420 {
+                y: Arc::new(y),
+                u: Arc::new(u),
+                v: Arc::new(v),
+                y_stride,
+                u_stride,
+                v_stride,
+            },
+            width,
+            height,
+        )
+        .with_color_space(color_space))
+    }
+
+    /// Create a video frame from P010 planes, carrying 10-bit HDR content
+    /// without reducing it to 8 bits.
+    ///
+    /// The planes are laid out like NV12, with strides measured in 16-bit
+    /// samples rather than bytes: the luma plane should contain at least
+    /// `y_stride * height` samples and the interleaved chroma plane at least
+    /// `uv_stride * ((height + 1) / 2)` samples. Use [`VideoColorSpace::Bt2020`] to
+    /// signal PQ-encoded HDR content.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the planes or strides don't match the dimensions. Use
+    /// [`Self::try_from_p010`] for planes that come from untrusted sources.
+    pub fn from_p010(
+        y_plane: Vec<u16>,
+        uv_plane: Vec<u16>,
+        y_stride: u32,
+        uv_stride: u32,
+        width: u32,
+        height: u32,
+        color_space: VideoColorSpace,
+    ) -> Self {
+        Self::try_from_p010(
+            y_plane,
+            uv_plane,
+            y_stride,
+            uv_stride,
+            width,
+            height,
+            color_space,
+        )
+        .expect("invalid P010 planes")
+    }
+
+    /// Create a video frame from P010 planes, validating the plane sizes and strides.
+    pub fn try_from_p010(
+        y_plane: Vec<u16>,
+        uv_plane: Vec<u16>,
+        y_stride: u32,
+        uv_stride: u32,
+        width: u32,
+        height: u32,
+        color_space: VideoColorSpace,
+    ) -> Result<Self, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        validate_plane(y_plane.len(), width, y_stride, height)?;
+        validate_plane(
+            uv_plane.len(),
+            row_size(width.div_ceil(2), 2)?,
+            uv_stride,
+            height.div_ceil(2),
+        )?;
+        Ok(Self::new(
+            VideoFrameData::P010 {
+                y_plane: Arc::new(y_plane),
+                uv_plane: Arc::new(uv_plane),
+                y_stride,
+                uv_stride,
+            },
+            width,
+            height,
+        )
+        .with_color_space(color_space))
+    }
+
+    /// Create a video frame from a macOS CoreVideo pixel buffer.
+    ///
+    /// This provides a zero-copy path on macOS. Frames in the video-range
+    /// 8-bit and 10-bit bi-planar formats are marked as [`ColorRange::Limited`].
+    ///
+    /// The frame retains the pixel buffer, each clone retains it again and each
+    /// drop releases it, so the frame can be sent to other threads, such as for
+    /// [`Self::to_bgra_async`], and the buffer is released exactly once by
+    /// whichever thread drops the last frame referencing it.
+    ///
+    /// Returns [`VideoFrameError::UnsupportedSourceFormat`] for pixel formats
+    /// other than `BGRA`, `420f`, `420v`, `xf20` and `x420`, which can't be
+    /// read back.
+    #[cfg(target_os = "macos")]
+    pub fn from_cv_pixel_buffer(
+        buffer: core_video::pixel_buffer::CVPixelBuffer,
+    ) -> Result<Self, VideoFrameError> {
+        let pixel_format = buffer.get_pixel_format();
+        if cv_pixel_format(pixel_format).is_none() {
+            return Err(unsupported_cv_pixel_format(pixel_format));
+        }
+        let width = buffer.get_width() as u32;
+        let height = buffer.get_height() as u32;
+        let color_range = cv_pixel_buffer_color_range(&buffer);
+        Ok(
+            Self::new(VideoFrameData::CoreVideo(buffer.into()), width, height)
+                .with_color_range(color_range),
+        )
+    }
+
+    /// Create a video frame from a macOS IOSurface, such as those delivered by
+    /// ScreenCaptureKit.
+    ///
+    /// The surface is wrapped in a CoreVideo pixel buffer without copying, so
+    /// the frame takes its size and pixel format from the surface and behaves
+    /// like one created with [`Self::from_cv_pixel_buffer`]. The pixel buffer
+    /// retains the surface for as long as the frame is alive.
+    ///
+    /// Returns [`VideoFrameError::SourceUnavailable`] if CoreVideo can't wrap
+    /// the surface.
+    ///
+    /// # Safety
+    ///
+    /// `surface` must be a valid IOSurface.
+    #[cfg(target_os = "macos")]
+    pub unsafe fn from_io_surface(
+        surface: io_surface::IOSurfaceRef,
+    ) -> Result<Self, VideoFrameError> {
+        use core_foundation::base::TCFType as _;
+        use core_video::{
+            pixel_buffer::CVPixelBuffer,
+            r#return::{CVReturn, kCVReturnSuccess},
+        };
+
+        unsafe extern "C" {
+            fn CVPixelBufferCreateWithIOSurface(
+                allocator: core_foundation_sys::base::CFAllocatorRef,
+                surface: io_surface::IOSurfaceRef,
+                pixel_buffer_attributes: core_foundation_sys::dictionary::CFDictionaryRef,
+                pixel_buffer_out: *mut *mut std::ffi::c_void,
+            ) -> CVReturn;
+        }
+
+        let mut pixel_buffer = std::ptr::null_mut();
+        let result = unsafe {
+            CVPixelBufferCreateWithIOSurface(
+                std::ptr::null(),
+                surface,
+                std::ptr::null(),
+                &mut pixel_buffer,
+            )
+        };
+        if result != kCVReturnSuccess || pixel_buffer.is_null() {
+            return Err(VideoFrameError::SourceUnavailable(format!(
+                "failed to create pixel buffer from IOSurface: CVReturn({result})"
+            )));
+        }
+        let pixel_buffer = unsafe { CVPixelBuffer::wrap_under_create_rule(pixel_buffer as _) };
+        Self::from_cv_pixel_buffer(pixel_buffer)
+    }
+
+    /// Create a video frame from a Windows D3D11 texture.
+    ///
+    /// This provides a zero-copy path on Windows when using hardware-accelerated
+    /// video decoding with Media Foundation.
+    ///
+    /// # Arguments
+    /// * `texture` - The D3D11 texture containing the decoded video frame
+    /// * `subresource_index` - The subresource index within the texture array (usually 0)
+    /// * `width` - The width of the video frame
+    /// * `height` - The height of the video frame
+    ///
+    /// The frame can be sent to other threads. Converting it with
+    /// [`Self::to_bgra`] uses the texture's immediate device context, which is
+    /// serialized through `ID3D11Multithread`, so if frames are read back away
+    /// from the thread that renders with the device, create the device with
+    /// multithread protection enabled.
+    #[cfg(target_os = "windows")]
+    pub fn from_d3d11_texture(
+        texture: windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
+        subresource_index: u32,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        Self::new(
+            VideoFrameData::D3D11 {
+                texture: texture.into(),
+                subresource_index,
+            },
+            width,
+            height,
+        )
+    }
+
+    /// Create a video frame from a D3D11 texture shared by another device or
+    /// process, by opening the NT handle returned from
+    /// [`Self::export_shared_handle`] on `device`.
+    ///
+    /// The frame reads the first subresource of the opened texture. The handle
+    /// isn't consumed, so the caller is still responsible for closing it. If
+    /// the texture was created with a keyed mutex, the caller must also acquire
+    /// it through `IDXGIKeyedMutex` before rendering the frame and release it
+    /// afterwards.
+    #[cfg(target_os = "windows")]
+    pub fn from_shared_handle(
+        device: &windows::Win32::Graphics::Direct3D11::ID3D11Device,
+        handle: windows::Win32::Foundation::HANDLE,
+        width: u32,
+        height: u32,
+    ) -> windows::core::Result<Self> {
+        use windows::{
+            Win32::Graphics::Direct3D11::{ID3D11Device1, ID3D11Texture2D},
+            core::Interface as _,
+        };
+
+        let device = device.cast::<ID3D11Device1>()?;
+        let texture = unsafe { device.OpenSharedResource1::<ID3D11Texture2D>(handle)? };
+        Ok(Self::from_d3d11_texture(texture, 0, width, height))
+    }
+
+    /// Export the D3D11 texture backing this frame as a shared NT handle, so it
+    /// can be opened on another device or in another process with
+    /// [`Self::from_shared_handle`].
+    ///
+    /// The texture must have been created with
+    /// `D3D11_RESOURCE_MISC_SHARED_NTHANDLE`. The whole texture is shared, not
+    /// just this frame's subresource. The caller owns the returned handle and
+    /// must close it with `CloseHandle`, and is responsible for synchronizing
+    /// access through `IDXGIKeyedMutex` if the texture has one.
+    ///
+    /// Returns `E_INVALIDARG` for frames that aren't backed by a D3D11 texture.
+    #[cfg(target_os = "windows")]
+    pub fn export_shared_handle(
+        &self,
+    ) -> windows::core::Result<windows::Win32::Foundation::HANDLE> {
+        use windows::{
+            Win32::{
+                Foundation::E_INVALIDARG,
+                Graphics::Dxgi::{
+                    DXGI_SHARED_RESOURCE_READ, DXGI_SHARED_RESOURCE_WRITE, IDXGIResource1,
+                },
+            },
+            core::{Interface as _, PCWSTR},
+        };
+
+        let VideoFrameData::D3D11 { texture, .. } = &self.data else {
+            return Err(windows::core::Error::new(
+                E_INVALIDARG,
+                "video frame is not backed by a D3D11 texture",
+            ));
+        };
+        let resource = texture.cast::<IDXGIResource1>()?;
+        unsafe {
+            resource.CreateSharedHandle(
+                None,
+                DXGI_SHARED_RESOURCE_READ | DXGI_SHARED_RESOURCE_WRITE,
+                PCWSTR::null(),
+            )
+        }
+    }
+
+    /// Create a frame that references another subresource of the D3D11
+    /// texture backing this frame, such as another slice of the texture array
+    /// a hardware decoder outputs into.
+    ///
+    /// The texture isn't copied, only its reference count is incremented. The
+    /// new frame keeps the size, color metadata and timing of this frame,
+    /// which can be replaced with [`Self::with_pts`] and friends. Returns
+    /// `None` for frames that aren't backed by a D3D11 texture.
+    #[cfg(target_os = "windows")]
+    pub fn d3d11_with_subresource(&self, index: u32) -> Option<VideoFrame> {
+        let VideoFrameData::D3D11 { texture, .. } = &self.data else {
+            return None;
+        };
+        Some(self.with_data(VideoFrameData::D3D11 {
+            texture: texture.clone(),
+            subresource_index: index,
+        }))
+    }
+
+    /// Get the index of the subresource this frame reads from the D3D11
+    /// texture backing it, or `None` if the frame isn't backed by a D3D11
+    /// texture.
+    #[cfg(target_os = "windows")]
+    pub fn d3d11_subresource(&self) -> Option<u32> {
+        match &self.data {
+            VideoFrameData::D3D11 {
+                subresource_index, ..
+            } => Some(*subresource_index),
+            _ => None,
+        }
+    }
+
+    /// Create one frame for each of the first `count` subresources of a D3D11
+    /// texture array, in order, such as for the pool of surfaces a hardware
+    /// decoder outputs a batch into. For a texture without mipmaps, each
+    /// subresource is one array slice.
+    ///
+    /// All of the frames share the texture, which stays alive until the last
+    /// of them is dropped. Nothing is copied, so a decoder writing into a
+    /// slice again changes what the frame for it shows.
+    #[cfg(target_os = "windows")]
+    pub fn d3d11_array_frames(
+        texture: windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
+        count: u32,
+        width: u32,
+        height: u32,
+    ) -> impl Iterator<Item = VideoFrame> {
+        let texture = SafeD3D11Texture::from(texture);
+        (0..count).map(move |subresource_index| {
+            Self::new(
+                VideoFrameData::D3D11 {
+                    texture: texture.clone(),
+                    subresource_index,
+                },
+                width,
+                height,
+            )
+        })
+    }
+
+    /// Create a video frame from a Linux DMA-BUF.
+    ///
+    /// This provides a zero-copy path on Linux for frames from PipeWire, V4L2 or
+    /// VA-API. The frame takes ownership of the file descriptor and closes it
+    /// when the frame and all of its clones have been dropped.
+    ///
+    /// # Arguments
+    /// * `fd` - The DMA-BUF file descriptor
+    /// * `width` - The width of the video frame
+    /// * `height` - The height of the video frame
+    /// * `fourcc` - The DRM fourcc code describing the pixel layout
+    /// * `modifier` - The DRM format modifier describing the memory layout
+    /// * `stride` - The number of bytes between the starts of consecutive rows
+    /// * `offset` - The offset of the first pixel from the start of the buffer
+    ///
+    /// Returns [`VideoFrameError::UnsupportedSourceFormat`] for fourccs other
+    /// than `AR24`, `XR24`, `AB24`, `XB24`, `NV12` and `YU12`, which can't be
+    /// read back.
+    #[cfg(target_os = "linux")]
+    pub fn from_dma_buf(
+        fd: std::os::fd::OwnedFd,
+        width: u32,
+        height: u32,
+        fourcc: u32,
+        modifier: u64,
+        stride: u32,
+        offset: u32,
+    ) -> Result<Self, VideoFrameError> {
+        if dma_buf_pixel_format(fourcc).is_none() {
+            return Err(unsupported_dma_buf_format(fourcc));
+        }
+        Ok(Self::new(
+            VideoFrameData::DmaBuf {
+                fd: Arc::new(fd),
+                fourcc,
+                modifier,
+                stride,
+                offset,
+            },
+            width,
+            height,
+        ))
+    }
+
+    /// Copy a hardware-backed frame into a new GPU buffer, giving an
+    /// independent frame, such as to hand the source buffer back to a decoder
+    /// that wants to reuse it, without reading the pixels back to the CPU.
+    ///
+    /// On Windows, the frame's subresource is copied with
+    /// `CopySubresourceRegion` on the texture's own device into a new texture
+    /// that can be bound as a shader resource. On macOS, a new IOSurface-backed
+    /// pixel buffer of the same format is allocated and the planes are copied
+    /// into it, since CoreVideo has no GPU-side copy; the source's attachments,
+    /// such as its color space, are carried over. The frame's timing, color
+    /// space and alpha mode are kept.
+    ///
+    /// DMA-BUF frames aren't supported, and neither are CPU frames, whose
+    /// clones already share their buffers without copying.
+    pub fn copy_gpu(&self) -> Result<VideoFrame, VideoFrameError> {
+        match &self.data {
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(buffer) => {
+                let copy = copy_cv_pixel_buffer(buffer)
+                    .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string()))?;
+                Ok(self.with_data(VideoFrameData::CoreVideo(copy.into())))
+            }
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 {
+                texture,
+                subresource_index,
+            } => {
+                let copy = copy_d3d11_texture(texture, *subresource_index)
+                    .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string()))?;
+                Ok(self.with_data(VideoFrameData::D3D11 {
+                    texture: copy.into(),
+                    subresource_index: 0,
+                }))
+            }
+            _ if self.cpu_planes().is_some() => {
+                Err(VideoFrameError::UnsupportedFormat(self.pixel_format()))
+            }
+            _ => Err(VideoFrameError::UnsupportedForHardwareFrame),
+        }
+    }
+
+    /// Get a copy of this frame that shares no pixel buffer with any other
+    /// frame, so that it's safe to keep indefinitely even if it came from a
+    /// [`VideoFramePool`] that will reuse its buffer.
+    ///
+    /// CPU frames that are already the only holder of their buffer are cloned
+    /// without copying, and other CPU frames have their planes copied into new
+    /// buffers. Hardware-backed frames are copied with [`Self::copy_gpu`],
+    /// falling back to [`Self::to_bgra`] where that isn't supported.
+    ///
+    /// Unlike the frame itself, a copy doesn't run the callback passed to
+    /// [`Self::from_bgra_with_drop`].
+    pub fn detach(&self) -> VideoFrame {
+        fn copy<T: Clone>(buffer: &Arc<Vec<T>>) -> Arc<Vec<T>> {
+            Arc::new(Vec::clone(buffer))
+        }
+
+        if self.is_buffer_unique() {
+            return self.clone();
+        }
+        let data = match &self.data {
+            VideoFrameData::Bgra { buffer, stride } => VideoFrameData::Bgra {
+                buffer: copy(buffer),
+                stride: *stride,
+            },
+            VideoFrameData::Rgba { buffer, stride } => VideoFrameData::Rgba {
+                buffer: copy(buffer),
+                stride: *stride,
+            },
+            VideoFrameData::Rgb { buffer } => VideoFrameData::Rgb {
+                buffer: copy(buffer),
+            },
+            VideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => VideoFrameData::Nv12 {
+                y_plane: copy(y_plane),
+                uv_plane: copy(uv_plane),
+                y_stride: *y_stride,
+                uv_stride: *uv_stride,
+            },
+            VideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => VideoFrameData::I420 {
+                y: copy(y),
+                u: copy(u),
+                v: copy(v),
+                y_stride: *y_stride,
+                u_stride: *u_stride,
+                v_stride: *v_stride,
+            },
+            VideoFrameData::P010 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => VideoFrameData::P010 {
+                y_plane: copy(y_plane),
+                uv_plane: copy(uv_plane),
+                y_stride: *y_stride,
+                uv_stride: *uv_stride,
+            },
+            VideoFrameData::Yuyv { buffer, stride } => VideoFrameData::Yuyv {
+                buffer: copy(buffer),
+                stride: *stride,
+            },
+            VideoFrameData::Rgba16 { buffer, float } => VideoFrameData::Rgba16 {
+                buffer: copy(buffer),
+                float: *float,
+            },
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => return self.to_bgra(),
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(source) => {
+                match ffmpeg::copy_av_frame(source, self.width, self.height).log_err() {
+                    Some(data) => data,
+                    None => return self.to_bgra(),
+                }
+            }
+            #[cfg(any(target_os = "macos", target_os = "windows"))]
+            _ => return self.copy_gpu().log_err().unwrap_or_else(|| self.to_bgra()),
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => return self.to_bgra(),
+        };
+        self.with_data(data)
+    }
+
+    /// Check that this frame's dimensions, strides and buffer sizes are
+    /// consistent with each other, such as for frames received over IPC or
+    /// whose public `width` and `height` were changed after construction.
+    ///
+    /// This repeats the checks of the fallible constructors, such as
+    /// [`Self::try_from_bgra_with_stride`] and [`Self::try_from_nv12`], and
+    /// returns the first inconsistency found. Only the dimensions of
+    /// hardware-backed frames are checked, since their buffers live on the
+    /// GPU.
+    pub fn validate(&self) -> Result<(), VideoFrameError> {
+        let (width, height) = (self.width, self.height);
+        validate_dimensions(width, height)?;
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        match &self.data {
+            VideoFrameData::Bgra { buffer, stride } | VideoFrameData::Rgba { buffer, stride } => {
+                validate_plane(buffer.len(), row_size(width, 4)?, *stride, height)
+            }
+            VideoFrameData::Rgb { buffer } => {
+                let row_size = row_size(width, 3)?;
+                validate_plane(buffer.len(), row_size, row_size, height)
+            }
+            VideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => {
+                validate_plane(y_plane.len(), width, *y_stride, height)?;
+                validate_plane(
+                    uv_plane.len(),
+                    row_size(chroma_width, 2)?,
+                    *uv_stride,
+                    chroma_height,
+                )
+            }
+            VideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => {
+                validate_plane(y.len(), width, *y_stride, height)?;
+                validate_plane(u.len(), chroma_width, *u_stride, chroma_height)?;
+                validate_plane(v.len(), chroma_width, *v_stride, chroma_height)
+            }
+            VideoFrameData::P010 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => {
+                validate_plane(y_plane.len(), width, *y_stride, height)?;
+                validate_plane(
+                    uv_plane.len(),
+                    row_size(chroma_width, 2)?,
+                    *uv_stride,
+                    chroma_height,
+                )
+            }
+            VideoFrameData::Yuyv { buffer, stride } => {
+                if !width.is_multiple_of(2) {
+                    return Err(VideoFrameError::OddWidth(width));
+                }
+                validate_plane(buffer.len(), row_size(width, 2)?, *stride, height)
+            }
+            VideoFrameData::Rgba16 { buffer, .. } => {
+                let row_size = row_size(width, 8)?;
+                validate_plane(buffer.len(), row_size, row_size, height)
+            }
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(buffer) => {
+                let pixel_format = buffer.get_pixel_format();
+                match cv_pixel_format(pixel_format) {
+                    Some(_) => Ok(()),
+                    None => Err(unsupported_cv_pixel_format(pixel_format)),
+                }
+            }
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => Ok(()),
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { fourcc, .. } => match dma_buf_pixel_format(*fourcc) {
+                Some(_) => Ok(()),
+                None => Err(unsupported_dma_buf_format(*fourcc)),
+            },
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => Ok(()),
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(source) => ffmpeg::validate_av_frame(source, width, height),
+        }
+    }
+
+    /// Set the presentation timestamp of this frame, the time at which it
+    /// should be shown relative to the start of the stream.
+    pub fn with_pts(mut self, pts: Duration) -> Self {
+        self.pts = Some(pts);
+        self
+    }
+
+    /// Set how long this frame should be shown for.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Set the color space of this frame, which selects the matrix used when
+    /// converting YUV samples to RGB.
+    ///
+    /// Frames default to full-range [`VideoColorSpace::Srgb`] for BGRA and RGBA
+    /// data, and to [`VideoColorSpace::Bt601`] for YUV data whose source didn't
+    /// specify a color space.
+    pub fn with_color_space(mut self, color_space: VideoColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Get the color space of this frame.
+    pub fn color_space(&self) -> VideoColorSpace {
+        self.color_space
+    }
+
+    /// Set the range of this frame's YUV samples, which must match the source
+    /// for colors to be neither washed out nor crushed.
+    ///
+    /// Frames default to [`ColorRange::Full`], which is always the case for RGB
+    /// frames. CoreVideo frames take their range from their pixel format, and
+    /// frames from FFmpeg and GStreamer from the source's metadata; callers
+    /// building YUV frames from raw planes should set the range the source
+    /// reports, which for most decoded video is [`ColorRange::Limited`].
+    pub fn with_color_range(mut self, color_range: ColorRange) -> Self {
+        self.color_range = color_range;
+        self
+    }
+
+    /// Get the range of this frame's YUV samples.
+    pub fn color_range(&self) -> ColorRange {
+        self.color_range
+    }
+
+    /// The conversion from this frame's YUV samples to RGB.
+    fn yuv_to_rgb(&self) -> YuvToRgb {
+        YuvToRgb::new(self.color_space, self.color_range == ColorRange::Limited)
+    }
+
+    /// Set how the alpha channel of this frame is interpreted when it is
+    /// painted. Frames default to [`AlphaMode::Straight`].
+    pub fn with_alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Get how the alpha channel of this frame is interpreted.
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    /// Set the rotation to apply when this frame is painted, such as the
+    /// orientation a phone camera or a WebRTC track tags its frames with.
+    ///
+    /// Unlike [`Self::rotate`], which bakes a rotation into new pixels, this
+    /// defers it to the renderer: a 1280×720 frame tagged with
+    /// [`Rotation::Cw90`] is laid out and painted as 720×1280 by the
+    /// [`Video`](crate::Video) element without copying its buffer. Frames
+    /// default to [`Rotation::None`], and the rotation is kept by frames
+    /// derived from this one.
+    pub fn with_display_rotation(mut self, rotation: Rotation) -> Self {
+        self.display_rotation = rotation;
+        self
+    }
+
+    /// Get the rotation to apply when this frame is painted.
+    pub fn display_rotation(&self) -> Rotation {
+        self.display_rotation
+    }
+
+    /// Attach arbitrary data to this frame, such as a camera ID, HDR mastering
+    /// metadata or closed-caption side data, replacing any attached before.
+    ///
+    /// Metadata is shared between clones without copying and carried over to
+    /// frames derived from this one, such as by [`Self::to_bgra`] or
+    /// [`Self::scale`]. GPUI never interprets the keys or values.
+    pub fn with_metadata(mut self, metadata: HashMap<String, Vec<u8>>) -> Self {
+        self.metadata = Some(Arc::new(metadata));
+        self
+    }
+
+    /// Get the data attached with [`Self::with_metadata`], if any.
+    pub fn metadata(&self) -> Option<&HashMap<String, Vec<u8>>> {
+        self.metadata.as_deref()
+    }
+
+    /// Get the presentation timestamp of this frame, if one was set.
+    pub fn pts(&self) -> Option<Duration> {
+        self.pts
+    }
+
+    /// Get how long this frame should be shown for, if known.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Get the size of this video frame in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Get the size of this video frame as a gpui [`Size`], with one [`Pixels`]
+    /// per pixel of the frame.
+    pub fn size_in_pixels(&self) -> Size<Pixels> {
+        size(px(self.width as f32), px(self.height as f32))
+    }
+
+    /// Get the ratio of this frame's width to its height.
+    ///
+    /// Returns `0.0` for a frame with a height of zero, such as a hardware
+    /// frame wrapping an empty surface.
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.height == 0 {
+            0.0
+        } else {
+            self.width as f32 / self.height as f32
+        }
+    }
+
+    /// Get the number of bytes between the start of consecutive rows.
+    ///
+    /// This is the stride for BGRA, RGBA and YUYV frames, which may include
+    /// row padding, `width * 3` for RGB frames and `width * 8` for 16-bit RGBA
+    /// frames. Other frames report `width * 4`, the row size they have after
+    /// [`Self::to_bgra`].
+    pub fn bytes_per_row(&self) -> u32 {
+        match &self.data {
+            VideoFrameData::Bgra { stride, .. }
+            | VideoFrameData::Rgba { stride, .. }
+            | VideoFrameData::Yuyv { stride, .. } => *stride,
+            VideoFrameData::Rgb { .. } => self.width * 3,
+            VideoFrameData::Rgba16 { .. } => self.width * 8,
+            _ => self.width * 4,
+        }
+    }
+
+    /// Get where this frame's pixels live.
+    pub fn backend(&self) -> VideoFrameBackend {
+        match &self.data {
+            VideoFrameData::Bgra { .. } => VideoFrameBackend::CpuBgra,
+            VideoFrameData::Rgba { .. } => VideoFrameBackend::CpuRgba,
+            VideoFrameData::Rgb { .. } => VideoFrameBackend::CpuRgb,
+            VideoFrameData::Nv12 { .. } => VideoFrameBackend::CpuNv12,
+            VideoFrameData::I420 { .. } => VideoFrameBackend::CpuI420,
+            VideoFrameData::P010 { .. } => VideoFrameBackend::CpuP010,
+            VideoFrameData::Yuyv { .. } => VideoFrameBackend::CpuYuyv,
+            VideoFrameData::Rgba16 { .. } => VideoFrameBackend::CpuRgba16,
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => VideoFrameBackend::CoreVideo,
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => VideoFrameBackend::D3D11,
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => VideoFrameBackend::DmaBuf,
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => VideoFrameBackend::Wgpu,
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => VideoFrameBackend::FFmpeg,
+        }
+    }
+
+    /// Whether this frame's pixels live in a GPU or platform buffer, or in a
+    /// buffer owned by another library such as FFmpeg, rather than in CPU
+    /// memory of the frame's own. Such frames have to be read back with
+    /// [`Self::to_bgra`] before their pixels can be accessed.
+    pub fn is_hardware_backed(&self) -> bool {
+        matches!(
+            self.backend(),
+            VideoFrameBackend::CoreVideo
+                | VideoFrameBackend::D3D11
+                | VideoFrameBackend::DmaBuf
+                | VideoFrameBackend::Wgpu
+                | VideoFrameBackend::FFmpeg
+        )
+    }
+
+    /// Get the layout of this frame's pixel data.
+    ///
+    /// For hardware-backed frames this describes the format of the underlying
+    /// GPU surface.
+    pub fn pixel_format(&self) -> PixelFormat {
+        match &self.data {
+            VideoFrameData::Bgra { .. } => PixelFormat::Bgra8,
+            VideoFrameData::Rgba { .. } => PixelFormat::Rgba8,
+            VideoFrameData::Rgb { .. } => PixelFormat::Rgb8,
+            VideoFrameData::Nv12 { .. } => PixelFormat::Nv12,
+            VideoFrameData::I420 { .. } => PixelFormat::I420,
+            VideoFrameData::P010 { .. } => PixelFormat::P010,
+            VideoFrameData::Yuyv { .. } => PixelFormat::Yuyv,
+            VideoFrameData::Rgba16 { float: false, .. } => PixelFormat::Rgba16Unorm,
+            VideoFrameData::Rgba16 { float: true, .. } => PixelFormat::Rgba16Float,
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(buffer) => {
+                cv_pixel_format(buffer.get_pixel_format()).unwrap_or(PixelFormat::Bgra8)
+            }
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { texture, .. } => {
+                let mut desc =
+                    windows::Win32::Graphics::Direct3D11::D3D11_TEXTURE2D_DESC::default();
+                unsafe { texture.GetDesc(&mut desc) };
+                match desc.Format {
+                    windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM => {
+                        PixelFormat::Bgra8
+                    }
+                    windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_R8G8B8A8_UNORM => {
+                        PixelFormat::Rgba8
+                    }
+                    _ => PixelFormat::Nv12,
+                }
+            }
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { fourcc, .. } => {
+                dma_buf_pixel_format(*fourcc).unwrap_or(PixelFormat::Bgra8)
+            }
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(texture) => {
+                wgpu::wgpu_pixel_format(texture.format()).unwrap_or(PixelFormat::Bgra8)
+            }
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(source) => {
+                ffmpeg::av_pixel_format(source.format()).unwrap_or(PixelFormat::Bgra8)
+            }
+        }
+    }
+
+    /// Get the number of significant bits in each sample: 16 for 16-bit RGBA
+    /// frames, 10 for P010 frames and 8 for everything else.
+    pub fn bit_depth(&self) -> u8 {
+        match self.pixel_format() {
+            PixelFormat::Rgba16Unorm | PixelFormat::Rgba16Float => 16,
+            PixelFormat::P010 => 10,
+            PixelFormat::Bgra8
+            | PixelFormat::Rgba8
+            | PixelFormat::Rgb8
+            | PixelFormat::Nv12
+            | PixelFormat::I420
+            | PixelFormat::Yuyv => 8,
+        }
+    }
+
+    /// Get the number of bytes each pixel takes in a packed frame: 8 for 16-bit
+    /// RGBA frames, 4 for BGRA and RGBA frames, 3 for RGB frames and 2 for YUYV
+    /// frames, whose pixel pairs share their chroma.
+    ///
+    /// Returns `None` for planar formats, whose samples are split across
+    /// planes of different resolutions.
+    pub fn bytes_per_pixel(&self) -> Option<u32> {
+        match self.pixel_format() {
+            PixelFormat::Rgba16Unorm | PixelFormat::Rgba16Float => Some(8),
+            PixelFormat::Bgra8 | PixelFormat::Rgba8 => Some(4),
+            PixelFormat::Rgb8 => Some(3),
+            PixelFormat::Yuyv => Some(2),
+            PixelFormat::Nv12 | PixelFormat::I420 | PixelFormat::P010 => None,
+        }
+    }
+
+    /// Get the number of planes in this frame's pixel format: 1 for packed
+    /// formats, 2 for NV12 and P010, and 3 for I420.
+    ///
+    /// Hardware-backed frames report the planes of their format, even though
+    /// [`Self::plane`] can't access them.
+    pub fn plane_count(&self) -> usize {
+        match self.pixel_format() {
+            PixelFormat::Bgra8
+            | PixelFormat::Rgba8
+            | PixelFormat::Rgb8
+            | PixelFormat::Yuyv
+            | PixelFormat::Rgba16Unorm
+            | PixelFormat::Rgba16Float => 1,
+            PixelFormat::Nv12 | PixelFormat::P010 => 2,
+            PixelFormat::I420 => 3,
+        }
+    }
+
+    /// Get the bytes of one of this frame's planes, along with its stride in
+    /// bytes.
+    ///
+    /// Planes are in storage order, luma first: the only plane of a packed
+    /// frame, Y then interleaved CbCr for NV12 and P010, and Y, Cb, Cr for
+    /// I420. P010 samples are 16-bit little-endian, so their strides are twice
+    /// the sample strides given to [`Self::from_p010`].
+    ///
+    /// Returns `None` if `index` is out of range or the frame is hardware
+    /// backed.
+    pub fn plane(&self, index: usize) -> Option<(&[u8], u32)> {
+        let plane = self.cpu_planes()?.into_iter().nth(index)?;
+        Some((plane.bytes, plane.stride as u32))
+    }
+
+    /// Get the number of bytes of pixel data this frame holds, for keeping
+    /// caches of frames within a memory budget.
+    ///
+    /// For CPU frames this is the size of every backing buffer, including row
+    /// padding. Buffers shared between clones are counted in full by each
+    /// clone. The GPU allocation behind hardware-backed frames can't be
+    /// queried, so for those this is an approximation based on their size and
+    /// pixel format.
+    pub fn memory_footprint(&self) -> usize {
+        match &self.data {
+            VideoFrameData::Bgra { buffer, .. }
+            | VideoFrameData::Rgba { buffer, .. }
+            | VideoFrameData::Rgb { buffer }
+            | VideoFrameData::Yuyv { buffer, .. }
+            | VideoFrameData::Rgba16 { buffer, .. } => buffer.len(),
+            VideoFrameData::Nv12 {
+                y_plane, uv_plane, ..
+            } => y_plane.len() + uv_plane.len(),
+            VideoFrameData::I420 { y, u, v, .. } => y.len() + u.len() + v.len(),
+            VideoFrameData::P010 {
+                y_plane, uv_plane, ..
+            } => {
+                std::mem::size_of_val(y_plane.as_slice())
+                    + std::mem::size_of_val(uv_plane.as_slice())
+            }
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => self.estimated_footprint(),
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => self.estimated_footprint(),
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => self.estimated_footprint(),
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => self.estimated_footprint(),
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => self.estimated_footprint(),
+        }
+    }
+
+    /// Estimate the size of this frame's pixel data from its size and format.
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "windows",
+        target_os = "linux",
+        feature = "wgpu",
+        feature = "ffmpeg"
+    ))]
+    fn estimated_footprint(&self) -> usize {
+        let bits_per_pixel = match self.pixel_format() {
+            PixelFormat::Rgba16Unorm | PixelFormat::Rgba16Float => 64,
+            PixelFormat::Bgra8 | PixelFormat::Rgba8 => 32,
+            PixelFormat::Rgb8 | PixelFormat::P010 => 24,
+            PixelFormat::Yuyv => 16,
+            PixelFormat::Nv12 | PixelFormat::I420 => 12,
+        };
+        self.width as usize * self.height as usize * bits_per_pixel / 8
+    }
+
+    /// Get the layout of this frame's pixel data.
+    ///
+    /// This is the same as [`Self::pixel_format`], named to pair with [`Self::from_raw`].
+    pub fn format(&self) -> PixelFormat {
+        self.pixel_format()
+    }
+
+    /// Hash the size, pixel format and visible pixels of this frame, for
+    /// detecting duplicate frames cheaply.
+    ///
+    /// Frames that compare equal have the same hash. Row padding is not hashed.
+    /// Hardware-backed frames only hash their size and format.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = collections::FxHasher::default();
+        (self.width, self.height, self.pixel_format()).hash(&mut hasher);
+        for plane in self.cpu_planes().into_iter().flatten() {
+            for row in plane.rows() {
+                hasher.write(row);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Cheaply guess whether this frame holds the same pixels as `other`, by
+    /// comparing their size and format and then a sparse grid of bytes from
+    /// each plane that includes its corners and center.
+    ///
+    /// This is meant as a pre-filter before `==`, such as when deduplicating
+    /// frames before encoding. Frames that are equal are always likely equal,
+    /// but so are frames that only differ between the sampled bytes, so a
+    /// `true` result should be confirmed with a full comparison wherever such
+    /// a false positive matters. Hardware-backed frames are only likely equal
+    /// when they share the same buffer, as with `==`.
+    pub fn likely_equal(&self, other: &VideoFrame) -> bool {
+        if self.width != other.width
+            || self.height != other.height
+            || self.pixel_format() != other.pixel_format()
+        {
+            return false;
+        }
+        match (self.cpu_planes(), other.cpu_planes()) {
+            (Some(planes), Some(other_planes)) => planes
+                .iter()
+                .zip(other_planes.iter())
+                .all(|(plane, other_plane)| plane.samples().eq(other_plane.samples())),
+            _ => self.shares_hardware_buffer(other),
+        }
+    }
+
+    /// Find the smallest rectangle containing every pixel that differs between
+    /// this frame and `previous`, as `(x, y, width, height)`, so that only the
+    /// changed region needs to be re-encoded.
+    ///
+    /// Returns `None` if the frames are identical. Only packed CPU frames of the
+    /// same size and format are compared; for any other pair of frames the
+    /// whole frame is reported as changed.
+    pub fn diff_bounds(&self, previous: &VideoFrame) -> Option<(u32, u32, u32, u32)> {
+        let whole_frame = Some((0, 0, self.width, self.height));
+        let (Ok((pixels, stride, format)), Ok((previous_pixels, previous_stride, previous_format))) =
+            (self.packed_pixels(), previous.packed_pixels())
+        else {
+            return whole_frame;
+        };
+        if format != previous_format || self.size() != previous.size() {
+            return whole_frame;
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let row_len = width * 4;
+        let rows = |row: usize| {
+            (
+                &pixels[row * stride..][..row_len],
+                &previous_pixels[row * previous_stride..][..row_len],
+            )
+        };
+        let row_changed = |row: &usize| {
+            let (current, previous) = rows(*row);
+            current != previous
+        };
+        let top = (0..height).find(row_changed)?;
+        let bottom = (top..height).rev().find(row_changed).unwrap_or(top);
+
+        let (mut left, mut right) = (width, 0);
+        for row in top..=bottom {
+            let (current, previous) = rows(row);
+            let column_changed = |column: &usize| {
+                let pixel = column * 4..column * 4 + 4;
+                current[pixel.clone()] != previous[pixel]
+            };
+            if let Some(column) = (0..left).find(column_changed) {
+                left = column;
+            }
+            if let Some(column) = (right..width).rev().find(column_changed) {
+                right = column + 1;
+            }
+        }
+        Some((
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom + 1 - top) as u32,
+        ))
+    }
+
+    /// Get the planes of a CPU frame, in storage order.
+    fn cpu_planes(&self) -> Option<Vec<Plane<'_>>> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let chroma_width = width.div_ceil(2);
+        let chroma_height = height.div_ceil(2);
+        match &self.data {
+            VideoFrameData::Bgra { buffer, stride } | VideoFrameData::Rgba { buffer, stride } => {
+                Some(vec![Plane::new(buffer, *stride, width * 4, height)])
+            }
+            VideoFrameData::Rgb { buffer } => {
+                Some(vec![Plane::new(buffer, self.width * 3, width * 3, height)])
+            }
+            VideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => Some(vec![
+                Plane::new(y_plane, *y_stride, width, height),
+                Plane::new(uv_plane, *uv_stride, chroma_width * 2, chroma_height),
+            ]),
+            VideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => Some(vec![
+                Plane::new(y, *y_stride, width, height),
+                Plane::new(u, *u_stride, chroma_width, chroma_height),
+                Plane::new(v, *v_stride, chroma_width, chroma_height),
+            ]),
+            VideoFrameData::P010 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => Some(vec![
+                Plane::new(sample_bytes(y_plane), y_stride * 2, width * 2, height),
+                Plane::new(
+                    sample_bytes(uv_plane),
+                    uv_stride * 2,
+                    chroma_width * 4,
+                    chroma_height,
+                ),
+            ]),
+            VideoFrameData::Yuyv { buffer, stride } => {
+                Some(vec![Plane::new(buffer, *stride, width * 2, height)])
+            }
+            VideoFrameData::Rgba16 { buffer, .. } => {
+                Some(vec![Plane::new(buffer, self.width * 8, width * 8, height)])
+            }
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => None,
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => None,
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => None,
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => None,
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => None,
+        }
+    }
+
+    /// Whether both frames wrap the same hardware buffer.
+    fn shares_hardware_buffer(&self, other: &Self) -> bool {
+        #[cfg(target_os = "macos")]
+        if let (VideoFrameData::CoreVideo(buffer), VideoFrameData::CoreVideo(other_buffer)) =
+            (&self.data, &other.data)
+        {
+            return buffer == other_buffer;
+        }
+        #[cfg(target_os = "windows")]
+        if let (
+            VideoFrameData::D3D11 {
+                texture,
+                subresource_index,
+            },
+            VideoFrameData::D3D11 {
+                texture: other_texture,
+                subresource_index: other_subresource_index,
+            },
+        ) = (&self.data, &other.data)
+        {
+            return texture == other_texture && subresource_index == other_subresource_index;
+        }
+        #[cfg(target_os = "linux")]
+        if let (VideoFrameData::DmaBuf { fd, .. }, VideoFrameData::DmaBuf { fd: other_fd, .. }) =
+            (&self.data, &other.data)
+        {
+            return Arc::ptr_eq(fd, other_fd);
+        }
+        #[cfg(feature = "wgpu")]
+        if let (VideoFrameData::Wgpu(texture), VideoFrameData::Wgpu(other_texture)) =
+            (&self.data, &other.data)
+        {
+            return texture == other_texture;
+        }
+        #[cfg(feature = "ffmpeg")]
+        if let (VideoFrameData::FFmpeg(source), VideoFrameData::FFmpeg(other_source)) =
+            (&self.data, &other.data)
+        {
+            return Arc::ptr_eq(source, other_source);
+        }
+        false
+    }
+
+    /// Get the pixel buffer, stride and format of a packed CPU frame.
+    pub(crate) fn packed_buffer(&self) -> Option<(&Arc<Vec<u8>>, u32, PixelFormat)> {
+        match &self.data {
+            VideoFrameData::Bgra { buffer, stride } => Some((buffer, *stride, PixelFormat::Bgra8)),
+            VideoFrameData::Rgba { buffer, stride } => Some((buffer, *stride, PixelFormat::Rgba8)),
+            _ => None,
+        }
+    }
+
+    /// Get the pixels, stride and format of a packed CPU frame, or the reason
+    /// the frame can't be processed as packed pixels.
+    fn packed_pixels(&self) -> Result<(&[u8], usize, PixelFormat), VideoFrameError> {
+        match &self.data {
+            VideoFrameData::Bgra { buffer, stride } => {
+                Ok((buffer.as_slice(), *stride as usize, PixelFormat::Bgra8))
+            }
+            VideoFrameData::Rgba { buffer, stride } => {
+                Ok((buffer.as_slice(), *stride as usize, PixelFormat::Rgba8))
+            }
+            VideoFrameData::Rgb { .. }
+            | VideoFrameData::Nv12 { .. }
+            | VideoFrameData::I420 { .. }
+            | VideoFrameData::P010 { .. }
+            | VideoFrameData::Yuyv { .. }
+            | VideoFrameData::Rgba16 { .. } => {
+                Err(VideoFrameError::UnsupportedFormat(self.pixel_format()))
+            }
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => Err(VideoFrameError::UnsupportedForHardwareFrame),
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => Err(VideoFrameError::UnsupportedForHardwareFrame),
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => Err(VideoFrameError::UnsupportedForHardwareFrame),
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => Err(VideoFrameError::UnsupportedForHardwareFrame),
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => Err(VideoFrameError::UnsupportedForHardwareFrame),
+        }
+    }
+
+    /// Get mutable access to the pixels, stride and format of a packed CPU
+    /// frame, copying the buffer first if it's shared with another frame.
+    fn packed_pixels_mut(&mut self) -> Result<(&mut [u8], usize, PixelFormat), VideoFrameError> {
+        let (_, _, format) = self.packed_pixels()?;
+        self.buffer_id = next_buffer_id();
+        match &mut self.data {
+            VideoFrameData::Bgra { buffer, stride } | VideoFrameData::Rgba { buffer, stride } => {
+                Ok((
+                    Arc::make_mut(buffer).as_mut_slice(),
+                    *stride as usize,
+                    format,
+                ))
+            }
+            _ => Err(VideoFrameError::UnsupportedFormat(format)),
+        }
+    }
+
+    /// Create a tightly packed frame derived from this one, such as the output
+    /// of a transform, keeping this frame's timing, color space, alpha mode,
+    /// display rotation and metadata.
+    fn with_packed_buffer(
+        &self,
+        buffer: Vec<u8>,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> Self {
+        Self {
+            pts: self.pts,
+            duration: self.duration,
+            color_space: self.color_space,
+            alpha_mode: self.alpha_mode,
+            display_rotation: self.display_rotation,
+            metadata: self.metadata.clone(),
+            ..Self::from_packed(buffer, width, height, format)
+        }
+    }
+
+    /// Create a frame of the same size backed by `data`, keeping this frame's
+    /// timing, color space, color range, alpha mode, display rotation and
+    /// metadata.
+    fn with_data(&self, data: VideoFrameData) -> Self {
+        Self {
+            pts: self.pts,
+            duration: self.duration,
+            color_space: self.color_space,
+            color_range: self.color_range,
+            alpha_mode: self.alpha_mode,
+            display_rotation: self.display_rotation,
+            metadata: self.metadata.clone(),
+            ..Self::new(data, self.width, self.height)
+        }
+    }
+
+    /// Get access to the raw pixel data, if this is a CPU-backed frame.
+    ///
+    /// For padded frames this includes the padding at the end of each row; use
+    /// [`Self::bytes_per_row`] to step between rows.
+    ///
+    /// Returns `None` for hardware-backed frames (e.g., CoreVideo on macOS, D3D11 on Windows,
+    /// DMA-BUF on Linux).
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.data {
+            VideoFrameData::Bgra { buffer, .. }
+            | VideoFrameData::Rgba { buffer, .. }
+            | VideoFrameData::Rgb { buffer }
+            | VideoFrameData::Yuyv { buffer, .. }
+            | VideoFrameData::Rgba16 { buffer, .. } => Some(buffer.as_slice()),
+            VideoFrameData::Nv12 { .. }
+            | VideoFrameData::I420 { .. }
+            | VideoFrameData::P010 { .. } => None,
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => None,
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => None,
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => None,
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => None,
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => None,
+        }
+    }
+
+    /// Get mutable access to the raw pixel data, if this is a CPU-backed frame
+    /// whose buffer isn't shared with any other frame.
+    ///
+    /// This uses [`Arc::get_mut`] rather than [`Arc::make_mut`], so it never
+    /// copies: if the buffer is aliased (for example by a clone of this frame, by
+    /// the [`VideoFramePool`] that handed it out, or by the renderer after the
+    /// frame has been painted), `None` is returned and the
+    /// caller can decide whether a copy is worth making. The slice has the same
+    /// layout as [`Self::as_bytes`], including any row padding.
+    ///
+    /// Returns `None` for hardware-backed and planar YUV frames.
+    pub fn as_bytes_mut(&mut self) -> Option<&mut [u8]> {
+        self.buffer_id = next_buffer_id();
+        match &mut self.data {
+            VideoFrameData::Bgra { buffer, .. }
+            | VideoFrameData::Rgba { buffer, .. }
+            | VideoFrameData::Rgb { buffer }
+            | VideoFrameData::Yuyv { buffer, .. }
+            | VideoFrameData::Rgba16 { buffer, .. } => {
+                Arc::get_mut(buffer).map(|buffer| buffer.as_mut_slice())
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the number of references to this frame's pixel buffer, counting
+    /// clones of the frame, the [`VideoFramePool`] that handed it out and the
+    /// renderer after the frame has been painted.
+    ///
+    /// For frames with several planes this is the count of the most shared
+    /// plane. Returns `None` for hardware-backed frames.
+    pub fn buffer_ref_count(&self) -> Option<usize> {
+        match &self.data {
+            VideoFrameData::Bgra { buffer, .. }
+            | VideoFrameData::Rgba { buffer, .. }
+            | VideoFrameData::Rgb { buffer }
+            | VideoFrameData::Yuyv { buffer, .. }
+            | VideoFrameData::Rgba16 { buffer, .. } => Some(Arc::strong_count(buffer)),
+            VideoFrameData::Nv12 {
+                y_plane, uv_plane, ..
+            } => Some(Arc::strong_count(y_plane).max(Arc::strong_count(uv_plane))),
+            VideoFrameData::I420 { y, u, v, .. } => Some(
+                Arc::strong_count(y)
+                    .max(Arc::strong_count(u))
+                    .max(Arc::strong_count(v)),
+            ),
+            VideoFrameData::P010 {
+                y_plane, uv_plane, ..
+            } => Some(Arc::strong_count(y_plane).max(Arc::strong_count(uv_plane))),
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => None,
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => None,
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => None,
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => None,
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => None,
+        }
+    }
+
+    /// Whether this CPU frame is the only holder of its pixel buffer, so
+    /// that a pool polling the frames it handed out can reclaim it.
+    ///
+    /// Returns `false` for hardware-backed frames.
+    pub fn is_buffer_unique(&self) -> bool {
+        self.buffer_ref_count() == Some(1)
+    }
+
+    /// Iterate over the rows of a packed CPU frame, top to bottom.
+    ///
+    /// Yields `height` rows of `width * bytes_per_pixel` bytes, without the
+    /// padding that [`Self::as_bytes`] includes at the end of each row.
+    ///
+    /// Returns `None` for hardware-backed and YUV frames.
+    pub fn rows(&self) -> Option<impl Iterator<Item = &[u8]>> {
+        let (bytes, stride, bytes_per_pixel) = match &self.data {
+            VideoFrameData::Bgra { buffer, stride } | VideoFrameData::Rgba { buffer, stride } => {
+                (buffer.as_slice(), *stride, 4)
+            }
+            VideoFrameData::Rgb { buffer } => (buffer.as_slice(), self.width * 3, 3),
+            VideoFrameData::Rgba16 { buffer, .. } => (buffer.as_slice(), self.width * 8, 8),
+            _ => return None,
+        };
+        let row_len = self.width as usize * bytes_per_pixel;
+        Some(Plane::new(bytes, stride, row_len, self.height as usize).rows())
+    }
+
+    /// Classify the alpha channel of this frame, such as for drawing it
+    /// without blending when it's fully opaque.
+    ///
+    /// The alpha of BGRA, RGBA and 16-bit RGBA frames is scanned, stopping as
+    /// soon as a translucent pixel, or both an opaque and a transparent pixel,
+    /// have been seen. Frames with [`AlphaMode::Opaque`] are fully opaque
+    /// without scanning.
+    ///
+    /// Returns `None` for formats without alpha, and for other hardware-backed
+    /// frames.
+    pub fn alpha_coverage(&self) -> Option<AlphaCoverage> {
+        // Whether a pixel is fully opaque, or `None` if it's translucent.
+        let (bytes_per_pixel, opacity): (usize, fn(&[u8]) -> Option<bool>) =
+            match self.pixel_format() {
+                PixelFormat::Bgra8 | PixelFormat::Rgba8 => (4, |pixel| match pixel[3] {
+                    u8::MAX => Some(true),
+                    0 => Some(false),
+                    _ => None,
+                }),
+                PixelFormat::Rgba16Unorm => {
+                    (8, |pixel| match u16::from_le_bytes([pixel[6], pixel[7]]) {
+                        u16::MAX => Some(true),
+                        0 => Some(false),
+                        _ => None,
+                    })
+                }
+                PixelFormat::Rgba16Float => (8, |pixel| {
+                    let alpha = f16_to_f32(u16::from_le_bytes([pixel[6], pixel[7]]));
+                    if alpha >= 1.0 {
+                        Some(true)
+                    } else if alpha <= 0.0 {
+                        Some(false)
+                    } else {
+                        None
+                    }
+                }),
+                PixelFormat::Rgb8
+                | PixelFormat::Nv12
+                | PixelFormat::I420
+                | PixelFormat::P010
+                | PixelFormat::Yuyv => return None,
+            };
+        if self.alpha_mode == AlphaMode::Opaque {
+            return Some(AlphaCoverage::FullyOpaque);
+        }
+
+        let (mut opaque, mut transparent) = (false, false);
+        for row in self.rows()? {
+            for pixel in row.chunks_exact(bytes_per_pixel) {
+                match opacity(pixel) {
+                    Some(true) => opaque = true,
+                    Some(false) => transparent = true,
+                    None => return Some(AlphaCoverage::Mixed),
+                }
+                if opaque && transparent {
+                    return Some(AlphaCoverage::Mixed);
+                }
+            }
+        }
+        Some(if opaque {
+            AlphaCoverage::FullyOpaque
+        } else {
+            AlphaCoverage::FullyTransparent
+        })
+    }
+
+    /// Get the color of this frame as a BGRA pixel, if every visible pixel
+    /// is the same, so that it can be drawn as a solid fill instead of being
+    /// uploaded.
+    ///
+    /// Scanning stops at the first pixel that differs. YUV frames are solid
+    /// when all of their luma and chroma samples are, and their color is
+    /// converted as in [`Self::to_bgra`].
+    ///
+    /// Returns `None` for hardware-backed frames.
+    pub fn dominant_solid_color(&self) -> Option<[u8; 4]> {
+        let planes = self.cpu_planes()?;
+        let sample_sizes: &[usize] = match self.pixel_format() {
+            PixelFormat::Bgra8 | PixelFormat::Rgba8 => &[4],
+            PixelFormat::Rgb8 => &[3],
+            PixelFormat::Nv12 => &[1, 2],
+            PixelFormat::I420 => &[1, 1, 1],
+            PixelFormat::P010 => &[2, 4],
+            PixelFormat::Yuyv => &[4],
+            PixelFormat::Rgba16Unorm | PixelFormat::Rgba16Float => &[8],
+        };
+        let mut samples = Vec::with_capacity(planes.len());
+        for (plane, &sample_size) in planes.iter().zip(sample_sizes) {
+            let first = plane.rows().next()?.get(..sample_size)?;
+            let solid = plane
+                .rows()
+                .all(|row| row.chunks_exact(sample_size).all(|sample| sample == first));
+            if !solid {
+                return None;
+            }
+            samples.push(first);
+        }
+
+        let conversion = self.yuv_to_rgb();
+        let high_byte = |sample: &[u8]| (u16::from_ne_bytes([sample[0], sample[1]]) >> 8) as f32;
+        Some(match (self.pixel_format(), samples.as_slice()) {
+            (PixelFormat::Bgra8, [pixel]) => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            (PixelFormat::Rgba8, [pixel]) => [pixel[2], pixel[1], pixel[0], pixel[3]],
+            (PixelFormat::Rgb8, [pixel]) => [pixel[2], pixel[1], pixel[0], 255],
+            (PixelFormat::Nv12, [y, uv]) => {
+                conversion.to_bgra(y[0] as f32, uv[0] as f32, uv[1] as f32)
+            }
+            (PixelFormat::I420, [y, u, v]) => {
+                conversion.to_bgra(y[0] as f32, u[0] as f32, v[0] as f32)
+            }
+            (PixelFormat::P010, [y, uv]) => {
+                conversion.to_bgra(high_byte(y), high_byte(&uv[..2]), high_byte(&uv[2..]))
+            }
+            (PixelFormat::Yuyv, [pair]) if pair[0] == pair[2] => {
+                conversion.to_bgra(pair[0] as f32, pair[1] as f32, pair[3] as f32)
+            }
+            (PixelFormat::Rgba16Unorm | PixelFormat::Rgba16Float, [pixel]) => {
+                let [red, green, blue, alpha] =
+                    rgba16_to_rgba8(pixel, self.pixel_format() == PixelFormat::Rgba16Float);
+                [blue, green, red, alpha]
+            }
+            _ => return None,
+        })
+    }
+
+    /// Count the luma of every visible pixel into `bins` equally sized bins,
+    /// from black in the first bin to white in the last, such as for an
+    /// exposure indicator or a levels widget.
+    ///
+    /// YUV frames are binned by their luma samples as recorded, skipping
+    /// chroma and row padding. RGB frames are binned by the luma of each
+    /// pixel, weighted by the coefficients of the frame's color space.
+    /// Hardware-backed and 16-bit RGBA frames are converted to BGRA first. A
+    /// `bins` of 0 gives an empty histogram.
+    pub fn luma_histogram(&self, bins: usize) -> Result<Vec<u32>, VideoFrameError> {
+        let mut histogram = vec![0; bins];
+        if bins == 0 {
+            return Ok(histogram);
+        }
+        let as_bgra = || -> Result<VideoFrame, VideoFrameError> {
+            let bgra = self
+                .bgra_buffer()
+                .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string()))?;
+            Ok(self.with_packed_buffer(bgra, self.width, self.height, PixelFormat::Bgra8))
+        };
+        let Some(planes) = self.cpu_planes() else {
+            return as_bgra()?.luma_histogram(bins);
+        };
+        let mut count = |value: usize, levels: usize| histogram[value * bins / levels] += 1;
+
+        let (kr, kb) = self.color_space.luma_coefficients();
+        let kg = 1.0 - kr - kb;
+        let (pixel_size, red, blue) = match self.pixel_format() {
+            PixelFormat::Bgra8 => (4, 2, 0),
+            PixelFormat::Rgba8 => (4, 0, 2),
+            PixelFormat::Rgb8 => (3, 0, 2),
+            PixelFormat::Nv12 | PixelFormat::I420 => {
+                for row in planes[0].rows() {
+                    row.iter().for_each(|luma| count(*luma as usize, 256));
+                }
+                return Ok(histogram);
+            }
+            PixelFormat::P010 => {
+                for row in planes[0].rows() {
+                    for luma in row.chunks_exact(2) {
+                        count(u16::from_ne_bytes([luma[0], luma[1]]) as usize, 1 << 16);
+                    }
+                }
+                return Ok(histogram);
+            }
+            PixelFormat::Yuyv => {
+                for row in planes[0].rows() {
+                    row.iter()
+                        .step_by(2)
+                        .for_each(|luma| count(*luma as usize, 256));
+                }
+                return Ok(histogram);
+            }
+            PixelFormat::Rgba16Unorm | PixelFormat::Rgba16Float => {
+                return as_bgra()?.luma_histogram(bins);
+            }
+        };
+        for row in planes[0].rows() {
+            for pixel in row.chunks_exact(pixel_size) {
+                let luma = kr * pixel[red] as f32 + kg * pixel[1] as f32 + kb * pixel[blue] as f32;
+                count((luma.round() as usize).min(255), 256);
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// Whether at least `fraction` of this frame's pixels have a luma below
+    /// `luma_threshold`, such as for showing an avatar instead of a camera
+    /// feed that's covered or disabled.
+    ///
+    /// Every pixel is scanned, using [`Self::luma_histogram`], so YUV frames
+    /// are judged by their luma plane. `luma_threshold` is in full range; for
+    /// limited-range YUV frames it's scaled into the 16–235 range their luma
+    /// spans, so that their black counts as black.
+    pub fn is_mostly_black(
+        &self,
+        luma_threshold: u8,
+        fraction: f32,
+    ) -> Result<bool, VideoFrameError> {
+        let histogram = self.luma_histogram(256)?;
+        let limited_yuv = self.color_range == ColorRange::Limited
+            && !self.is_hardware_backed()
+            && matches!(
+                self.pixel_format(),
+                PixelFormat::Nv12 | PixelFormat::I420 | PixelFormat::P010 | PixelFormat::Yuyv
+            );
+        let threshold = if limited_yuv {
+            16 + luma_threshold as usize * 219 / 255
+        } else {
+            luma_threshold as usize
+        };
+        let dark_pixels: u64 = histogram[..threshold]
+            .iter()
+            .map(|&count| count as u64)
+            .sum();
+        let pixel_count = self.width as u64 * self.height as u64;
+        Ok(dark_pixels as f64 >= fraction as f64 * pixel_count as f64)
+    }
+
+    /// Convert this frame into a CPU-backed BGRA frame.
+    ///
+    /// BGRA frames are returned as-is, sharing the same buffer. Frames in other
+    /// formats are converted on the CPU, and hardware-backed frames are first
+    /// read back from the GPU. The returned frame always has its pixels available
+    /// via [`Self::as_bytes`], tightly packed as `width * height * 4` bytes.
+    ///
+    /// P010 frames are reduced to 8 bits per sample before conversion, without
+    /// tone mapping. 16-bit RGBA channels are rounded to 8 bits, with float
+    /// channels clamped to `0.0..=1.0`.
+    ///
+    /// If a hardware readback fails, the error is logged and a black frame of
+    /// the same size is returned.
+    pub fn to_bgra(&self) -> VideoFrame {
+        if let VideoFrameData::Bgra { stride, .. } = &self.data
+            && *stride == self.width * 4
+        {
+            return self.clone();
+        }
+        let buffer = self
+            .bgra_buffer()
+            .log_err()
+            .unwrap_or_else(|| self.black_bgra_buffer());
+        self.with_packed_buffer(buffer, self.width, self.height, PixelFormat::Bgra8)
+            .with_color_space(VideoColorSpace::Srgb)
+    }
+
+    /// Convert this frame into a tightly packed BGRA buffer, taking ownership
+    /// of the pixels to hand them to an encoder or another library.
+    ///
+    /// When this is a tightly packed BGRA frame whose buffer isn't shared with
+    /// any other frame, the buffer is returned without copying. Shared buffers
+    /// are copied, other formats are converted as in [`Self::to_bgra`], and
+    /// hardware-backed frames are read back from the GPU. Unlike
+    /// [`Self::to_bgra`], a failed readback is returned as an error.
+    pub fn into_bgra_vec(self) -> Result<Vec<u8>, VideoFrameError> {
+        let len = self.width as usize * self.height as usize * 4;
+        match self.data {
+            VideoFrameData::Bgra { buffer, stride } if stride == self.width * 4 => {
+                let mut buffer =
+                    Arc::try_unwrap(buffer).unwrap_or_else(|buffer| buffer[..len].to_vec());
+                buffer.truncate(len);
+                Ok(buffer)
+            }
+            _ => self
+                .bgra_buffer()
+                .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string())),
+        }
+    }
+
+    /// Convert the pixels of this frame to a tightly packed BGRA buffer.
+    fn bgra_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        record_stat(if self.is_hardware_backed() {
+            VideoStat::GpuReadback
+        } else {
+            VideoStat::CpuConversion
+        });
+        Ok(match &self.data {
+            VideoFrameData::Bgra { buffer, stride } => {
+                pack_rows(buffer, *stride, self.width * 4, self.height)
+            }
+            VideoFrameData::Rgba { buffer, stride } => {
+                let mut bgra = pack_rows(buffer, *stride, self.width * 4, self.height);
+                swap_red_blue(&mut bgra);
+                bgra
+            }
+            VideoFrameData::Rgb { buffer } => {
+                let pixel_count = self.width as usize * self.height as usize;
+                let mut bgra = Vec::with_capacity(pixel_count * 4);
+                for pixel in buffer.chunks_exact(3).take(pixel_count) {
+                    bgra.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+                }
+                bgra
+            }
+            VideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => Yuv420Planes::nv12(y_plane, *y_stride, uv_plane, *uv_stride).to_bgra(
+                self.width,
+                self.height,
+                self.yuv_to_rgb(),
+            ),
+            VideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => Yuv420Planes::i420(y, *y_stride, u, *u_stride, v, *v_stride).to_bgra(
+                self.width,
+                self.height,
+                self.yuv_to_rgb(),
+            ),
+            VideoFrameData::P010 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => {
+                let y_plane = most_significant_bytes(y_plane);
+                let uv_plane = most_significant_bytes(uv_plane);
+                Yuv420Planes::nv12(&y_plane, *y_stride, &uv_plane, *uv_stride).to_bgra(
+                    self.width,
+                    self.height,
+                    self.yuv_to_rgb(),
+                )
+            }
+            VideoFrameData::Yuyv { buffer, stride } => {
+                yuyv_to_bgra(buffer, *stride, self.width, self.height, self.yuv_to_rgb())
+            }
+            VideoFrameData::Rgba16 { buffer, float } => {
+                let mut bgra = Vec::with_capacity(buffer.len() / 2);
+                for pixel in buffer.chunks_exact(8) {
+                    let [red, green, blue, alpha] = rgba16_to_rgba8(pixel, *float);
+                    bgra.extend_from_slice(&[blue, green, red, alpha]);
+                }
+                bgra
+            }
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(buffer) => {
+                cv_pixel_buffer_to_bgra(buffer, self.yuv_to_rgb())?
+            }
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 {
+                texture,
+                subresource_index,
+            } => d3d11_texture_to_bgra(
+                texture,
+                *subresource_index,
+                self.width,
+                self.height,
+                self.yuv_to_rgb(),
+            )?,
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf {
+                fd,
+                fourcc,
+                modifier,
+                stride,
+                offset,
+            } => dma_buf_to_bgra(
+                fd,
+                *fourcc,
+                *modifier,
+                *stride,
+                *offset,
+                self.width,
+                self.height,
+                self.yuv_to_rgb(),
+            )?,
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => {
+                anyhow::bail!("wgpu textures can't be read back without their device")
+            }
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(source) => {
+                ffmpeg::av_frame_to_bgra(source, self.width, self.height, self.yuv_to_rgb())?
+            }
+        })
+    }
+
+    /// Convert this frame into a CPU-backed BGRA frame on a background thread,
+    /// as described in [`Self::to_bgra`], including any hardware readback.
+    ///
+    /// Frames that are already tightly packed BGRA resolve immediately. The
+    /// conversion is cancelled if the returned task is dropped, so it must be
+    /// awaited or detached.
+    pub fn to_bgra_async(&self, executor: &BackgroundExecutor) -> Task<VideoFrame> {
+        if let VideoFrameData::Bgra { stride, .. } = &self.data
+            && *stride == self.width * 4
+        {
+            return Task::ready(self.clone());
+        }
+        let frame = self.clone();
+        executor.spawn(async move { frame.to_bgra() })
+    }
+
+    /// Swap the red and blue channels, turning a BGRA frame into a tightly
+    /// packed RGBA frame and vice versa.
+    ///
+    /// Frames in any other format are converted to BGRA first, as described in
+    /// [`Self::to_bgra`], so they come back as RGBA.
+    pub fn swap_rb(&self) -> VideoFrame {
+        let (mut pixels, format) = match &self.data {
+            VideoFrameData::Rgba { buffer, stride } => (
+                pack_rows(buffer, *stride, self.width * 4, self.height),
+                PixelFormat::Bgra8,
+            ),
+            _ => (
+                self.to_bgra().as_bytes().unwrap_or_default().to_vec(),
+                PixelFormat::Rgba8,
+            ),
+        };
+        swap_red_blue(&mut pixels);
+        self.with_packed_buffer(pixels, self.width, self.height, format)
+            .with_color_space(VideoColorSpace::Srgb)
+    }
+
+    /// Convert an NV12 frame into a tightly packed BGRA frame on the CPU, for
+    /// renderers without a YUV path.
+    ///
+    /// Unlike [`Self::to_bgra`], which repeats each chroma sample across its
+    /// 2x2 block, chroma is upsampled with bilinear interpolation between
+    /// sample centers. The matrix is selected by the frame's [`VideoColorSpace`] and
+    /// limited-range samples are expanded according to its [`ColorRange`].
+    ///
+    /// Returns [`VideoFrameError::UnsupportedFormat`] for frames in any other
+    /// format, and [`VideoFrameError::UnsupportedForHardwareFrame`] for
+    /// hardware-backed frames.
+    pub fn nv12_to_bgra(&self) -> Result<VideoFrame, VideoFrameError> {
+        let VideoFrameData::Nv12 {
+            y_plane,
+            uv_plane,
+            y_stride,
+            uv_stride,
+        } = &self.data
+        else {
+            return Err(match self.cpu_planes() {
+                Some(_) => VideoFrameError::UnsupportedFormat(self.pixel_format()),
+                None => VideoFrameError::UnsupportedForHardwareFrame,
+            });
+        };
+        let buffer = Yuv420Planes::nv12(y_plane, *y_stride, uv_plane, *uv_stride).to_bgra_bilinear(
+            self.width,
+            self.height,
+            self.yuv_to_rgb(),
+        );
+        Ok(self
+            .with_packed_buffer(buffer, self.width, self.height, PixelFormat::Bgra8)
+            .with_color_space(VideoColorSpace::Srgb))
+    }
+
+    /// Convert an NV12 or I420 frame into a packed 4:2:2 YUYV frame, for
+    /// consumers that only accept packed YUV.
+    ///
+    /// Each chroma sample is repeated for the two rows it covers, and luma
+    /// and the frame's color metadata are kept as they are. YUYV frames are
+    /// returned as is. Returns [`VideoFrameError::OddWidth`] for frames with an
+    /// odd width, since YUYV stores pixels in pairs,
+    /// [`VideoFrameError::UnsupportedFormat`] for frames in other formats, and
+    /// [`VideoFrameError::UnsupportedForHardwareFrame`] for hardware-backed
+    /// frames.
+    pub fn to_packed_yuyv(&self) -> Result<VideoFrame, VideoFrameError> {
+        let planes = match &self.data {
+            VideoFrameData::Yuyv { .. } => return Ok(self.clone()),
+            VideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => Yuv420Planes::nv12(y_plane, *y_stride, uv_plane, *uv_stride),
+            VideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => Yuv420Planes::i420(y, *y_stride, u, *u_stride, v, *v_stride),
+            _ if self.cpu_planes().is_some() => {
+                return Err(VideoFrameError::UnsupportedFormat(self.pixel_format()));
+            }
+            _ => return Err(VideoFrameError::UnsupportedForHardwareFrame),
+        };
+        if !self.width.is_multiple_of(2) {
+            return Err(VideoFrameError::OddWidth(self.width));
+        }
+        record_stat(VideoStat::CpuConversion);
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut yuyv = Vec::with_capacity(width * height * 2);
+        for row in 0..height {
+            for column in (0..width).step_by(2) {
+                let (cb, cr) = planes.chroma(column / 2, row / 2);
+                yuyv.extend_from_slice(&[
+                    planes.luma(column, row),
+                    cb,
+                    planes.luma(column + 1, row),
+                    cr,
+                ]);
+            }
+        }
+        Ok(self.with_data(VideoFrameData::Yuyv {
+            buffer: Arc::new(yuyv),
+            stride: self.width * 2,
+        }))
+    }
+
+    /// Convert a BGRA or RGBA frame into a planar I420 frame, such as for
+    /// feeding a software encoder.
+    ///
+    /// Samples are computed with the coefficients of the frame's color space,
+    /// with sRGB using those of BT.709, and scaled to the frame's color range.
+    /// Each chroma sample averages the block of up to 2×2 pixels it covers,
+    /// with the chroma planes rounded up for odd dimensions. Alpha is dropped.
+    /// I420 frames are returned as is. Returns
+    /// [`VideoFrameError::UnsupportedFormat`] for frames in other formats and
+    /// [`VideoFrameError::UnsupportedForHardwareFrame`] for hardware-backed
+    /// frames.
+    pub fn to_i420(&self) -> Result<VideoFrame, VideoFrameError> {
+        if let VideoFrameData::I420 { .. } = self.data {
+            return Ok(self.clone());
+        }
+        let (pixels, stride, format) = self.packed_pixels()?;
+        record_stat(VideoStat::CpuConversion);
+        let (red, blue) = match format {
+            PixelFormat::Rgba8 => (0, 2),
+            _ => (2, 0),
+        };
+        let (kr, kb) = self.color_space.luma_coefficients();
+        let kg = 1.0 - kr - kb;
+        let (luma_offset, luma_scale, chroma_scale) = if self.color_range == ColorRange::Limited {
+            (16.0, 219.0 / 255.0, 224.0 / 255.0)
+        } else {
+            (0.0, 1.0, 1.0)
+        };
+        let to_sample = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+        let pixel = |column: usize, row: usize| {
+            let pixel = &pixels[row * stride + column * 4..][..4];
+            [pixel[red], pixel[1], pixel[blue]].map(f32::from)
+        };
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        let mut y = Vec::with_capacity(width * height);
+        for row in 0..height {
+            for column in 0..width {
+                let [r, g, b] = pixel(column, row);
+                y.push(to_sample(
+                    luma_offset + (kr * r + kg * g + kb * b) * luma_scale,
+                ));
+            }
+        }
+        let mut u = Vec::with_capacity(chroma_width * chroma_height);
+        let mut v = Vec::with_capacity(chroma_width * chroma_height);
+        for chroma_row in 0..chroma_height {
+            for chroma_column in 0..chroma_width {
+                let mut sum = [0.0; 3];
+                let mut count = 0.0;
+                for row in chroma_row * 2..(chroma_row * 2 + 2).min(height) {
+                    for column in chroma_column * 2..(chroma_column * 2 + 2).min(width) {
+                        let rgb = pixel(column, row);
+                        sum.iter_mut()
+                            .zip(rgb)
+                            .for_each(|(sum, value)| *sum += value);
+                        count += 1.0;
+                    }
+                }
+                let [r, g, b] = sum.map(|sum| sum / count);
+                let luma = kr * r + kg * g + kb * b;
+                u.push(to_sample(
+                    128.0 + (b - luma) / (2.0 * (1.0 - kb)) * chroma_scale,
+                ));
+                v.push(to_sample(
+                    128.0 + (r - luma) / (2.0 * (1.0 - kr)) * chroma_scale,
+                ));
+            }
+        }
+        let color_space = match self.color_space {
+            VideoColorSpace::Srgb => VideoColorSpace::Bt709,
+            color_space => color_space,
+        };
+        Ok(self
+            .with_data(VideoFrameData::I420 {
+                y: Arc::new(y),
+                u: Arc::new(u),
+                v: Arc::new(v),
+                y_stride: self.width,
+                u_stride: chroma_width as u32,
+                v_stride: chroma_width as u32,
+            })
+            .with_color_space(color_space)
+            .with_alpha_mode(AlphaMode::Opaque))
+    }
+
+    /// Convert this frame to whichever of the `accepted` formats is cheapest to
+    /// reach, such as for handing frames to an encoder or another library.
+    ///
+    /// The frame is returned as is when its format is accepted. Otherwise,
+    /// reordering or repacking channels, as between BGRA and RGBA or from
+    /// NV12 to YUYV, is preferred over converting between YUV and RGB, and
+    /// other YUV frames are converted to I420 through BGRA. Frames are
+    /// read back from the GPU only when they have to be. Among formats that
+    /// are equally cheap, the one listed first in `accepted` wins. Returns
+    /// [`VideoFrameError::UnsupportedFormat`] if none of the `accepted`
+    /// formats can be converted to.
+    pub fn negotiate(&self, accepted: &[PixelFormat]) -> Result<VideoFrame, VideoFrameError> {
+        let format = self.pixel_format();
+        if accepted.contains(&format) {
+            return Ok(self.clone());
+        }
+        let target = accepted
+            .iter()
+            .filter_map(|&target| Some((target, self.conversion_cost(target)?)))
+            .min_by_key(|(_, cost)| *cost)
+            .map(|(target, _)| target)
+            .ok_or(VideoFrameError::UnsupportedFormat(format))?;
+        match target {
+            PixelFormat::Bgra8 => Ok(self.to_bgra()),
+            PixelFormat::Rgba8 => {
+                let bgra = self.to_bgra();
+                let rgba = bgra.to_rgba_pixels();
+                Ok(bgra.with_packed_buffer(rgba, self.width, self.height, PixelFormat::Rgba8))
+            }
+            PixelFormat::Rgb8 => {
+                let bgra = self.to_bgra();
+                let rgb = bgra
+                    .as_bytes()
+                    .unwrap_or_default()
+                    .chunks_exact(4)
+                    .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+                    .collect();
+                Ok(bgra
+                    .with_data(VideoFrameData::Rgb {
+                        buffer: Arc::new(rgb),
+                    })
+                    .with_alpha_mode(AlphaMode::Opaque))
+            }
+            PixelFormat::Yuyv => self.to_packed_yuyv(),
+            PixelFormat::I420 if self.packed_buffer().is_some() => self.to_i420(),
+            PixelFormat::I420 => self.to_bgra().to_i420(),
+            _ => Err(VideoFrameError::UnsupportedFormat(format)),
+        }
+    }
+
+    /// The relative cost of converting this frame to `target` in
+    /// [`Self::negotiate`], or `None` if it can't be converted to it.
+    fn conversion_cost(&self, target: PixelFormat) -> Option<u32> {
+        let format = self.pixel_format();
+        let hardware_backed = self.is_hardware_backed();
+        let rgb_source = matches!(
+            format,
+            PixelFormat::Bgra8
+                | PixelFormat::Rgba8
+                | PixelFormat::Rgb8
+                | PixelFormat::Rgba16Unorm
+                | PixelFormat::Rgba16Float
+        );
+        let cost = match target {
+            _ if target == format => 0,
+            PixelFormat::Bgra8 | PixelFormat::Rgba8 | PixelFormat::Rgb8 if rgb_source => 1,
+            PixelFormat::Bgra8 | PixelFormat::Rgba8 | PixelFormat::Rgb8 => 2,
+            PixelFormat::Yuyv
+                if !hardware_backed && matches!(format, PixelFormat::Nv12 | PixelFormat::I420) =>
+            {
+                1
+            }
+            PixelFormat::I420 if rgb_source => 2,
+            // Other YUV frames go through BGRA.
+            PixelFormat::I420 => 3,
+            _ => return None,
+        };
+        Some(if hardware_backed { cost + 2 } else { cost })
+    }
+
+    /// Extract a tightly packed, single-channel `width * height` buffer of the
+    /// frame's luma.
+    ///
+    /// For YUV frames this is a copy of the luma plane, which already reflects
+    /// the frame's [`VideoColorSpace`]; P010 frames are reduced to 8 bits. For RGB
+    /// frames luma is computed with the Rec. 709 weights
+    /// `0.2126 R + 0.7152 G + 0.0722 B`, ignoring alpha.
+    ///
+    /// Hardware-backed frames aren't supported; call [`Self::to_bgra`] first.
+    pub fn to_luma8(&self) -> Result<Vec<u8>, VideoFrameError> {
+        let (width, height) = (self.width, self.height);
+        match &self.data {
+            VideoFrameData::Nv12 {
+                y_plane, y_stride, ..
+            } => Ok(pack_rows(y_plane, *y_stride, width, height)),
+            VideoFrameData::I420 { y, y_stride, .. } => Ok(pack_rows(y, *y_stride, width, height)),
+            VideoFrameData::P010 {
+                y_plane, y_stride, ..
+            } => Ok(pack_rows(
+                &most_significant_bytes(y_plane),
+                *y_stride,
+                width,
+                height,
+            )),
+            VideoFrameData::Yuyv { buffer, stride } => {
+                let mut luma = Vec::with_capacity(width as usize * height as usize);
+                for row in buffer.chunks(*stride as usize).take(height as usize) {
+                    luma.extend(row[..width as usize * 2].iter().step_by(2));
+                }
+                Ok(luma)
+            }
+            VideoFrameData::Rgb { buffer } => Ok(buffer
+                .chunks_exact(3)
+                .take(width as usize * height as usize)
+                .map(|pixel| rec709_luma(pixel[0], pixel[1], pixel[2]))
+                .collect()),
+            _ => {
+                let (pixels, stride, format) = self.packed_pixels()?;
+                let mut luma = Vec::with_capacity(width as usize * height as usize);
+                for row in pixels.chunks(stride).take(height as usize) {
+                    for pixel in row[..width as usize * 4].chunks_exact(4) {
+                        luma.push(match format {
+                            PixelFormat::Rgba8 => rec709_luma(pixel[0], pixel[1], pixel[2]),
+                            _ => rec709_luma(pixel[2], pixel[1], pixel[0]),
+                        });
+                    }
+                }
+                Ok(luma)
+            }
+        }
+    }
+
+    /// Convert this frame into an [`image::RgbaImage`].
+    ///
+    /// Frames in other formats are converted to RGBA, and hardware-backed frames
+    /// are read back as described in [`Self::to_bgra`].
+    pub fn to_image(&self) -> Option<image::RgbaImage> {
+        image::RgbaImage::from_raw(self.width, self.height, self.to_rgba_pixels())
+    }
+
+    /// Encode this frame as an 8-bit RGBA PNG.
+    ///
+    /// Frames in other formats are converted to RGBA, and hardware-backed frames
+    /// are read back as described in [`Self::to_bgra`]. Row padding isn't
+    /// written.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: std::io::Write>(&self, writer: W) -> std::io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.to_rgba_pixels())?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Encode this frame as a PNG file at `path`, as described in
+    /// [`Self::write_png`].
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_png(std::io::BufWriter::new(file))
+    }
+
+    /// Get the pixels of this frame as tightly packed RGBA.
+    fn to_rgba_pixels(&self) -> Vec<u8> {
+        match &self.data {
+            VideoFrameData::Rgba { buffer, stride } => {
+                pack_rows(buffer, *stride, self.width * 4, self.height)
+            }
+            _ => {
+                let mut rgba = self.to_bgra().as_bytes().unwrap_or_default().to_vec();
+                swap_red_blue(&mut rgba);
+                rgba
+            }
+        }
+    }
+
+    /// Create an RGBA video frame from a copy of an [`image::RgbaImage`].
+    ///
+    /// Returns [`VideoFrameError::ZeroDimension`] if the image has zero width
+    /// or height. Converting an owned image with [`TryFrom`] avoids the copy.
+    pub fn from_image(image: &image::RgbaImage) -> Result<Self, VideoFrameError> {
+        Self::try_from_rgba(image.as_raw().clone(), image.width(), image.height())
+    }
+
+    fn black_bgra_buffer(&self) -> Vec<u8> {
+        [0, 0, 0, 255].repeat((self.width * self.height) as usize)
+    }
+
+    /// Get access to the luma and chroma planes of an NV12 frame, along with
+    /// their strides, as `(y_plane, uv_plane, y_stride, uv_stride)`.
+    ///
+    /// Returns `None` for frames in any other format.
+    pub fn nv12_planes(&self) -> Option<(&[u8], &[u8], u32, u32)> {
+        match &self.data {
+            VideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => Some((
+                y_plane.as_slice(),
+                uv_plane.as_slice(),
+                *y_stride,
+                *uv_stride,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Get access to the Y, U and V planes of an I420 frame, along with their
+    /// strides, as `(y, u, v, y_stride, u_stride, v_stride)`.
+    ///
+    /// Returns `None` for frames in any other format.
+    pub fn i420_planes(&self) -> Option<(&[u8], &[u8], &[u8], u32, u32, u32)> {
+        match &self.data {
+            VideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => Some((
+                y.as_slice(),
+                u.as_slice(),
+                v.as_slice(),
+                *y_stride,
+                *u_stride,
+                *v_stride,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Get the CoreVideo pixel format type (such as
+    /// `kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange`) of a frame backed by a
+    /// CoreVideo pixel buffer.
+    #[cfg(target_os = "macos")]
+    pub fn cv_pixel_format(&self) -> Option<u32> {
+        match &self.data {
+            VideoFrameData::CoreVideo(buffer) => Some(buffer.get_pixel_format()),
+            _ => None,
+        }
+    }
+
+    /// Get the number of planes in the CoreVideo pixel buffer backing this
+    /// frame, or 0 if the frame isn't backed by a planar pixel buffer.
+    #[cfg(target_os = "macos")]
+    pub fn cv_plane_count(&self) -> usize {
+        match &self.data {
+            VideoFrameData::CoreVideo(buffer) => buffer.get_plane_count(),
+            _ => 0,
+        }
+    }
+
+    /// Lock the CoreVideo pixel buffer backing this frame for reading and get
+    /// the bytes of one of its planes, without converting the frame.
+    ///
+    /// The base address stays locked until the returned guard is dropped, so it
+    /// should be dropped as soon as the plane has been read or uploaded.
+    /// Returns `None` if the frame isn't backed by a CoreVideo pixel buffer,
+    /// `plane` is out of range, or the buffer can't be locked.
+    #[cfg(target_os = "macos")]
+    pub fn cv_plane_bytes(&self, plane: usize) -> Option<CvPlaneBytes<'_>> {
+        use core_video::{pixel_buffer::kCVPixelBufferLock_ReadOnly, r#return::kCVReturnSuccess};
+
+        let VideoFrameData::CoreVideo(buffer) = &self.data else {
+            return None;
+        };
+        if plane >= buffer.get_plane_count() {
+            return None;
+        }
+        unsafe {
+            if buffer.lock_base_address(kCVPixelBufferLock_ReadOnly) != kCVReturnSuccess {
+                return None;
+            }
+            let bytes_per_row = buffer.get_bytes_per_row_of_plane(plane);
+            let bytes = std::slice::from_raw_parts(
+                buffer.get_base_address_of_plane(plane) as *const u8,
+                bytes_per_row * buffer.get_height_of_plane(plane),
+            );
+            Some(CvPlaneBytes {
+                buffer: buffer.as_raw(),
+                bytes,
+                bytes_per_row,
+            })
+        }
+    }
+}
+
+/// The bytes of one plane of a CoreVideo pixel buffer, returned by
+/// [`VideoFrame::cv_plane_bytes`]. The pixel buffer's base address is unlocked
+/// when this is dropped.
+#[cfg(target_os = "macos")]
+pub struct CvPlaneBytes<'a> {
+    buffer: &'a core_video::pixel_buffer::CVPixelBuffer,
+    bytes: &'a [u8],
+    bytes_per_row: usize,
+}
+
+#[cfg(target_os = "macos")]
+impl CvPlaneBytes<'_> {
+    /// The number of bytes between the starts of consecutive rows of the plane.
+    pub fn bytes_per_row(&self) -> usize {
+        self.bytes_per_row
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl std::ops::Deref for CvPlaneBytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.bytes
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for CvPlaneBytes<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.buffer
+                .unlock_base_address(core_video::pixel_buffer::kCVPixelBufferLock_ReadOnly)
+        };
+    }
+}
+
+/// Borrowed planes of a frame with 4:2:0 chroma subsampling.
+#[derive(Clone, Copy)]
+struct Yuv420Planes<'a> {
+    y_plane: &'a [u8],
+    y_stride: usize,
+    chroma: ChromaPlanes<'a>,
+}
+
+#[derive(Clone, Copy)]
+enum ChromaPlanes<'a> {
+    /// Cb and Cr samples interleaved in one plane, as in NV12.
+    Interleaved {
+        uv_plane: &'a [u8],
+        uv_stride: usize,
+    },
+    /// Cb and Cr samples in separate planes, as in I420.
+    Separate {
+        u_plane: &'a [u8],
+        u_stride: usize,
+        v_plane: &'a [u8],
+        v_stride: usize,
+    },
+}
+
+impl<'a> Yuv420Planes<'a> {
+    fn nv12(y_plane: &'a [u8], y_stride: u32, uv_plane: &'a [u8], uv_stride: u32) -> Self {
+        Self {
+            y_plane,
+            y_stride: y_stride as usize,
+            chroma: ChromaPlanes::Interleaved {
+                uv_plane,
+                uv_stride: uv_stride as usize,
+            },
+        }
+    }
+
+    fn i420(
+        y_plane: &'a [u8],
+        y_stride: u32,
+        u_plane: &'a [u8],
+        u_stride: u32,
+        v_plane: &'a [u8],
+        v_stride: u32,
+    ) -> Self {
+        Self {
+            y_plane,
+            y_stride: y_stride as usize,
+            chroma: ChromaPlanes::Separate {
+                u_plane,
+                u_stride: u_stride as usize,
+                v_plane,
+                v_stride: v_stride as usize,
+            },
+        }
+    }
+
+    fn luma(&self, column: usize, row: usize) -> u8 {
+        self.y_plane
+            .get(row * self.y_stride + column)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The (Cb, Cr) pair at the given position in chroma sample coordinates.
+    fn chroma(&self, column: usize, row: usize) -> (u8, u8) {
+        match self.chroma {
+            ChromaPlanes::Interleaved {
+                uv_plane,
+                uv_stride,
+            } => {
+                let index = row * uv_stride + column * 2;
+                (
+                    uv_plane.get(index).copied().unwrap_or(128),
+                    uv_plane.get(index + 1).copied().unwrap_or(128),
+                )
+            }
+            ChromaPlanes::Separate {
+                u_plane,
+                u_stride,
+                v_plane,
+                v_stride,
+            } => (
+                u_plane.get(row * u_stride + column).copied().unwrap_or(128),
+                v_plane.get(row * v_stride + column).copied().unwrap_or(128),
+            ),
+        }
+    }
+
+    /// Convert to a tightly packed BGRA buffer on the CPU. For BT.601 these are
+    /// the same coefficients as the GPU surface shaders use, so that CPU and
+    /// GPU paths produce matching colors.
+    fn to_bgra(&self, width: u32, height: u32, conversion: YuvToRgb) -> Vec<u8> {
+        let width = width as usize;
+        let height = height as usize;
+        let mut bgra = vec![0; width * height * 4];
+        for row in 0..height {
+            for column in 0..width {
+                let (cb, cr) = self.chroma(column / 2, row / 2);
+                let offset = (row * width + column) * 4;
+                bgra[offset..offset + 4].copy_from_slice(&conversion.to_bgra(
+                    self.luma(column, row) as f32,
+                    cb as f32,
+                    cr as f32,
+                ));
+            }
+        }
+        bgra
+    }
+
+    /// Like [`Self::to_bgra`], but interpolating chroma bilinearly between the
+    /// centers of the chroma samples instead of repeating each one.
+    fn to_bgra_bilinear(&self, width: u32, height: u32, conversion: YuvToRgb) -> Vec<u8> {
+        let chroma_width = width.div_ceil(2) as usize;
+        let chroma_height = height.div_ceil(2) as usize;
+        let (width, height) = (width as usize, height as usize);
+        let neighbors = |position: usize, len: usize| {
+            let center = ((position as f32 + 0.5) / 2.0 - 0.5).max(0.0);
+            let first = (center as usize).min(len - 1);
+            let second = (first + 1).min(len - 1);
+            (first, second, center - first as f32)
+        };
+        let mut bgra = vec![0; width * height * 4];
+        for row in 0..height {
+            let (top, bottom, vertical) = neighbors(row, chroma_height);
+            for column in 0..width {
+                let (left, right, horizontal) = neighbors(column, chroma_width);
+                let lerp = |first: (u8, u8), second: (u8, u8), weight: f32| {
+                    (
+                        first.0 as f32 + (second.0 as f32 - first.0 as f32) * weight,
+                        first.1 as f32 + (second.1 as f32 - first.1 as f32) * weight,
+                    )
+                };
+                let upper = lerp(self.chroma(left, top), self.chroma(right, top), horizontal);
+                let lower = lerp(
+                    self.chroma(left, bottom),
+                    self.chroma(right, bottom),
+                    horizontal,
+                );
+                let cb = upper.0 + (lower.0 - upper.0) * vertical;
+                let cr = upper.1 + (lower.1 - upper.1) * vertical;
+                let offset = (row * width + column) * 4;
+                bgra[offset..offset + 4].copy_from_slice(&conversion.to_bgra(
+                    self.luma(column, row) as f32,
+                    cb,
+                    cr,
+                ));
+            }
+        }
+        bgra
+    }
+}
+
+/// The coefficients for converting YUV samples to RGB in one color space.
+#[derive(Clone, Copy)]
+struct YuvToRgb {
+    luma_offset: f32,
+    luma_scale: f32,
+    chroma_scale: f32,
+    cr_to_r: f32,
+    cb_to_g: f32,
+    cr_to_g: f32,
+    cb_to_b: f32,
+}
+
+impl YuvToRgb {
+    /// Limited-range samples span 16–235 for luma and 16–240 for chroma, and
+    /// are expanded to the full 0–255 range.
+    fn new(color_space: VideoColorSpace, limited_range: bool) -> Self {
+        let (kr, kb) = color_space.luma_coefficients();
+        let kg = 1.0 - kr - kb;
+        let cr_to_r = 2.0 * (1.0 - kr);
+        let cb_to_b = 2.0 * (1.0 - kb);
+        let (luma_offset, luma_scale, chroma_scale) = if limited_range {
+            (16.0, 255.0 / 219.0, 255.0 / 224.0)
+        } else {
+            (0.0, 1.0, 1.0)
+        };
+        Self {
+            luma_offset,
+            luma_scale,
+            chroma_scale,
+            cr_to_r,
+            cb_to_g: cb_to_b * kb / kg,
+            cr_to_g: cr_to_r * kr / kg,
+            cb_to_b,
+        }
+    }
+
+    /// Convert one sample to an opaque BGRA pixel.
+    fn to_bgra(&self, y: f32, cb: f32, cr: f32) -> [u8; 4] {
+        let y = (y - self.luma_offset) * self.luma_scale;
+        let cb = (cb - 128.0) * self.chroma_scale;
+        let cr = (cr - 128.0) * self.chroma_scale;
+        let r = y + self.cr_to_r * cr;
+        let g = y - self.cb_to_g * cb - self.cr_to_g * cr;
+        let b = y + self.cb_to_b * cb;
+        [
+            b.round().clamp(0.0, 255.0) as u8,
+            g.round().clamp(0.0, 255.0) as u8,
+            r.round().clamp(0.0, 255.0) as u8,
+            255,
+        ]
+    }
+}
+
+/// Validate a packed 4-byte-per-pixel buffer.
+fn validate_packed(
+    buffer: &[u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+) -> Result<(), VideoFrameError> {
+    validate_dimensions(width, height)?;
+    let row_size = row_size(width, 4)?;
+    validate_plane(buffer.len(), row_size, stride, height)?;
+    if !stride.is_multiple_of(4) {
+        return Err(VideoFrameError::InvalidStride { stride, row_size });
+    }
+    Ok(())
+}
+
+fn validate_dimensions(width: u32, height: u32) -> Result<(), VideoFrameError> {
+    if width == 0 || height == 0 {
+        Err(VideoFrameError::ZeroDimension)
+    } else {
+        Ok(())
+    }
+}
+
+/// The number of bytes in a row of `width` samples that are `bytes_per_sample` bytes each.
+fn row_size(width: u32, bytes_per_sample: u32) -> Result<u32, VideoFrameError> {
+    width
+        .checked_mul(bytes_per_sample)
+        .ok_or(VideoFrameError::BufferTooSmall {
+            expected: usize::MAX,
+            actual: 0,
+        })
+}
+
+fn validate_len(actual: usize, expected: usize) -> Result<(), VideoFrameError> {
+    if actual < expected {
+        Err(VideoFrameError::BufferTooSmall { expected, actual })
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that `rows` rows of `row_size` bytes, each starting `stride` bytes
+/// after the previous one, fit in a plane of `len` bytes.
+fn validate_plane(
+    len: usize,
+    row_size: u32,
+    stride: u32,
+    rows: u32,
+) -> Result<(), VideoFrameError> {
+    if stride < row_size {
+        return Err(VideoFrameError::InvalidStride { stride, row_size });
+    }
+    validate_len(len, (stride as usize).saturating_mul(rows as usize))
+}
+
+/// View 16-bit samples as bytes, in native byte order.
+fn sample_bytes(samples: &[u16]) -> &[u8] {
+    // SAFETY: `u8` has no alignment requirement and every bit pattern is valid.
+    unsafe { std::slice::from_raw_parts(samples.as_ptr().cast::<u8>(), samples.len() * 2) }
+}
+
+/// Reduce a 16-bit RGBA pixel of little-endian channels to 8 bits per
+/// channel, clamping float channels to `0.0..=1.0`.
+fn rgba16_to_rgba8(pixel: &[u8], float: bool) -> [u8; 4] {
+    let mut rgba = [0; 4];
+    for (channel, bytes) in rgba.iter_mut().zip(pixel.chunks_exact(2)) {
+        let value = u16::from_le_bytes([bytes[0], bytes[1]]);
+        *channel = if float {
+            (f16_to_f32(value).clamp(0.0, 1.0) * 255.0).round() as u8
+        } else {
+            ((value as u32 * 255 + 32767) / 65535) as u8
+        };
+    }
+    rgba
+}
+
+/// Decode the bits of an IEEE 754 half-precision float.
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+/// Reduce 16-bit samples to 8 bits by keeping their most significant byte.
+fn most_significant_bytes(samples: &[u16]) -> Vec<u8> {
+    samples.iter().map(|sample| (sample >> 8) as u8).collect()
+}
+
+/// Like [`most_significant_bytes`], for samples stored as little-endian bytes.
+#[cfg(target_os = "macos")]
+fn most_significant_bytes_le(samples: &[u8]) -> Vec<u8> {
+    samples.chunks_exact(2).map(|sample| sample[1]).collect()
+}
+
+/// One plane of CPU pixel data.
+struct Plane<'a> {
+    bytes: &'a [u8],
+    /// The distance between the starts of consecutive rows, in bytes.
+    stride: usize,
+    /// The number of visible bytes in each row.
+    row_len: usize,
+    rows: usize,
+}
+
+impl<'a> Plane<'a> {
+    fn new(bytes: &'a [u8], stride: u32, row_len: usize, rows: usize) -> Self {
+        Self {
+            bytes,
+            stride: stride as usize,
+            row_len,
+            rows,
+        }
+    }
+
+    /// Iterate over the visible bytes of each row, skipping padding.
+    fn rows(&self) -> impl Iterator<Item = &'a [u8]> + use<'a> {
+        let row_len = self.row_len;
+        self.bytes
+            .chunks(self.stride)
+            .take(self.rows)
+            .map(move |row| &row[..row_len])
+    }
+
+    /// Iterate over a grid of visible bytes spread evenly over the plane,
+    /// starting at its first byte and ending at the last byte of its last row.
+    fn samples(&self) -> impl Iterator<Item = Option<u8>> + use<'a> {
+        fn spread(index: usize, length: usize) -> usize {
+            index * length.saturating_sub(1) / (SAMPLE_GRID_SIZE - 1)
+        }
+        let (bytes, stride, row_len, rows) = (self.bytes, self.stride, self.row_len, self.rows);
+        (0..SAMPLE_GRID_SIZE).flat_map(move |row| {
+            (0..SAMPLE_GRID_SIZE).map(move |column| {
+                bytes
+                    .get(spread(row, rows) * stride + spread(column, row_len))
+                    .copied()
+            })
+        })
+    }
+}
+
+/// The number of rows and columns of the grid of bytes that
+/// [`VideoFrame::likely_equal`] compares in each plane. Odd, so that the
+/// grid includes the center.
+const SAMPLE_GRID_SIZE: usize = 9;
+
+/// Convert a packed YUYV buffer whose rows are `stride` bytes apart to a
+/// tightly packed BGRA buffer, giving both pixels of each pair their shared
+/// chroma.
+fn yuyv_to_bgra(
+    buffer: &[u8],
+    stride: u32,
+    width: u32,
+    height: u32,
+    conversion: YuvToRgb,
+) -> Vec<u8> {
+    let mut bgra = Vec::with_capacity(width as usize * height as usize * 4);
+    for row in buffer.chunks(stride as usize).take(height as usize) {
+        for pair in row[..width as usize * 2].chunks_exact(4) {
+            let (cb, cr) = (pair[1] as f32, pair[3] as f32);
+            bgra.extend_from_slice(&conversion.to_bgra(pair[0] as f32, cb, cr));
+            bgra.extend_from_slice(&conversion.to_bgra(pair[2] as f32, cb, cr));
+        }
+    }
+    bgra
+}
+
+/// Copy `height` rows of `row_len` bytes out of a buffer whose rows are `stride` bytes apart.
+fn pack_rows(buffer: &[u8], stride: u32, row_len: u32, height: u32) -> Vec<u8> {
+    let row_len = row_len as usize;
+    let mut packed = Vec::with_capacity(row_len * height as usize);
+    for row in buffer.chunks(stride as usize).take(height as usize) {
+        packed.extend_from_slice(&row[..row_len.min(row.len())]);
+    }
+    packed
+}
+
+/// Compute the Rec. 709 luma of an RGB color, in 8.8 fixed point.
+fn rec709_luma(red: u8, green: u8, blue: u8) -> u8 {
+    ((red as u32 * 54 + green as u32 * 183 + blue as u32 * 19 + 128) >> 8) as u8
+}
+
+/// Swap the first and third channel of every pixel, converting between BGRA and RGBA.
+///
+/// Uses AVX2 or SSSE3 shuffles on x86_64 when the CPU supports them, and NEON
+/// on aarch64.
+fn swap_red_blue(pixels: &mut [u8]) {
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2.
+        return unsafe { swap_red_blue_avx2(pixels) };
+    }
+    #[cfg(target_arch = "x86_64")]
+    if std::arch::is_x86_feature_detected!("ssse3") {
+        // SAFETY: the CPU supports SSSE3.
+        return unsafe { swap_red_blue_ssse3(pixels) };
+    }
+    #[cfg(target_arch = "aarch64")]
+    return swap_red_blue_neon(pixels);
+    #[cfg(not(target_arch = "aarch64"))]
+    swap_red_blue_scalar(pixels);
+}
+
+fn swap_red_blue_scalar(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+}
+
+/// The byte order of four pixels with their first and third channels swapped.
+#[cfg(target_arch = "x86_64")]
+const SWAP_RED_BLUE_SHUFFLE: [i8; 16] = [2, 1, 0, 3, 6, 5, 4, 7, 10, 9, 8, 11, 14, 13, 12, 15];
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn swap_red_blue_avx2(pixels: &mut [u8]) {
+    use std::arch::x86_64::{
+        __m256i, _mm_loadu_si128, _mm256_loadu_si256, _mm256_setr_m128i, _mm256_shuffle_epi8,
+        _mm256_storeu_si256,
+    };
+
+    // SAFETY: the shuffle mask is 16 bytes long, and each chunk is 32 bytes,
+    // so every unaligned load and store stays in bounds.
+    unsafe {
+        let lane = _mm_loadu_si128(SWAP_RED_BLUE_SHUFFLE.as_ptr().cast());
+        let shuffle = _mm256_setr_m128i(lane, lane);
+        let mut chunks = pixels.chunks_exact_mut(32);
+        for chunk in &mut chunks {
+            let pointer = chunk.as_mut_ptr().cast::<__m256i>();
+            _mm256_storeu_si256(
+                pointer,
+                _mm256_shuffle_epi8(_mm256_loadu_si256(pointer), shuffle),
+            );
+        }
+        swap_red_blue_scalar(chunks.into_remainder());
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn swap_red_blue_ssse3(pixels: &mut [u8]) {
+    use std::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_shuffle_epi8, _mm_storeu_si128};
+
+    // SAFETY: the shuffle mask and each chunk are 16 bytes long, so every
+    // unaligned load and store stays in bounds.
+    unsafe {
+        let shuffle = _mm_loadu_si128(SWAP_RED_BLUE_SHUFFLE.as_ptr().cast());
+        let mut chunks = pixels.chunks_exact_mut(16);
+        for chunk in &mut chunks {
+            let pointer = chunk.as_mut_ptr().cast::<__m128i>();
+            _mm_storeu_si128(pointer, _mm_shuffle_epi8(_mm_loadu_si128(pointer), shuffle));
+        }
+        swap_red_blue_scalar(chunks.into_remainder());
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn swap_red_blue_neon(pixels: &mut [u8]) {
+    use std::arch::aarch64::{uint8x16x4_t, vld4q_u8, vst4q_u8};
+
+    let mut chunks = pixels.chunks_exact_mut(64);
+    for chunk in &mut chunks {
+        // SAFETY: NEON is always available on aarch64, and each chunk holds
+        // exactly the 16 pixels that are loaded and stored.
+        unsafe {
+            let pixel = vld4q_u8(chunk.as_ptr());
+            vst4q_u8(
+                chunk.as_mut_ptr(),
+                uint8x16x4_t(pixel.2, pixel.1, pixel.0, pixel.3),
+            );
+        }
+    }
+    swap_red_blue_scalar(chunks.into_remainder());
+}
+
+/// A CoreVideo pixel buffer that can be shared between threads.
+#[cfg(target_os = "macos")]
+#[derive(Clone, PartialEq)]
+pub(crate) struct SafeCVPixelBuffer {
+    raw: core_video::pixel_buffer::CVPixelBuffer,
+}
+
+#[cfg(target_os = "macos")]
+impl SafeCVPixelBuffer {
+    pub(crate) fn as_raw(&self) -> &core_video::pixel_buffer::CVPixelBuffer {
+        &self.raw
+    }
+}
+
+// `CVPixelBuffer` owns one retain of the buffer: `Clone` retains it and `Drop`
+// releases it, and retaining and releasing CoreFoundation objects is
+// thread-safe. The getters for the buffer's size, format and planes only read
+// attributes that are immutable after creation, and every access to the
+// pixels, in `cv_pixel_buffer_to_bgra` and `cv_plane_bytes`, locks the base
+// address for reading first. Metal textures created from the buffer through a
+// `CVMetalTextureCache` retain it for as long as they're in use.
+#[cfg(target_os = "macos")]
+unsafe impl Send for SafeCVPixelBuffer {}
+#[cfg(target_os = "macos")]
+unsafe impl Sync for SafeCVPixelBuffer {}
+
+#[cfg(target_os = "macos")]
+impl From<core_video::pixel_buffer::CVPixelBuffer> for SafeCVPixelBuffer {
+    fn from(value: core_video::pixel_buffer::CVPixelBuffer) -> Self {
+        SafeCVPixelBuffer { raw: value }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl std::ops::Deref for SafeCVPixelBuffer {
+    type Target = core_video::pixel_buffer::CVPixelBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+/// The range of a CoreVideo pixel buffer's samples, given by its pixel format.
+#[cfg(target_os = "macos")]
+pub(crate) fn cv_pixel_buffer_color_range(
+    buffer: &core_video::pixel_buffer::CVPixelBuffer,
+) -> ColorRange {
+    use core_video::pixel_buffer::kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange;
+
+    match buffer.get_pixel_format() {
+        kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange
+        | CV_PIXEL_FORMAT_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE => ColorRange::Limited,
+        _ => ColorRange::Full,
+    }
+}
+
+/// The `xf20` CoreVideo pixel format: full-range 10-bit bi-planar 4:2:0, with
+/// each sample in the high bits of a little-endian `u16`, as in P010.
+#[cfg(target_os = "macos")]
+const CV_PIXEL_FORMAT_420_YP_CB_CR_10_BI_PLANAR_FULL_RANGE: u32 = u32::from_be_bytes(*b"xf20");
+
+/// The `x420` CoreVideo pixel format: the video-range variant of `xf20`.
+#[cfg(target_os = "macos")]
+const CV_PIXEL_FORMAT_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE: u32 = u32::from_be_bytes(*b"x420");
+
+/// The pixel format of the CoreVideo pixel formats that can be read back.
+#[cfg(target_os = "macos")]
+fn cv_pixel_format(pixel_format: u32) -> Option<PixelFormat> {
+    use core_video::pixel_buffer::{
+        kCVPixelFormatType_32BGRA, kCVPixelFormatType_420YpCbCr8BiPlanarFullRange,
+        kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange,
+    };
+
+    match pixel_format {
+        kCVPixelFormatType_32BGRA => Some(PixelFormat::Bgra8),
+        kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
+        | kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange => Some(PixelFormat::Nv12),
+        CV_PIXEL_FORMAT_420_YP_CB_CR_10_BI_PLANAR_FULL_RANGE
+        | CV_PIXEL_FORMAT_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE => Some(PixelFormat::P010),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn unsupported_cv_pixel_format(pixel_format: u32) -> VideoFrameError {
+    VideoFrameError::UnsupportedSourceFormat(format!(
+        "CoreVideo {}",
+        String::from_utf8_lossy(&pixel_format.to_be_bytes())
+    ))
+}
+
+/// Copy the contents of a CoreVideo pixel buffer into a tightly packed BGRA buffer.
+#[cfg(target_os = "macos")]
+fn cv_pixel_buffer_to_bgra(
+    buffer: &core_video::pixel_buffer::CVPixelBuffer,
+    conversion: YuvToRgb,
+) -> anyhow::Result<Vec<u8>> {
+    use core_video::{
+        pixel_buffer::{
+            kCVPixelBufferLock_ReadOnly, kCVPixelFormatType_32BGRA,
+            kCVPixelFormatType_420YpCbCr8BiPlanarFullRange,
+            kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange,
+        },
+        r#return::kCVReturnSuccess,
+    };
+
+    let width = buffer.get_width();
+    let height = buffer.get_height();
+    let pixel_format = buffer.get_pixel_format();
+
+    unsafe {
+        let lock_result = buffer.lock_base_address(kCVPixelBufferLock_ReadOnly);
+        anyhow::ensure!(
+            lock_result == kCVReturnSuccess,
+            "failed to lock pixel buffer: CVReturn({lock_result})"
+        );
+
+        let result = if pixel_format == kCVPixelFormatType_32BGRA {
+            let stride = buffer.get_bytes_per_row();
+            let source =
+                std::slice::from_raw_parts(buffer.get_base_address() as *const u8, stride * height);
+            let mut bgra = Vec::with_capacity(width * height * 4);
+            for row in source.chunks_exact(stride) {
+                bgra.extend_from_slice(&row[..width * 4]);
+            }
+            Ok(bgra)
+        } else if pixel_format == kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
+            || pixel_format == kCVPixelFormatType_420YpCbCr8BiPlanarVideoRange
+        {
+            let y_stride = buffer.get_bytes_per_row_of_plane(0);
+            let uv_stride = buffer.get_bytes_per_row_of_plane(1);
+            let y_plane = std::slice::from_raw_parts(
+                buffer.get_base_address_of_plane(0) as *const u8,
+                y_stride * buffer.get_height_of_plane(0),
+            );
+            let uv_plane = std::slice::from_raw_parts(
+                buffer.get_base_address_of_plane(1) as *const u8,
+                uv_stride * buffer.get_height_of_plane(1),
+            );
+            Ok(
+                Yuv420Planes::nv12(y_plane, y_stride as u32, uv_plane, uv_stride as u32).to_bgra(
+                    width as u32,
+                    height as u32,
+                    conversion,
+                ),
+            )
+        } else if pixel_format == CV_PIXEL_FORMAT_420_YP_CB_CR_10_BI_PLANAR_FULL_RANGE
+            || pixel_format == CV_PIXEL_FORMAT_420_YP_CB_CR_10_BI_PLANAR_VIDEO_RANGE
+        {
+            let y_stride = buffer.get_bytes_per_row_of_plane(0);
+            let uv_stride = buffer.get_bytes_per_row_of_plane(1);
+            let y_plane = std::slice::from_raw_parts(
+                buffer.get_base_address_of_plane(0) as *const u8,
+                y_stride * buffer.get_height_of_plane(0),
+            );
+            let uv_plane = std::slice::from_raw_parts(
+                buffer.get_base_address_of_plane(1) as *const u8,
+                uv_stride * buffer.get_height_of_plane(1),
+            );
+            let y_plane = most_significant_bytes_le(y_plane);
+            let uv_plane = most_significant_bytes_le(uv_plane);
+            Ok(Yuv420Planes::nv12(
+                &y_plane,
+                (y_stride / 2) as u32,
+                &uv_plane,
+                (uv_stride / 2) as u32,
+            )
+            .to_bgra(width as u32, height as u32, conversion))
+        } else {
+            Err(anyhow::anyhow!(
+                "unsupported CoreVideo pixel format: {pixel_format:#x}"
+            ))
+        };
+
+        buffer.unlock_base_address(kCVPixelBufferLock_ReadOnly);
+        result
+    }
+}
+
+/// Copy the planes of a CoreVideo pixel buffer into a new IOSurface-backed
+/// pixel buffer of the same size and format.
+#[cfg(target_os = "macos")]
+fn copy_cv_pixel_buffer(
+    source: &core_video::pixel_buffer::CVPixelBuffer,
+) -> anyhow::Result<core_video::pixel_buffer::CVPixelBuffer> {
+    use core_foundation::{
+        base::{CFType, TCFType as _},
+        dictionary::CFDictionary,
+        string::CFString,
+    };
+    use core_video::{
+        pixel_buffer::{CVPixelBuffer, kCVPixelBufferLock_ReadOnly},
+        r#return::{CVReturn, kCVReturnSuccess},
+    };
+
+    unsafe extern "C" {
+        static kCVPixelBufferIOSurfacePropertiesKey: core_foundation_sys::string::CFStringRef;
+
+        fn CVPixelBufferCreate(
+            allocator: core_foundation_sys::base::CFAllocatorRef,
+            width: usize,
+            height: usize,
+            pixel_format_type: u32,
+            pixel_buffer_attributes: core_foundation_sys::dictionary::CFDictionaryRef,
+            pixel_buffer_out: *mut *mut std::ffi::c_void,
+        ) -> CVReturn;
+
+        fn CVBufferPropagateAttachments(
+            source_buffer: *mut std::ffi::c_void,
+            destination_buffer: *mut std::ffi::c_void,
+        );
+    }
+
+    let attributes: CFDictionary<CFType, CFType> = unsafe {
+        let key = CFString::wrap_under_get_rule(kCVPixelBufferIOSurfacePropertiesKey);
+        let io_surface_properties = CFDictionary::<CFType, CFType>::from_CFType_pairs(&[]);
+        CFDictionary::from_CFType_pairs(&[(key.as_CFType(), io_surface_properties.as_CFType())])
+    };
+    let mut pixel_buffer = std::ptr::null_mut();
+    let result = unsafe {
+        CVPixelBufferCreate(
+            std::ptr::null(),
+            source.get_width(),
+            source.get_height(),
+            source.get_pixel_format(),
+            attributes.as_concrete_TypeRef(),
+            &mut pixel_buffer,
+        )
+    };
+    anyhow::ensure!(
+        result == kCVReturnSuccess && !pixel_buffer.is_null(),
+        "failed to create pixel buffer: CVReturn({result})"
+    );
+    let destination = unsafe { CVPixelBuffer::wrap_under_create_rule(pixel_buffer as _) };
+
+    unsafe {
+        let lock_result = source.lock_base_address(kCVPixelBufferLock_ReadOnly);
+        anyhow::ensure!(
+            lock_result == kCVReturnSuccess,
+            "failed to lock pixel buffer: CVReturn({lock_result})"
+        );
+        let lock_result = destination.lock_base_address(0);
+        if lock_result != kCVReturnSuccess {
+            source.unlock_base_address(kCVPixelBufferLock_ReadOnly);
+            anyhow::bail!("failed to lock pixel buffer: CVReturn({lock_result})");
+        }
+
+        let copy_rows = |source_rows: *const u8,
+                         source_stride: usize,
+                         destination_rows: *mut u8,
+                         destination_stride: usize,
+                         height: usize| {
+            let row_len = source_stride.min(destination_stride);
+            for row in 0..height {
+                std::ptr::copy_nonoverlapping(
+                    source_rows.add(row * source_stride),
+                    destination_rows.add(row * destination_stride),
+                    row_len,
+                );
+            }
+        };
+        if source.get_plane_count() == 0 {
+            copy_rows(
+                source.get_base_address() as *const u8,
+                source.get_bytes_per_row(),
+                destination.get_base_address() as *mut u8,
+                destination.get_bytes_per_row(),
+                source.get_height(),
+            );
+        } else {
+            for plane in 0..source.get_plane_count() {
+                copy_rows(
+                    source.get_base_address_of_plane(plane) as *const u8,
+                    source.get_bytes_per_row_of_plane(plane),
+                    destination.get_base_address_of_plane(plane) as *mut u8,
+                    destination.get_bytes_per_row_of_plane(plane),
+                    source.get_height_of_plane(plane),
+                );
+            }
+        }
+
+        destination.unlock_base_address(0);
+        source.unlock_base_address(kCVPixelBufferLock_ReadOnly);
+        CVBufferPropagateAttachments(
+            source.as_concrete_TypeRef() as _,
+            destination.as_concrete_TypeRef() as _,
+        );
+    }
+    Ok(destination)
+}
+
+/// A D3D11 texture that can be shared between threads.
+#[cfg(target_os = "windows")]
+#[derive(Clone, PartialEq)]
+pub(crate) struct SafeD3D11Texture {
+    raw: windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
+}
+
+#[cfg(target_os = "windows")]
+impl SafeD3D11Texture {
+    pub(crate) fn as_raw(&self) -> &windows::Win32::Graphics::Direct3D11::ID3D11Texture2D {
+        &self.raw
+    }
+}
+
+// D3D11 resources are free-threaded: reference counting and the resource and
+// device methods used through this texture may be called from any thread. The
+// only thread-affine object involved is the device's immediate context, which
+// `d3d11_texture_to_bgra` locks through `ID3D11Multithread` while it's in use.
+#[cfg(target_os = "windows")]
+unsafe impl Send for SafeD3D11Texture {}
+#[cfg(target_os = "windows")]
+unsafe impl Sync for SafeD3D11Texture {}
+
+#[cfg(target_os = "windows")]
+impl From<windows::Win32::Graphics::Direct3D11::ID3D11Texture2D> for SafeD3D11Texture {
+    fn from(value: windows::Win32::Graphics::Direct3D11::ID3D11Texture2D) -> Self {
+        SafeD3D11Texture { raw: value }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl std::ops::Deref for SafeD3D11Texture {
+    type Target = windows::Win32::Graphics::Direct3D11::ID3D11Texture2D;
+
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+/// Holds the `ID3D11Multithread` lock of a device context until dropped.
+#[cfg(target_os = "windows")]
+struct DeviceContextLock(Option<windows::Win32::Graphics::Direct3D11::ID3D11Multithread>);
+
+#[cfg(target_os = "windows")]
+impl DeviceContextLock {
+    fn new(device_context: &windows::Win32::Graphics::Direct3D11::ID3D11DeviceContext) -> Self {
+        use windows::core::Interface as _;
+
+        let multithread = device_context
+            .cast::<windows::Win32::Graphics::Direct3D11::ID3D11Multithread>()
+            .ok();
+        if let Some(multithread) = &multithread {
+            unsafe { multithread.Enter() };
+        }
+        Self(multithread)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for DeviceContextLock {
+    fn drop(&mut self) {
+        if let Some(multithread) = &self.0 {
+            unsafe { multithread.Leave() };
+        }
+    }
+}
+
+/// Copy one array slice of a D3D11 texture into a new texture on the same
+/// device that can be sampled by the renderer.
+#[cfg(target_os = "windows")]
+fn copy_d3d11_texture(
+    texture: &windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
+    subresource_index: u32,
+) -> anyhow::Result<windows::Win32::Graphics::Direct3D11::ID3D11Texture2D> {
+    use anyhow::Context as _;
+    use windows::Win32::Graphics::{
+        Direct3D11::{
+            D3D11_BIND_SHADER_RESOURCE, D3D11_TEXTURE2D_DESC, D3D11_USAGE_DEFAULT, ID3D11Texture2D,
+        },
+        Dxgi::Common::DXGI_SAMPLE_DESC,
+    };
+
+    unsafe {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        texture.GetDesc(&mut desc);
+        let device = texture.GetDevice()?;
+        let device_context = device.GetImmediateContext()?;
+
+        let copy_desc = D3D11_TEXTURE2D_DESC {
+            MipLevels: 1,
+            ArraySize: 1,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as u32,
+            CPUAccessFlags: 0,
+            MiscFlags: 0,
+            ..desc
+        };
+        let copy: ID3D11Texture2D = {
+            let mut copy = None;
+            device.CreateTexture2D(&copy_desc, None, Some(&mut copy))?;
+            copy.context("failed to create texture")?
+        };
+
+        let _lock = DeviceContextLock::new(&device_context);
+        device_context.CopySubresourceRegion(
+            &copy,
+            0,
+            0,
+            0,
+            0,
+            texture,
+            subresource_index * desc.MipLevels,
+            None,
+        );
+        Ok(copy)
+    }
+}
+
+/// Read back a D3D11 texture through a staging texture into a tightly packed BGRA buffer.
+#[cfg(target_os = "windows")]
+fn d3d11_texture_to_bgra(
+    texture: &windows::Win32::Graphics::Direct3D11::ID3D11Texture2D,
+    subresource_index: u32,
+    width: u32,
+    height: u32,
+    conversion: YuvToRgb,
+) -> anyhow::Result<Vec<u8>> {
+    use anyhow::Context as _;
+    use windows::Win32::Graphics::{
+        Direct3D11::{
+            D3D11_CPU_ACCESS_READ, D3D11_MAP_READ, D3D11_MAPPED_SUBRESOURCE, D3D11_TEXTURE2D_DESC,
+            D3D11_USAGE_STAGING, ID3D11Texture2D,
+        },
+        Dxgi::Common::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_NV12, DXGI_SAMPLE_DESC},
+    };
+
+    unsafe {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        texture.GetDesc(&mut desc);
+        let device = texture.GetDevice()?;
+        let device_context = device.GetImmediateContext()?;
+
+        let staging_desc = D3D11_TEXTURE2D_DESC {
+            Width: desc.Width,
+            Height: desc.Height,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: desc.Format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_STAGING,
+            BindFlags: 0,
+            CPUAccessFlags: D3D11_CPU_ACCESS_READ.0 as u32,
+            MiscFlags: 0,
+        };
+        let staging_texture: ID3D11Texture2D = {
+            let mut staging_texture = None;
+            device.CreateTexture2D(&staging_desc, None, Some(&mut staging_texture))?;
+            staging_texture.context("failed to create staging texture")?
+        };
+
+        let _lock = DeviceContextLock::new(&device_context);
+
+        // `subresource_index` selects an array slice, matching how the renderer
+        // binds these textures, so skip over the mip chain of preceding slices.
+        device_context.CopySubresourceRegion(
+            &staging_texture,
+            0,
+            0,
+            0,
+            0,
+            texture,
+            subresource_index * desc.MipLevels,
+            None,
+        );
+
+        let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+        device_context.Map(&staging_texture, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+
+        let width = width.min(desc.Width);
+        let height = height.min(desc.Height);
+        let row_pitch = mapped.RowPitch as usize;
+        let data = mapped.pData as *const u8;
+        let result = if desc.Format == DXGI_FORMAT_B8G8R8A8_UNORM {
+            let source = std::slice::from_raw_parts(data, row_pitch * desc.Height as usize);
+            let mut bgra = Vec::with_capacity((width * height * 4) as usize);
+            for row in source.chunks_exact(row_pitch).take(height as usize) {
+                bgra.extend_from_slice(&row[..width as usize * 4]);
+            }
+            Ok(bgra)
+        } else if desc.Format == DXGI_FORMAT_NV12 {
+            // Mapped NV12 textures store the chroma plane directly below the luma plane.
+            let y_len = row_pitch * desc.Height as usize;
+            let uv_len = row_pitch * desc.Height.div_ceil(2) as usize;
+            let y_plane = std::slice::from_raw_parts(data, y_len);
+            let uv_plane = std::slice::from_raw_parts(data.add(y_len), uv_len);
+            Ok(
+                Yuv420Planes::nv12(y_plane, row_pitch as u32, uv_plane, row_pitch as u32)
+                    .to_bgra(width, height, conversion),
+            )
+        } else {
+            Err(anyhow::anyhow!(
+                "unsupported D3D11 texture format: {:?}",
+                desc.Format
+            ))
+        };
+
+        device_context.Unmap(&staging_texture, 0);
+        result
+    }
+}
+
+/// Builds a DRM fourcc code from its four-character name.
+#[cfg(target_os = "linux")]
+const fn drm_fourcc(name: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*name)
+}
+
+#[cfg(target_os = "linux")]
+const DRM_FORMAT_ARGB8888: u32 = drm_fourcc(b"AR24");
+#[cfg(target_os = "linux")]
+const DRM_FORMAT_XRGB8888: u32 = drm_fourcc(b"XR24");
+#[cfg(target_os = "linux")]
+const DRM_FORMAT_ABGR8888: u32 = drm_fourcc(b"AB24");
+#[cfg(target_os = "linux")]
+const DRM_FORMAT_XBGR8888: u32 = drm_fourcc(b"XB24");
+#[cfg(target_os = "linux")]
+const DRM_FORMAT_NV12: u32 = drm_fourcc(b"NV12");
+#[cfg(target_os = "linux")]
+const DRM_FORMAT_YUV420: u32 = drm_fourcc(b"YU12");
+#[cfg(target_os = "linux")]
+const DRM_FORMAT_MOD_LINEAR: u64 = 0;
+
+/// `DMA_BUF_IOCTL_SYNC` from `linux/dma-buf.h`, which brackets CPU access to
+/// a DMA-BUF.
+#[cfg(target_os = "linux")]
+const DMA_BUF_IOCTL_SYNC: u64 = 0x4008_6200;
+#[cfg(target_os = "linux")]
+const DMA_BUF_SYNC_READ: u64 = 1;
+#[cfg(target_os = "linux")]
+const DMA_BUF_SYNC_START: u64 = 0;
+#[cfg(target_os = "linux")]
+const DMA_BUF_SYNC_END: u64 = 1 << 2;
+
+/// The pixel format of the DMA-BUF fourccs that can be read back.
+#[cfg(target_os = "linux")]
+fn dma_buf_pixel_format(fourcc: u32) -> Option<PixelFormat> {
+    match fourcc {
+        DRM_FORMAT_ARGB8888 | DRM_FORMAT_XRGB8888 => Some(PixelFormat::Bgra8),
+        DRM_FORMAT_ABGR8888 | DRM_FORMAT_XBGR8888 => Some(PixelFormat::Rgba8),
+        DRM_FORMAT_NV12 => Some(PixelFormat::Nv12),
+        DRM_FORMAT_YUV420 => Some(PixelFormat::I420),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn unsupported_dma_buf_format(fourcc: u32) -> VideoFrameError {
+    VideoFrameError::UnsupportedSourceFormat(format!(
+        "DMA-BUF {}",
+        String::from_utf8_lossy(&fourcc.to_le_bytes())
+    ))
+}
+
+/// Start or end CPU access to a DMA-BUF, so that the kernel waits for the
+/// GPU to finish writing it and keeps CPU caches coherent with it.
+///
+/// Buffers that aren't DMA-BUFs, such as memfds, need no synchronization.
+#[cfg(target_os = "linux")]
+fn sync_dma_buf(fd: std::os::fd::RawFd, flags: u64) -> std::io::Result<()> {
+    #[repr(C)]
+    struct DmaBufSync {
+        flags: u64,
+    }
+
+    let sync = DmaBufSync { flags };
+    loop {
+        if unsafe { libc::ioctl(fd, DMA_BUF_IOCTL_SYNC as _, &sync) } == 0 {
+            return Ok(());
+        }
+        let error = std::io::Error::last_os_error();
+        match error.raw_os_error() {
+            Some(libc::EINTR | libc::EAGAIN) => continue,
+            Some(libc::ENOTTY) => return Ok(()),
+            _ => return Err(error),
+        }
+    }
+}
+
+/// Read back a linear DMA-BUF by mapping it into memory, producing a tightly
+/// packed BGRA buffer. NV12 buffers are expected to store the chroma plane
+/// directly below the luma plane, with the same stride, and YU12 buffers the
+/// U and V planes below it, in that order, each with half its stride.
+#[cfg(target_os = "linux")]
+fn dma_buf_to_bgra(
+    fd: &std::os::fd::OwnedFd,
+    fourcc: u32,
+    modifier: u64,
+    stride: u32,
+    offset: u32,
+    width: u32,
+    height: u32,
+    conversion: YuvToRgb,
+) -> anyhow::Result<Vec<u8>> {
+    use std::os::fd::AsRawFd as _;
+
+    /// Unmaps the buffer when dropped.
+    struct Mapping {
+        address: *mut libc::c_void,
+        len: usize,
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            unsafe { libc::munmap(self.address, self.len) };
+        }
+    }
+
+    /// Ends CPU access to the buffer when dropped.
+    struct CpuAccess(std::os::fd::RawFd);
+
+    impl Drop for CpuAccess {
+        fn drop(&mut self) {
+            sync_dma_buf(self.0, DMA_BUF_SYNC_END | DMA_BUF_SYNC_READ).log_err();
+        }
+    }
+
+    anyhow::ensure!(
+        modifier == DRM_FORMAT_MOD_LINEAR,
+        "unsupported DMA-BUF modifier: {modifier:#x}"
+    );
+    let luma_len = stride as usize * height as usize;
+    let chroma_len = (stride / 2) as usize * height.div_ceil(2) as usize;
+    let pixels_len = match fourcc {
+        DRM_FORMAT_NV12 => luma_len + stride as usize * height.div_ceil(2) as usize,
+        DRM_FORMAT_YUV420 => luma_len + chroma_len * 2,
+        _ => luma_len,
+    };
+    let len = offset as usize + pixels_len;
+
+    let address = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            fd.as_raw_fd(),
+            0,
+        )
+    };
+    if address == libc::MAP_FAILED {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let mapping = Mapping { address, len };
+    sync_dma_buf(fd.as_raw_fd(), DMA_BUF_SYNC_START | DMA_BUF_SYNC_READ)?;
+    let _cpu_access = CpuAccess(fd.as_raw_fd());
+    let bytes = unsafe { std::slice::from_raw_parts(mapping.address as *const u8, mapping.len) };
+    let pixels = &bytes[offset as usize..];
+
+    let mut bgra = match fourcc {
+        DRM_FORMAT_ARGB8888 | DRM_FORMAT_XRGB8888 => {
+            pack_rows(pixels, stride, row_size(width, 4)?, height)
+        }
+        DRM_FORMAT_ABGR8888 | DRM_FORMAT_XBGR8888 => {
+            let mut bgra = pack_rows(pixels, stride, row_size(width, 4)?, height);
+            swap_red_blue(&mut bgra);
+            bgra
+        }
+        DRM_FORMAT_NV12 => {
+            let (y_plane, uv_plane) = pixels.split_at(luma_len);
+            return Ok(Yuv420Planes::nv12(y_plane, stride, uv_plane, stride)
+                .to_bgra(width, height, conversion));
+        }
+        DRM_FORMAT_YUV420 => {
+            let (y_plane, chroma) = pixels.split_at(luma_len);
+            let (u_plane, v_plane) = chroma.split_at(chroma_len);
+            return Ok(Yuv420Planes::i420(
+                y_plane,
+                stride,
+                u_plane,
+                stride / 2,
+                v_plane,
+                stride / 2,
+            )
+            .to_bgra(width, height, conversion));
+        }
+        _ => return Err(unsupported_dma_buf_format(fourcc).into()),
+    };
+    if matches!(fourcc, DRM_FORMAT_XRGB8888 | DRM_FORMAT_XBGR8888) {
+        for pixel in bgra.chunks_exact_mut(4) {
+            pixel[3] = 255;
+        }
+    }
+    Ok(bgra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ScaleFilter, TestAppContext};
+
+    #[test]
+    fn test_nv12_planes() {
+        let frame = VideoFrame::from_nv12(
+            vec![16; 8 * 2],
+            vec![128; 8],
+            8,
+            8,
+            6,
+            2,
+            VideoColorSpace::Bt601,
+        );
+
+        assert_eq!(frame.size(), (6, 2));
+        assert!(frame.as_bytes().is_none());
+        let (y_plane, uv_plane, y_stride, uv_stride) = frame.nv12_planes().unwrap();
+        assert_eq!(
+            (y_plane.len(), uv_plane.len(), y_stride, uv_stride),
+            (16, 8, 8, 8)
+        );
+    }
+
+    #[test]
+    fn test_nv12_to_bgra_neutral_chroma_is_gray() {
+        let bgra = Yuv420Planes::nv12(&[100; 4], 2, &[128; 2], 2).to_bgra(
+            2,
+            2,
+            YuvToRgb::new(VideoColorSpace::Bt601, false),
+        );
+
+        assert_eq!(bgra, [100, 100, 100, 255].repeat(4));
+    }
+
+    #[test]
+    fn test_to_bgra() {
+        let bgra = VideoFrame::from_bgra(vec![1, 2, 3, 4], 1, 1);
+        assert_eq!(bgra.to_bgra().as_bytes(), Some([1, 2, 3, 4].as_slice()));
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![50; 4],
+            vec![128; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        let converted = nv12.to_bgra();
+        assert_eq!(converted.size(), (2, 2));
+        assert_eq!(
+            converted.as_bytes(),
+            Some([50, 50, 50, 255].repeat(4).as_slice())
+        );
+    }
+
+    #[test]
+    fn test_bgra_with_stride() {
+        let mut buffer = Vec::new();
+        for row in 0..2u8 {
+            buffer.extend_from_slice(&[row; 8]);
+            buffer.extend_from_slice(&[0xff; 8]);
+        }
+        let frame = VideoFrame::from_bgra_with_stride(buffer, 2, 2, 16);
+
+        assert_eq!(frame.bytes_per_row(), 16);
+        assert_eq!(frame.as_bytes().map(<[u8]>::len), Some(32));
+
+        let packed = frame.to_bgra();
+        assert_eq!(packed.bytes_per_row(), 8);
+        assert_eq!(
+            packed.as_bytes(),
+            Some([[0; 8], [1; 8]].concat().as_slice())
+        );
+    }
+
+    #[test]
+    fn test_try_from_bgra_validation() {
+        assert_eq!(
+            VideoFrame::try_from_bgra(vec![0; 15], 2, 2).unwrap_err(),
+            VideoFrameError::BufferTooSmall {
+                expected: 16,
+                actual: 15
+            }
+        );
+        assert_eq!(
+            VideoFrame::try_from_bgra(Vec::new(), 0, 2).unwrap_err(),
+            VideoFrameError::ZeroDimension
+        );
+        assert_eq!(
+            VideoFrame::try_from_bgra_with_stride(vec![0; 8], 2, 2, 4).unwrap_err(),
+            VideoFrameError::InvalidStride {
+                stride: 4,
+                row_size: 8
+            }
+        );
+        assert_eq!(
+            VideoFrame::try_from_nv12(vec![0; 4], vec![0; 1], 2, 2, 2, 2, VideoColorSpace::Bt601)
+                .unwrap_err(),
+            VideoFrameError::BufferTooSmall {
+                expected: 2,
+                actual: 1
+            }
+        );
+        assert!(VideoFrame::try_from_bgra_arc(Arc::new(vec![0; 16]), 2, 2).is_ok());
+    }
+
+    #[test]
+    fn test_rgba() {
+        let frame = VideoFrame::from_rgba(vec![1, 2, 3, 4, 5, 6, 7, 8], 2, 1);
+
+        assert_eq!(frame.pixel_format(), PixelFormat::Rgba8);
+        assert_eq!(frame.as_bytes(), Some([1, 2, 3, 4, 5, 6, 7, 8].as_slice()));
+
+        let bgra = frame.to_bgra();
+        assert_eq!(bgra.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(bgra.as_bytes(), Some([3, 2, 1, 4, 7, 6, 5, 8].as_slice()));
+
+        assert!(VideoFrame::try_from_rgba(vec![0; 7], 2, 1).is_err());
+    }
+
+    #[test]
+    fn test_from_raw() {
+        let bgra = VideoFrame::from_raw(vec![0; 16], 2, 2, PixelFormat::Bgra8).unwrap();
+        assert_eq!(bgra.format(), PixelFormat::Bgra8);
+
+        let rgb = VideoFrame::from_raw(vec![1, 2, 3, 4, 5, 6], 2, 1, PixelFormat::Rgb8).unwrap();
+        assert_eq!(
+            rgb.as_bytes(),
+            Some([3, 2, 1, 255, 6, 5, 4, 255].as_slice())
+        );
+
+        // A 3x3 frame has 2x2 chroma planes.
+        let mut i420 = vec![10; 9];
+        i420.extend_from_slice(&[1, 2, 3, 4]);
+        i420.extend_from_slice(&[5, 6, 7, 8]);
+        let frame = VideoFrame::from_raw(i420, 3, 3, PixelFormat::I420).unwrap();
+        assert_eq!(frame.format(), PixelFormat::I420);
+        let (y, u, v, y_stride, u_stride, v_stride) = frame.i420_planes().unwrap();
+        assert_eq!(y, [10; 9]);
+        assert_eq!(u, [1, 2, 3, 4]);
+        assert_eq!(v, [5, 6, 7, 8]);
+        assert_eq!((y_stride, u_stride, v_stride), (3, 2, 2));
+
+        assert_eq!(
+            VideoFrame::from_raw(vec![0; 16], 3, 3, PixelFormat::Nv12).unwrap_err(),
+            VideoFrameError::BufferTooSmall {
+                expected: 17,
+                actual: 16
+            }
+        );
+    }
+
+    #[test]
+    fn test_i420_odd_dimensions() {
+        // A 3x3 frame has 2x2 chroma planes.
+        let frame = VideoFrame::from_i420(
+            vec![80; 9],
+            vec![128; 4],
+            vec![128; 4],
+            3,
+            2,
+            2,
+            3,
+            3,
+            VideoColorSpace::Bt601,
+        );
+
+        assert!(frame.as_bytes().is_none());
+        assert_eq!(frame.pixel_format(), PixelFormat::I420);
+        assert_eq!(
+            frame.to_bgra().as_bytes(),
+            Some([80, 80, 80, 255].repeat(9).as_slice())
+        );
+
+        assert_eq!(
+            VideoFrame::try_from_i420(
+                vec![0; 9],
+                vec![0; 4],
+                vec![0; 2],
+                3,
+                2,
+                2,
+                3,
+                3,
+                VideoColorSpace::Bt601
+            )
+            .unwrap_err(),
+            VideoFrameError::BufferTooSmall {
+                expected: 4,
+                actual: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_equality_and_content_hash() {
+        let frame = VideoFrame::from_bgra(vec![1, 2, 3, 4, 5, 6, 7, 8], 1, 2);
+        let padded = VideoFrame::from_bgra_with_stride(
+            vec![1, 2, 3, 4, 99, 99, 99, 99, 5, 6, 7, 8, 42, 42, 42, 42],
+            1,
+            2,
+            8,
+        );
+        assert_eq!(frame, padded);
+        assert_eq!(frame.content_hash(), padded.content_hash());
+
+        let different = VideoFrame::from_bgra(vec![1, 2, 3, 4, 5, 6, 7, 9], 1, 2);
+        assert_ne!(frame, different);
+        assert_ne!(frame.content_hash(), different.content_hash());
+
+        let reshaped = VideoFrame::from_bgra(vec![1, 2, 3, 4, 5, 6, 7, 8], 2, 1);
+        assert_ne!(frame, reshaped);
+
+        let rgba = VideoFrame::from_rgba(vec![1, 2, 3, 4, 5, 6, 7, 8], 1, 2);
+        assert_ne!(frame, rgba);
+        assert_ne!(frame.content_hash(), rgba.content_hash());
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![16; 4],
+            vec![128; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        let padded_nv12 = VideoFrame::from_nv12(
+            vec![16; 8],
+            vec![128; 4],
+            4,
+            4,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(nv12, padded_nv12);
+        assert_eq!(nv12.content_hash(), padded_nv12.content_hash());
+    }
+
+    #[test]
+    fn test_image_interop() {
+        let frame = VideoFrame::from_bgra(vec![1, 2, 3, 255, 4, 5, 6, 128], 2, 1);
+        let image = frame.to_image().unwrap();
+        assert_eq!((image.width(), image.height()), (2, 1));
+        assert_eq!(image.as_raw(), &vec![3, 2, 1, 255, 6, 5, 4, 128]);
+
+        let round_tripped = VideoFrame::from_image(&image).unwrap();
+        assert_eq!(round_tripped.pixel_format(), PixelFormat::Rgba8);
+        assert_eq!(round_tripped.to_bgra(), frame);
+        assert_eq!(
+            VideoFrame::from_image(&image::RgbaImage::new(0, 1)).unwrap_err(),
+            VideoFrameError::ZeroDimension
+        );
+
+        let rgba = VideoFrame::from_rgba(vec![9, 8, 7, 6], 1, 1);
+        assert_eq!(rgba.to_image().unwrap().as_raw(), &vec![9, 8, 7, 6]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_dma_buf_readback() {
+        use std::{
+            io::Write as _,
+            os::fd::{FromRawFd as _, OwnedFd},
+        };
+
+        let raw_fd = unsafe { libc::memfd_create(c"video-frame-test".as_ptr(), 0) };
+        assert!(raw_fd >= 0);
+        let mut file = std::fs::File::from(unsafe { OwnedFd::from_raw_fd(raw_fd) });
+        // A 4 byte header, then two XRGB8888 rows of one pixel padded to 8 bytes.
+        file.write_all(&[0, 0, 0, 0, 1, 2, 3, 0, 0, 0, 0, 0, 4, 5, 6, 0, 0, 0, 0, 0])
+            .unwrap();
+
+        let frame = VideoFrame::from_dma_buf(
+            file.into(),
+            1,
+            2,
+            DRM_FORMAT_XRGB8888,
+            DRM_FORMAT_MOD_LINEAR,
+            8,
+            4,
+        )
+        .unwrap();
+        assert_eq!(
+            format!("{frame:?}"),
+            "VideoFrame { width: 1, height: 2, format: Bgra8, color_space: Srgb, \
+             bytes_per_row: 4, footprint: 8, data: \"DmaBuf\" }"
+        );
+        assert_eq!(frame.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(frame.backend(), VideoFrameBackend::DmaBuf);
+        assert!(frame.is_hardware_backed());
+        assert_eq!(frame.as_bytes(), None);
+        assert_eq!(frame, frame.clone());
+        assert_eq!(
+            frame.to_bgra().as_bytes(),
+            Some([1, 2, 3, 255, 4, 5, 6, 255].as_slice())
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_dma_buf_formats() {
+        use std::{
+            io::Write as _,
+            os::fd::{FromRawFd as _, OwnedFd},
+        };
+
+        let memfd = |bytes: &[u8]| -> OwnedFd {
+            let raw_fd = unsafe { libc::memfd_create(c"video-frame-test".as_ptr(), 0) };
+            assert!(raw_fd >= 0);
+            let mut file = std::fs::File::from(unsafe { OwnedFd::from_raw_fd(raw_fd) });
+            file.write_all(bytes).unwrap();
+            file.into()
+        };
+
+        // Two rows of two luma samples padded to 4 bytes, then U and V planes
+        // with half the stride.
+        let frame = VideoFrame::from_dma_buf(
+            memfd(&[16, 235, 0, 0, 81, 145, 0, 0, 90, 0, 240, 0]),
+            2,
+            2,
+            DRM_FORMAT_YUV420,
+            DRM_FORMAT_MOD_LINEAR,
+            4,
+            0,
+        )
+        .unwrap();
+        assert_eq!(frame.pixel_format(), PixelFormat::I420);
+        let expected = VideoFrame::from_i420(
+            vec![16, 235, 81, 145],
+            vec![90],
+            vec![240],
+            2,
+            1,
+            1,
+            2,
+            2,
+            frame.color_space,
+        );
+        assert_eq!(frame.to_bgra().as_bytes(), expected.to_bgra().as_bytes());
+
+        let unsupported = drm_fourcc(b"YUYV");
+        let error = VideoFrame::from_dma_buf(
+            memfd(&[0; 8]),
+            2,
+            1,
+            unsupported,
+            DRM_FORMAT_MOD_LINEAR,
+            8,
+            0,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            VideoFrameError::UnsupportedSourceFormat("DMA-BUF YUYV".into())
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_core_video_frame_across_threads() {
+        use core_foundation::base::TCFType as _;
+        use core_video::pixel_buffer::{CVPixelBuffer, kCVPixelFormatType_32BGRA};
+
+        let buffer = CVPixelBuffer::new(kCVPixelFormatType_32BGRA, 4, 2, None).unwrap();
+        let retain_count = buffer.retain_count();
+        let frame = VideoFrame::from_cv_pixel_buffer(buffer.clone()).unwrap();
+        assert_eq!(buffer.retain_count(), retain_count + 1);
+
+        let clone = frame.clone();
+        let readback_thread = std::thread::spawn(move || {
+            let bgra = clone.to_bgra();
+            drop(clone);
+            bgra
+        });
+        let bgra = readback_thread.join().unwrap();
+        assert_eq!(bgra.size(), (4, 2));
+        assert_eq!(bgra.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(buffer.retain_count(), retain_count + 1);
+
+        drop(frame);
+        assert_eq!(buffer.retain_count(), retain_count);
+    }
+
+    #[test]
+    fn test_color_space_selects_yuv_matrix() {
+        let bgra = VideoFrame::from_bgra(vec![0; 4], 1, 1);
+        assert_eq!(bgra.color_space(), VideoColorSpace::Srgb);
+
+        // A saturated red-ish sample: Cr well above neutral.
+        let frame = |color_space| {
+            VideoFrame::from_nv12(vec![100; 4], vec![128, 200], 2, 2, 2, 2, color_space)
+        };
+        let bt601 = frame(VideoColorSpace::Bt601).to_bgra();
+        let bt709 = frame(VideoColorSpace::Bt709).to_bgra();
+        assert_eq!(bt601.color_space(), VideoColorSpace::Srgb);
+
+        // R = Y + 2(1 - Kr) * Cr, G = Y - 2Kr(1 - Kr) / Kg * Cr
+        assert_eq!(&bt601.as_bytes().unwrap()[..4], &[100, 49, 201, 255]);
+        assert_eq!(&bt709.as_bytes().unwrap()[..4], &[100, 66, 213, 255]);
+
+        let from_raw = VideoFrame::from_raw(vec![0; 6], 2, 2, PixelFormat::Nv12).unwrap();
+        assert_eq!(from_raw.color_space(), VideoColorSpace::Bt601);
+        assert_eq!(
+            from_raw
+                .with_color_space(VideoColorSpace::Bt2020)
+                .color_space(),
+            VideoColorSpace::Bt2020
+        );
+    }
+
+    #[test]
+    fn test_p010() {
+        // Neutral chroma is 512 in 10 bits, stored as 512 << 6.
+        let frame = VideoFrame::from_p010(
+            vec![940 << 6; 4],
+            vec![512 << 6; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt2020,
+        );
+        assert_eq!(frame.pixel_format(), PixelFormat::P010);
+        assert_eq!(frame.bit_depth(), 10);
+        assert_eq!(frame.color_space(), VideoColorSpace::Bt2020);
+        assert_eq!(frame.as_bytes(), None);
+        assert_eq!(
+            frame.to_bgra().as_bytes(),
+            Some([235, 235, 235, 255].repeat(4).as_slice())
+        );
+        assert_eq!(VideoFrame::from_bgra(vec![0; 4], 1, 1).bit_depth(), 8);
+
+        let raw = [[0x00, 0xeb].repeat(4), [0x00, 0x80].repeat(2)].concat();
+        let from_raw = VideoFrame::from_raw(raw, 2, 2, PixelFormat::P010).unwrap();
+        assert_eq!(from_raw, frame);
+
+        assert_eq!(
+            VideoFrame::try_from_p010(vec![0; 4], vec![0; 1], 2, 2, 2, 2, VideoColorSpace::Bt2020)
+                .unwrap_err(),
+            VideoFrameError::BufferTooSmall {
+                expected: 2,
+                actual: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_as_bytes_mut() {
+        let mut frame = VideoFrame::from_bgra(vec![10, 20, 30, 255], 1, 1);
+        if let Some(bytes) = frame.as_bytes_mut() {
+            bytes[0] = 40;
+        }
+        assert_eq!(frame.as_bytes(), Some(&[40, 20, 30, 255][..]));
+
+        let shared = frame.clone();
+        assert!(frame.as_bytes_mut().is_none());
+        drop(shared);
+        assert!(frame.as_bytes_mut().is_some());
+
+        let mut nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert!(nv12.as_bytes_mut().is_none());
+    }
+
+    #[test]
+    fn test_send_frame_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<VideoFrame>();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let capture_thread = std::thread::spawn(move || {
+            sender
+                .send(VideoFrame::from_bgra(vec![1, 2, 3, 255], 1, 1))
+                .unwrap();
+        });
+        let frame: VideoFrame = receiver.recv().unwrap();
+        capture_thread.join().unwrap();
+        assert_eq!(frame.as_bytes(), Some(&[1, 2, 3, 255][..]));
+    }
+
+    #[gpui::test]
+    async fn test_async_conversion(cx: &mut TestAppContext) {
+        let executor = cx.executor();
+        let nv12 = VideoFrame::from_nv12(
+            vec![128; 4],
+            vec![128; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        let task = nv12.to_bgra_async(&executor);
+        let bgra = task.await;
+        assert_eq!(bgra, nv12.to_bgra());
+        assert_eq!(bgra.pixel_format(), PixelFormat::Bgra8);
+
+        let task = bgra.scale_async(1, 1, ScaleFilter::Nearest, &executor);
+        assert_eq!(task.await.unwrap().size(), (1, 1));
+
+        let task = nv12.scale_async(1, 1, ScaleFilter::Nearest, &executor);
+        assert_eq!(
+            task.await.unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::Nv12)
+        );
+    }
+
+    #[test]
+    fn test_diff_bounds() {
+        let previous = VideoFrame::solid_color(4, 3, [0, 0, 0, 255]);
+        assert_eq!(previous.diff_bounds(&previous.clone()), None);
+        assert_eq!(
+            VideoFrame::solid_color(4, 3, [0, 0, 0, 255]).diff_bounds(&previous),
+            None
+        );
+
+        let pixel_offset = |column: usize, row: usize| (row * 4 + column) * 4;
+        let mut buffer = previous.as_bytes().unwrap().to_vec();
+        buffer[pixel_offset(2, 1)] = 255;
+        let single = VideoFrame::from_bgra(buffer.clone(), 4, 3);
+        assert_eq!(single.diff_bounds(&previous), Some((2, 1, 1, 1)));
+
+        buffer[pixel_offset(0, 2) + 1] = 255;
+        let two = VideoFrame::from_bgra(buffer, 4, 3);
+        assert_eq!(two.diff_bounds(&previous), Some((0, 1, 3, 2)));
+
+        let resized = VideoFrame::solid_color(2, 2, [0, 0, 0, 255]);
+        assert_eq!(resized.diff_bounds(&previous), Some((0, 0, 2, 2)));
+    }
+
+    #[test]
+    fn test_rgb8() {
+        let frame = VideoFrame::from_rgb8(vec![1, 2, 3, 4, 5, 6], 2, 1);
+        assert_eq!(frame.pixel_format(), PixelFormat::Rgb8);
+        assert_eq!(frame.backend(), VideoFrameBackend::CpuRgb);
+        assert!(!frame.is_hardware_backed());
+        assert_eq!(frame.alpha_mode(), AlphaMode::Opaque);
+        assert_eq!(frame.bytes_per_row(), 6);
+        assert_eq!(frame.as_bytes(), Some(&[1, 2, 3, 4, 5, 6][..]));
+        assert_eq!(
+            frame.to_bgra().as_bytes(),
+            Some(&[3, 2, 1, 255, 6, 5, 4, 255][..])
+        );
+        assert_eq!(
+            frame.crop(0, 0, 1, 1).unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::Rgb8)
+        );
+
+        assert_eq!(
+            VideoFrame::try_from_rgb8(vec![0; 5], 2, 1).unwrap_err(),
+            VideoFrameError::BufferTooSmall {
+                expected: 6,
+                actual: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        let padded = VideoFrame::from_bgra_with_stride(vec![0; 24], 2, 2, 12);
+        assert_eq!(padded.memory_footprint(), 24);
+
+        let i420 = VideoFrame::from_i420(
+            vec![0; 9],
+            vec![0; 4],
+            vec![0; 4],
+            3,
+            2,
+            2,
+            3,
+            3,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(i420.memory_footprint(), 17);
+
+        let p010 =
+            VideoFrame::from_p010(vec![0; 4], vec![0; 2], 2, 2, 2, 2, VideoColorSpace::Bt2020);
+        assert_eq!(p010.memory_footprint(), 12);
+    }
+
+    #[test]
+    fn test_to_luma8() {
+        let bgra = VideoFrame::from_bgra_with_stride(
+            vec![0, 0, 255, 255, 0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0],
+            1,
+            2,
+            8,
+        );
+        assert_eq!(bgra.to_luma8().unwrap(), [54, 255]);
+
+        let rgba = VideoFrame::from_rgba(vec![0, 255, 0, 255], 1, 1);
+        assert_eq!(rgba.to_luma8().unwrap(), [182]);
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![10, 20, 0, 30, 40, 0],
+            vec![128; 2],
+            3,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt709,
+        );
+        assert_eq!(nv12.to_luma8().unwrap(), [10, 20, 30, 40]);
+
+        let p010 = VideoFrame::from_p010(
+            vec![0x1000, 0x2000, 0x3000, 0x4000],
+            vec![0x8000; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt2020,
+        );
+        assert_eq!(p010.to_luma8().unwrap(), [0x10, 0x20, 0x30, 0x40]);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_png_round_trip() {
+        let frame = VideoFrame::from_bgra_with_stride(
+            vec![
+                1, 2, 3, 255, 4, 5, 6, 128, 0, 0, 0, 0, 7, 8, 9, 0, 10, 11, 12, 64, 0, 0, 0, 0,
+            ],
+            2,
+            2,
+            12,
+        );
+        let mut encoded = Vec::new();
+        frame.write_png(&mut encoded).unwrap();
+
+        let decoder = png::Decoder::new(std::io::Cursor::new(encoded));
+        let mut reader = decoder.read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (2, 2));
+        let mut decoded = vec![0; 16];
+        reader.next_frame(&mut decoded).unwrap();
+        assert_eq!(
+            decoded,
+            [3, 2, 1, 255, 6, 5, 4, 128, 9, 8, 7, 0, 12, 11, 10, 64]
+        );
+    }
+
+    #[test]
+    fn test_nv12_to_bgra() {
+        let frame = VideoFrame::from_nv12(
+            vec![100; 4],
+            vec![150, 100],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt709,
+        );
+        let converted = frame.nv12_to_bgra().unwrap();
+        assert_eq!(converted.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(converted.bytes_per_row(), 8);
+        assert_eq!(converted.as_bytes().unwrap(), [141, 109, 56, 255].repeat(4));
+
+        let gradient = VideoFrame::from_nv12(
+            vec![128; 8],
+            vec![128, 128, 128, 192],
+            4,
+            4,
+            4,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        let converted = gradient.nv12_to_bgra().unwrap();
+        let red = |column: usize| converted.as_bytes().unwrap()[column * 4 + 2];
+        assert_eq!([red(0), red(1), red(2), red(3)], [128, 150, 195, 218]);
+
+        assert_eq!(
+            VideoFrame::from_bgra(vec![0; 4], 1, 1)
+                .nv12_to_bgra()
+                .unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::Bgra8)
+        );
+    }
+
+    #[test]
+    fn test_swap_rb() {
+        let frame = VideoFrame::from_bgra_with_stride(
+            [[1, 2, 3, 4], [5, 6, 7, 8], [0, 0, 0, 0]]
+                .concat()
+                .repeat(2),
+            2,
+            2,
+            12,
+        );
+        let swapped = frame.swap_rb();
+        assert_eq!(swapped.pixel_format(), PixelFormat::Rgba8);
+        assert_eq!(
+            swapped.as_bytes().unwrap(),
+            [3, 2, 1, 4, 7, 6, 5, 8].repeat(2)
+        );
+        assert_eq!(swapped.swap_rb().pixel_format(), PixelFormat::Bgra8);
+
+        let mut state = 0x2545_f491_u32;
+        let pixels = (0..4099 * 4)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect::<Vec<u8>>();
+        let mut scalar = pixels.clone();
+        swap_red_blue_scalar(&mut scalar);
+        #[cfg(target_arch = "x86_64")]
+        if std::arch::is_x86_feature_detected!("ssse3") {
+            let mut ssse3 = pixels.clone();
+            unsafe { swap_red_blue_ssse3(&mut ssse3) };
+            assert_eq!(ssse3, scalar);
+        }
+        let mut simd = pixels;
+        swap_red_blue(&mut simd);
+        assert_eq!(simd, scalar);
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        let frame = VideoFrame::from_bgra(vec![0; 4 * 4 * 2], 4, 2);
+        assert_eq!(frame.aspect_ratio(), 2.0);
+        assert_eq!(frame.size_in_pixels(), size(px(4.0), px(2.0)));
+
+        let mut empty = frame;
+        empty.height = 0;
+        assert_eq!(empty.aspect_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_from_bgra_with_drop() {
+        let released = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let frame = VideoFrame::from_bgra_with_drop(vec![0; 4], 1, 1, {
+            let released = released.clone();
+            move || {
+                released.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        });
+        let clone = frame.clone().with_pts(Duration::from_millis(40));
+        let converted = frame.swap_rb();
+        drop(frame);
+        assert_eq!(released.load(std::sync::atomic::Ordering::SeqCst), 0);
+        std::thread::spawn(move || drop(clone)).join().unwrap();
+        assert_eq!(released.load(std::sync::atomic::Ordering::SeqCst), 1);
+        drop(converted);
+        assert_eq!(released.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let result = VideoFrame::try_from_bgra_with_drop(vec![0; 3], 1, 1, {
+            let released = released.clone();
+            move || {
+                released.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(released.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_rows() {
+        let frame = VideoFrame::from_bgra_with_stride(
+            [&[1, 2, 3, 4, 5, 6, 7, 8][..], &[0xff; 4]]
+                .concat()
+                .repeat(2),
+            2,
+            2,
+            12,
+        );
+        let rows: Vec<_> = frame.rows().unwrap().collect();
+        assert_eq!(rows, [[1, 2, 3, 4, 5, 6, 7, 8]; 2]);
+
+        let rgb = VideoFrame::from_rgb8((0..6).collect(), 1, 2);
+        let rows: Vec<_> = rgb.rows().unwrap().collect();
+        assert_eq!(rows, [[0, 1, 2], [3, 4, 5]]);
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert!(nv12.rows().is_none());
+    }
+
+    #[test]
+    fn test_dominant_solid_color() {
+        let frame = VideoFrame::from_bgra_with_stride(
+            [&[1, 2, 3, 4, 1, 2, 3, 4][..], &[0xff; 4]]
+                .concat()
+                .repeat(2),
+            2,
+            2,
+            12,
+        );
+        assert_eq!(frame.dominant_solid_color(), Some([1, 2, 3, 4]));
+
+        let mut pixels = [1, 2, 3, 4].repeat(4);
+        pixels[13] = 0;
+        assert_eq!(
+            VideoFrame::from_bgra(pixels, 2, 2).dominant_solid_color(),
+            None
+        );
+
+        let rgba = VideoFrame::from_rgba([1, 2, 3, 4].repeat(4), 2, 2);
+        assert_eq!(rgba.dominant_solid_color(), Some([3, 2, 1, 4]));
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![128; 4],
+            vec![128; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(nv12.dominant_solid_color(), Some([128, 128, 128, 255]));
+        assert_eq!(
+            nv12.dominant_solid_color().map(|color| color.to_vec()),
+            nv12.to_bgra().as_bytes().map(|pixels| pixels[..4].to_vec())
+        );
+        let nv12 = VideoFrame::from_nv12(
+            vec![128, 128, 128, 0],
+            vec![128; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(nv12.dominant_solid_color(), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let frame = VideoFrame::from_bgra(vec![0; 1920 * 1080 * 4], 1920, 1080);
+        assert_eq!(frame.to_string(), "VideoFrame 1920x1080 Bgra8 Srgb 8.3MB");
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt709);
+        assert_eq!(nv12.to_string(), "VideoFrame 2x2 Nv12 Bt709 6B");
+        assert_eq!(
+            format!("{nv12:?}"),
+            "VideoFrame { width: 2, height: 2, format: Nv12, color_space: Bt709, \
+             bytes_per_row: 8, footprint: 6, data: \"CpuNv12\" }"
+        );
+    }
+
+    #[test]
+    fn test_into_bgra_vec() {
+        let frame = VideoFrame::from_bgra(vec![1, 2, 3, 4], 1, 1);
+        let address = frame.as_bytes().unwrap().as_ptr();
+        let buffer = frame.into_bgra_vec().unwrap();
+        assert_eq!(buffer, [1, 2, 3, 4]);
+        assert_eq!(buffer.as_ptr(), address);
+
+        let frame = VideoFrame::from_bgra(vec![1, 2, 3, 4], 1, 1);
+        let clone = frame.clone();
+        assert_eq!(frame.into_bgra_vec().unwrap(), [1, 2, 3, 4]);
+        assert_eq!(clone.as_bytes(), Some(&[1, 2, 3, 4][..]));
+
+        let padded = VideoFrame::from_bgra_with_stride([1, 2, 3, 4, 0, 0, 0, 0].repeat(2), 1, 2, 8);
+        assert_eq!(padded.into_bgra_vec().unwrap(), [1, 2, 3, 4].repeat(2));
+        let rgba = VideoFrame::from_rgba(vec![1, 2, 3, 4], 1, 1);
+        assert_eq!(rgba.into_bgra_vec().unwrap(), [3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn test_copy_gpu_rejects_cpu_frames() {
+        let frame = VideoFrame::from_bgra(vec![0; 4], 1, 1);
+        assert_eq!(
+            frame.copy_gpu().unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::Bgra8)
+        );
+    }
+
+    #[test]
+    fn test_color_range() {
+        let frame = |luma| {
+            VideoFrame::from_nv12(
+                vec![luma; 4],
+                vec![128; 2],
+                2,
+                2,
+                2,
+                2,
+                VideoColorSpace::Bt601,
+            )
+        };
+        assert_eq!(frame(16).color_range(), ColorRange::Full);
+        assert_eq!(
+            VideoFrame::from_bgra(vec![0; 4], 1, 1).color_range(),
+            ColorRange::Full
+        );
+
+        let black = frame(16).with_color_range(ColorRange::Limited);
+        assert_eq!(black.color_range(), ColorRange::Limited);
+        assert_eq!(
+            black.to_bgra().as_bytes(),
+            Some([0, 0, 0, 255].repeat(4).as_slice())
+        );
+        assert_eq!(black.to_bgra().color_range(), ColorRange::Full);
+
+        let white = frame(235).with_color_range(ColorRange::Limited);
+        assert_eq!(white.to_bgra().as_bytes(), Some([255; 16].as_slice()));
+        assert_eq!(white.nv12_to_bgra().unwrap(), white.to_bgra());
+
+        assert_eq!(
+            frame(16).to_bgra().as_bytes(),
+            Some([16, 16, 16, 255].repeat(4).as_slice())
+        );
+    }
+
+    #[test]
+    fn test_buffer_ref_count() {
+        let frame = VideoFrame::from_bgra(vec![0; 4], 1, 1);
+        assert_eq!(frame.buffer_ref_count(), Some(1));
+        assert!(frame.is_buffer_unique());
+
+        let clone = frame.clone();
+        assert_eq!(frame.buffer_ref_count(), Some(2));
+        assert!(!frame.is_buffer_unique());
+        drop(clone);
+        assert!(frame.is_buffer_unique());
+
+        let pool = VideoFramePool::new();
+        let buffer = pool.acquire(1, 1, PixelFormat::Bgra8).unwrap();
+        let pooled = pool.build_frame(buffer, 1, 1).unwrap();
+        assert_eq!(pooled.buffer_ref_count(), Some(2));
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        let converted = nv12.to_bgra();
+        assert!(nv12.is_buffer_unique());
+        assert!(converted.is_buffer_unique());
+    }
+
+    #[test]
+    fn test_detach() {
+        let frame =
+            VideoFrame::from_bgra(vec![1, 2, 3, 4], 1, 1).with_pts(Duration::from_millis(40));
+        let detached = frame.detach();
+        assert_eq!(detached, frame);
+        assert_eq!(frame.buffer_ref_count(), Some(2));
+
+        drop(detached);
+        let pool = VideoFramePool::new();
+        let buffer = pool.acquire(2, 1, PixelFormat::Rgba8).unwrap();
+        let pooled = pool
+            .build_frame(buffer, 2, 1)
+            .unwrap()
+            .with_pts(Duration::from_millis(40));
+        let detached = pooled.detach();
+        assert_eq!(detached, pooled);
+        assert_eq!(detached.pts(), Some(Duration::from_millis(40)));
+        assert!(detached.is_buffer_unique());
+        assert_eq!(pooled.buffer_ref_count(), Some(2));
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt709);
+        let shared = nv12.clone();
+        let detached = nv12.detach();
+        assert_eq!(detached, nv12);
+        assert_eq!(detached.color_space(), VideoColorSpace::Bt709);
+        assert!(detached.is_buffer_unique());
+        drop(shared);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let frame = VideoFrame::from_rgba(vec![1, 2, 3, 4], 1, 1);
+        assert_eq!(frame.metadata(), None);
+
+        let metadata = HashMap::from_iter([("camera".to_string(), b"front".to_vec())]);
+        let frame = frame.with_metadata(metadata.clone());
+        assert_eq!(frame.metadata(), Some(&metadata));
+        assert_eq!(frame.to_bgra().metadata(), Some(&metadata));
+        assert_eq!(frame.detach().metadata(), Some(&metadata));
+    }
+
+    #[test]
+    fn test_luma_histogram() {
+        let gray = VideoFrame::from_bgra_with_stride(
+            [[128, 128, 128, 255].repeat(2), vec![0; 4]]
+                .concat()
+                .repeat(2),
+            2,
+            2,
+            12,
+        );
+        let histogram = gray.luma_histogram(16).unwrap();
+        assert_eq!(histogram.iter().filter(|count| **count > 0).count(), 1);
+        assert_eq!(histogram[8], 4);
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![0, 64, 128, 255],
+            vec![0, 255],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(nv12.luma_histogram(4).unwrap(), [1, 1, 1, 1]);
+
+        let p010 = VideoFrame::from_p010(
+            vec![0xffc0; 4],
+            vec![0x8000; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt2020,
+        );
+        assert_eq!(p010.luma_histogram(2).unwrap(), [0, 4]);
+
+        assert!(gray.luma_histogram(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut frame = VideoFrame::from_bgra_with_stride(vec![0; 24], 2, 2, 12);
+        assert_eq!(frame.validate(), Ok(()));
+        frame.height = 3;
+        assert_eq!(
+            frame.validate(),
+            Err(VideoFrameError::BufferTooSmall {
+                expected: 36,
+                actual: 24
+            })
+        );
+        frame.height = 2;
+        frame.width = 4;
+        assert_eq!(
+            frame.validate(),
+            Err(VideoFrameError::InvalidStride {
+                stride: 12,
+                row_size: 16
+            })
+        );
+        frame.width = 0;
+        assert_eq!(frame.validate(), Err(VideoFrameError::ZeroDimension));
+
+        let mut rgb = VideoFrame::from_rgb8(vec![0; 12], 2, 2);
+        assert_eq!(rgb.validate(), Ok(()));
+        rgb.width = 3;
+        assert_eq!(
+            rgb.validate(),
+            Err(VideoFrameError::BufferTooSmall {
+                expected: 18,
+                actual: 12
+            })
+        );
+
+        let mut nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![0; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(nv12.validate(), Ok(()));
+        nv12.height = 3;
+        assert_eq!(
+            nv12.validate(),
+            Err(VideoFrameError::BufferTooSmall {
+                expected: 6,
+                actual: 4
+            })
+        );
+
+        let mut i420 = VideoFrame::from_i420(
+            vec![0; 8],
+            vec![0; 2],
+            vec![0; 2],
+            4,
+            2,
+            2,
+            4,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(i420.validate(), Ok(()));
+        i420.width = 5;
+        assert_eq!(
+            i420.validate(),
+            Err(VideoFrameError::InvalidStride {
+                stride: 4,
+                row_size: 5
+            })
+        );
+
+        let mut p010 =
+            VideoFrame::from_p010(vec![0; 4], vec![0; 2], 2, 2, 2, 2, VideoColorSpace::Bt2020);
+        assert_eq!(p010.validate(), Ok(()));
+        p010.height = 4;
+        assert_eq!(
+            p010.validate(),
+            Err(VideoFrameError::BufferTooSmall {
+                expected: 8,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_display_rotation() {
+        let frame = VideoFrame::from_bgra(vec![0; 2 * 4], 2, 1);
+        assert_eq!(frame.display_rotation(), Rotation::None);
+
+        let rotated = frame.with_display_rotation(Rotation::Cw90);
+        assert_eq!(rotated.display_rotation(), Rotation::Cw90);
+        assert!(rotated.display_rotation().swaps_dimensions());
+        assert_eq!(rotated.size(), (2, 1));
+        assert_eq!(rotated.buffer_ref_count(), Some(1));
+        assert_eq!(rotated.to_bgra().display_rotation(), Rotation::Cw90);
+        assert_eq!(
+            rotated.rotate(Rotation::Cw90).unwrap().display_rotation(),
+            Rotation::Cw90
+        );
+        assert!(!Rotation::Cw180.swaps_dimensions());
+    }
+
+    #[test]
+    fn test_plane_layout() {
+        let bgra = VideoFrame::from_bgra_with_stride(vec![1; 16], 1, 2, 8);
+        assert_eq!(bgra.bytes_per_pixel(), Some(4));
+        assert_eq!(bgra.plane_count(), 1);
+        assert_eq!(bgra.plane(0), Some((&[1; 16][..], 8)));
+        assert_eq!(bgra.plane(1), None);
+
+        let rgb = VideoFrame::from_rgb8(vec![1, 2, 3, 4, 5, 6], 2, 1);
+        assert_eq!(rgb.bytes_per_pixel(), Some(3));
+        assert_eq!(rgb.plane(0), Some((&[1, 2, 3, 4, 5, 6][..], 6)));
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![1; 4], vec![2; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(nv12.bytes_per_pixel(), None);
+        assert_eq!(nv12.plane_count(), 2);
+        assert_eq!(nv12.plane(0), Some((&[1; 4][..], 2)));
+        assert_eq!(nv12.plane(1), Some((&[2; 2][..], 2)));
+        assert_eq!(nv12.plane(2), None);
+
+        let i420 = VideoFrame::from_i420(
+            vec![1; 4],
+            vec![2],
+            vec![3],
+            2,
+            1,
+            1,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(i420.plane_count(), 3);
+        let planes: Vec<_> = (0..i420.plane_count())
+            .map(|index| i420.plane(index).unwrap())
+            .collect();
+        assert_eq!(planes, [(&[1; 4][..], 2), (&[2][..], 1), (&[3][..], 1)]);
+
+        let p010 = VideoFrame::from_p010(
+            vec![1 << 6; 4],
+            vec![2 << 6; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt2020,
+        );
+        assert_eq!(p010.plane_count(), 2);
+        assert_eq!(p010.plane(0), Some((&[64, 0, 64, 0, 64, 0, 64, 0][..], 4)));
+        assert_eq!(p010.plane(1), Some((&[128, 0, 128, 0][..], 4)));
+    }
+
+    #[test]
+    fn test_likely_equal() {
+        let frame = VideoFrame::from_bgra(vec![7; 16 * 16 * 4], 16, 16);
+        assert!(frame.likely_equal(&frame.clone()));
+        assert!(frame.likely_equal(&VideoFrame::from_bgra_with_stride(
+            vec![7; 16 * 20 * 4],
+            16,
+            16,
+            20 * 4
+        )));
+
+        let changed_at = |index: usize| {
+            let mut buffer = vec![7; 16 * 16 * 4];
+            buffer[index] = 0;
+            VideoFrame::from_bgra(buffer, 16, 16)
+        };
+        assert!(!frame.likely_equal(&changed_at(16 * 16 * 4 - 1)));
+        assert!(!frame.likely_equal(&changed_at(7 * 16 * 4 + 31)));
+        let unsampled = changed_at(2 * 16 * 4 + 1);
+        assert!(frame.likely_equal(&unsampled));
+        assert_ne!(frame, unsampled);
+
+        assert!(!frame.likely_equal(&VideoFrame::from_bgra(vec![7; 16 * 8 * 4], 16, 8)));
+        assert!(!frame.likely_equal(&VideoFrame::from_rgba(vec![7; 16 * 16 * 4], 16, 16)));
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![1; 4], vec![2; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        let other =
+            VideoFrame::from_nv12(vec![1; 4], vec![2, 3], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert!(nv12.likely_equal(&nv12.clone()));
+        assert!(!nv12.likely_equal(&other));
+    }
+
+    #[test]
+    fn test_frame_limits() {
+        let limits = FrameLimits {
+            max_width: 4,
+            max_height: 4,
+            max_bytes: 32,
+        };
+        let frame =
+            VideoFrame::try_from_raw_with_limits(vec![0; 32], 4, 2, PixelFormat::Bgra8, &limits)
+                .unwrap();
+        assert_eq!(frame.size(), (4, 2));
+        assert_eq!(
+            VideoFrame::try_from_raw_with_limits(vec![0; 40], 5, 2, PixelFormat::Bgra8, &limits),
+            Err(VideoFrameError::ExceedsLimits)
+        );
+        assert_eq!(
+            VideoFrame::try_from_raw_with_limits(vec![0; 64], 4, 4, PixelFormat::Bgra8, &limits),
+            Err(VideoFrameError::ExceedsLimits)
+        );
+        assert_eq!(limits.check(4, 4, PixelFormat::Nv12), Ok(()));
+        assert_eq!(
+            limits.check(4, 2, PixelFormat::Rgb8),
+            Ok(()),
+            "RGB frames count the BGRA buffer they're expanded to"
+        );
+
+        let limits = FrameLimits::default();
+        assert_eq!(limits.check(16384, 16384, PixelFormat::Bgra8), Ok(()));
+        assert_eq!(
+            limits.check(100_000, 100_000, PixelFormat::I420),
+            Err(VideoFrameError::ExceedsLimits)
+        );
+    }
+
+    #[test]
+    fn test_to_packed_yuyv() {
+        let i420 = VideoFrame::from_i420(
+            vec![10, 20, 30, 40, 50, 60, 70, 80],
+            vec![100, 110],
+            vec![150, 160],
+            4,
+            2,
+            2,
+            4,
+            2,
+            VideoColorSpace::Bt709,
+        )
+        .with_color_range(ColorRange::Limited);
+        let yuyv = i420.to_packed_yuyv().unwrap();
+        assert_eq!(yuyv.pixel_format(), PixelFormat::Yuyv);
+        assert_eq!(yuyv.backend(), VideoFrameBackend::CpuYuyv);
+        assert_eq!(yuyv.color_space(), VideoColorSpace::Bt709);
+        assert_eq!(yuyv.color_range(), ColorRange::Limited);
+        assert_eq!(yuyv.bytes_per_row(), 8);
+        assert_eq!(
+            yuyv.as_bytes().unwrap(),
+            [
+                10, 100, 20, 150, 30, 110, 40, 160, //
+                50, 100, 60, 150, 70, 110, 80, 160,
+            ]
+        );
+        assert_eq!(yuyv.validate(), Ok(()));
+        assert_eq!(yuyv.to_luma8().unwrap(), i420.to_luma8().unwrap());
+        assert_eq!(yuyv.to_bgra(), i420.to_bgra());
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![1, 2, 3, 4],
+            vec![5, 6],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        let yuyv = nv12.to_packed_yuyv().unwrap();
+        assert_eq!(yuyv.as_bytes().unwrap(), [1, 5, 2, 6, 3, 5, 4, 6]);
+        assert_eq!(yuyv.to_bgra(), nv12.to_bgra());
+        assert_eq!(yuyv.to_packed_yuyv().unwrap(), yuyv);
+        assert_eq!(
+            VideoFrame::from_raw(yuyv.as_bytes().unwrap().to_vec(), 2, 2, PixelFormat::Yuyv)
+                .unwrap(),
+            yuyv
+        );
+
+        let odd =
+            VideoFrame::from_nv12(vec![0; 6], vec![128; 4], 3, 4, 3, 2, VideoColorSpace::Bt601);
+        assert_eq!(odd.to_packed_yuyv(), Err(VideoFrameError::OddWidth(3)));
+        assert_eq!(
+            VideoFrame::from_raw(vec![0; 12], 3, 2, PixelFormat::Yuyv),
+            Err(VideoFrameError::OddWidth(3))
+        );
+        assert_eq!(
+            VideoFrame::from_bgra(vec![0; 4], 1, 1).to_packed_yuyv(),
+            Err(VideoFrameError::UnsupportedFormat(PixelFormat::Bgra8))
+        );
+    }
+
+    #[test]
+    fn test_rgba16() {
+        let channels: [u16; 8] = [0, 32768, 65535, 65535, 65535, 0, 257, 0];
+        let bytes = channels
+            .iter()
+            .flat_map(|channel| channel.to_le_bytes())
+            .collect::<Vec<_>>();
+        let unorm = VideoFrame::from_rgba16_unorm(bytes.clone(), 2, 1).unwrap();
+        assert_eq!(unorm.pixel_format(), PixelFormat::Rgba16Unorm);
+        assert_eq!(unorm.backend(), VideoFrameBackend::CpuRgba16);
+        assert_eq!(unorm.bit_depth(), 16);
+        assert_eq!(unorm.bytes_per_row(), 16);
+        assert_eq!(unorm.as_bytes().unwrap(), bytes.as_slice());
+        assert_eq!(
+            unorm.to_bgra().as_bytes().unwrap(),
+            [255, 128, 0, 255, 1, 0, 255, 0]
+        );
+        assert_eq!(
+            VideoFrame::from_raw(bytes, 2, 1, PixelFormat::Rgba16Unorm).unwrap(),
+            unorm
+        );
+
+        let halves: [u16; 4] = [0x3c00, 0x3800, 0x4000, 0xbc00];
+        let bytes = halves
+            .iter()
+            .flat_map(|channel| channel.to_le_bytes())
+            .collect::<Vec<_>>();
+        let float = VideoFrame::from_rgba16_float(bytes, 1, 1).unwrap();
+        assert_eq!(float.pixel_format(), PixelFormat::Rgba16Float);
+        assert_eq!(float.to_bgra().as_bytes().unwrap(), [255, 128, 255, 0]);
+        assert_eq!(float.dominant_solid_color(), Some([255, 128, 255, 0]));
+
+        assert_eq!(
+            VideoFrame::from_rgba16_float(vec![0; 4], 1, 1),
+            Err(VideoFrameError::BufferTooSmall {
+                expected: 8,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_is_mostly_black() {
+        let black = VideoFrame::solid_color(4, 4, [0, 0, 0, 255]);
+        assert_eq!(black.is_mostly_black(16, 0.95), Ok(true));
+        let bright = VideoFrame::solid_color(4, 4, [200, 220, 240, 255]);
+        assert_eq!(bright.is_mostly_black(16, 0.95), Ok(false));
+
+        let mut pixels = black.as_bytes().unwrap().to_vec();
+        pixels[..8].fill(255);
+        let mostly_black = VideoFrame::from_bgra(pixels, 4, 4);
+        assert_eq!(mostly_black.is_mostly_black(16, 0.85), Ok(true));
+        assert_eq!(mostly_black.is_mostly_black(16, 0.9), Ok(false));
+
+        let limited = VideoFrame::from_nv12(
+            vec![16; 4],
+            vec![128; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        )
+        .with_color_range(ColorRange::Limited);
+        assert_eq!(limited.is_mostly_black(8, 1.0), Ok(true));
+        let full = limited.with_color_range(ColorRange::Full);
+        assert_eq!(full.is_mostly_black(8, 1.0), Ok(false));
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let bgra = VideoFrame::from_bgra(vec![10, 20, 30, 255], 1, 1);
+        let negotiated = bgra
+            .negotiate(&[PixelFormat::Rgba8, PixelFormat::Bgra8])
+            .unwrap();
+        assert_eq!(negotiated.buffer_ref_count(), Some(2));
+        let rgba = bgra
+            .negotiate(&[PixelFormat::Nv12, PixelFormat::Rgba8])
+            .unwrap();
+        assert_eq!(rgba.pixel_format(), PixelFormat::Rgba8);
+        assert_eq!(rgba.as_bytes().unwrap(), [30, 20, 10, 255]);
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![1, 2, 3, 4],
+            vec![5, 6],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        let yuyv = nv12
+            .negotiate(&[PixelFormat::Bgra8, PixelFormat::Yuyv])
+            .unwrap();
+        assert_eq!(yuyv.pixel_format(), PixelFormat::Yuyv);
+        let rgb = nv12.negotiate(&[PixelFormat::Rgb8]).unwrap();
+        assert_eq!(rgb.pixel_format(), PixelFormat::Rgb8);
+        assert_eq!(rgb.color_space(), VideoColorSpace::Srgb);
+        assert_eq!(rgb.to_bgra(), nv12.to_bgra());
+
+        assert_eq!(
+            nv12.negotiate(&[PixelFormat::P010]),
+            Err(VideoFrameError::UnsupportedFormat(PixelFormat::Nv12))
+        );
+
+        let i420 = bgra
+            .negotiate(&[PixelFormat::I420, PixelFormat::Nv12])
+            .unwrap();
+        assert_eq!(i420, bgra.to_i420().unwrap());
+        let i420 = nv12.negotiate(&[PixelFormat::I420]).unwrap();
+        assert_eq!(i420.pixel_format(), PixelFormat::I420);
+        assert_eq!(i420, nv12.to_bgra().to_i420().unwrap());
+        let yuyv = nv12
+            .negotiate(&[PixelFormat::I420, PixelFormat::Yuyv])
+            .unwrap();
+        assert_eq!(yuyv.pixel_format(), PixelFormat::Yuyv);
+    }
+
+    #[test]
+    fn test_to_i420() {
+        let red = VideoFrame::from_bgra([0, 0, 255, 255].repeat(9), 3, 3)
+            .with_color_space(VideoColorSpace::Bt709)
+            .with_color_range(ColorRange::Limited);
+        let i420 = red.to_i420().unwrap();
+        assert_eq!(i420.pixel_format(), PixelFormat::I420);
+        assert_eq!(i420.color_space(), VideoColorSpace::Bt709);
+        let VideoFrameData::I420 {
+            y, u, v, u_stride, ..
+        } = &i420.data
+        else {
+            panic!("expected an I420 frame");
+        };
+        assert_eq!(y.as_slice(), [63; 9]);
+        assert_eq!((u.as_slice(), v.as_slice()), (&[102; 4][..], &[240; 4][..]));
+        assert_eq!(*u_stride, 2);
+
+        let checkerboard =
+            VideoFrame::from_bgra([[0, 0, 0, 255], [255; 4]].concat().repeat(2), 2, 2);
+        let i420 = checkerboard.to_i420().unwrap();
+        let VideoFrameData::I420 { y, u, v, .. } = &i420.data else {
+            panic!("expected an I420 frame");
+        };
+        assert_eq!(y.as_slice(), [0, 255, 0, 255]);
+        assert_eq!((u.as_slice(), v.as_slice()), (&[128][..], &[128][..]));
+        assert_eq!(
+            i420.to_i420().unwrap().memory_footprint(),
+            i420.memory_footprint()
+        );
+
+        assert_eq!(
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601)
+                .to_i420(),
+            Err(VideoFrameError::UnsupportedFormat(PixelFormat::Nv12))
+        );
+    }
+
+    #[test]
+    fn test_from_bgra_unchecked() {
+        // SAFETY: the buffer holds exactly `2 * 1 * 4` bytes.
+        let frame = unsafe { VideoFrame::from_bgra_unchecked(vec![1, 2, 3, 4, 5, 6, 7, 8], 2, 1) };
+        assert_eq!(
+            frame,
+            VideoFrame::from_bgra(vec![1, 2, 3, 4, 5, 6, 7, 8], 2, 1)
+        );
+        assert_eq!(frame.bytes_per_row(), 8);
+    }
+
+    #[test]
+    fn test_replace_bgra() {
+        let mut frame = VideoFrame::from_rgba(vec![0; 4], 1, 1)
+            .with_pts(Duration::from_millis(40))
+            .with_color_space(VideoColorSpace::Bt709);
+        let previous = frame.clone();
+        let buffer = Arc::new(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        frame.replace_bgra(buffer.clone(), 2, 1).unwrap();
+        assert_eq!(frame.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(frame.size(), (2, 1));
+        assert_eq!(frame.as_bytes().unwrap(), buffer.as_slice());
+        assert_eq!(frame.pts(), Some(Duration::from_millis(40)));
+        assert_eq!(frame.color_space(), VideoColorSpace::Bt709);
+        assert_eq!(Arc::strong_count(&buffer), 2);
+        assert_eq!(previous.as_bytes().unwrap(), [0; 4]);
+
+        assert_eq!(
+            frame.replace_bgra(Arc::new(vec![0; 4]), 2, 1),
+            Err(VideoFrameError::BufferTooSmall {
+                expected: 8,
+                actual: 4
+            })
+        );
+        assert_eq!(frame.size(), (2, 1));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let frame: VideoFrame = (vec![1, 2, 3, 4], 1, 1).try_into().unwrap();
+        assert_eq!(frame, VideoFrame::from_bgra(vec![1, 2, 3, 4], 1, 1));
+        assert_eq!(frame.as_bytes(), Some([1, 2, 3, 4].as_slice()));
+        assert_eq!(
+            VideoFrame::try_from((vec![0; 4], 2, 1)),
+            Err(VideoFrameError::BufferTooSmall {
+                expected: 8,
+                actual: 4
+            })
+        );
+
+        let image = image::RgbaImage::from_raw(1, 1, vec![5, 6, 7, 8]).unwrap();
+        let frame = VideoFrame::try_from(image).unwrap();
+        assert_eq!(frame.pixel_format(), PixelFormat::Rgba8);
+        assert_eq!(frame.as_bytes(), Some([5, 6, 7, 8].as_slice()));
+        assert_eq!(
+            VideoFrame::try_from(image::RgbaImage::new(0, 1)),
+            Err(VideoFrameError::ZeroDimension)
+        );
+    }
+
+    #[test]
+    fn test_alpha_coverage() {
+        let opaque = VideoFrame::solid_color(2, 2, [10, 20, 30, 255]);
+        assert_eq!(opaque.alpha_coverage(), Some(AlphaCoverage::FullyOpaque));
+        let transparent = VideoFrame::from_rgba(vec![0; 16], 2, 2);
+        assert_eq!(
+            transparent.alpha_coverage(),
+            Some(AlphaCoverage::FullyTransparent)
+        );
+        assert_eq!(
+            transparent
+                .with_alpha_mode(AlphaMode::Opaque)
+                .alpha_coverage(),
+            Some(AlphaCoverage::FullyOpaque)
+        );
+
+        let mixed = VideoFrame::from_bgra([[0, 0, 0, 255], [0; 4]].concat(), 2, 1);
+        assert_eq!(mixed.alpha_coverage(), Some(AlphaCoverage::Mixed));
+        let translucent = VideoFrame::solid_color(1, 1, [0, 0, 0, 128]);
+        assert_eq!(translucent.alpha_coverage(), Some(AlphaCoverage::Mixed));
+        let rgba16 =
+            VideoFrame::from_rgba16_unorm([0, 0, 0, 0, 0, 0, 255, 255].repeat(2), 2, 1).unwrap();
+        assert_eq!(rgba16.alpha_coverage(), Some(AlphaCoverage::FullyOpaque));
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(nv12.alpha_coverage(), None);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_core_video_pixel_formats() {
+        use core_video::pixel_buffer::CVPixelBuffer;
+
+        let buffer = CVPixelBuffer::new(u32::from_be_bytes(*b"x420"), 4, 2, None).unwrap();
+        let frame = VideoFrame::from_cv_pixel_buffer(buffer).unwrap();
+        assert_eq!(frame.pixel_format(), PixelFormat::P010);
+        assert_eq!(frame.color_range(), ColorRange::Limited);
+        assert_eq!(frame.to_bgra().size(), (4, 2));
+
+        let buffer = CVPixelBuffer::new(u32::from_be_bytes(*b"2vuy"), 4, 2, None).unwrap();
+        assert_eq!(
+            VideoFrame::from_cv_pixel_buffer(buffer).unwrap_err(),
+            VideoFrameError::UnsupportedSourceFormat("CoreVideo 2vuy".into())
+        );
+    }
+}
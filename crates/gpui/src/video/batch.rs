@@ -0,0 +1,51 @@
+use super::VideoFrame;
+use rayon::prelude::*;
+
+impl VideoFrame {
+    /// Convert a batch of frames with [`Self::to_bgra`] in parallel on the
+    /// global rayon thread pool, returning them in the same order.
+    ///
+    /// This is meant for offline work such as transcoding a queue of decoded
+    /// frames. It blocks until every frame is converted, so it shouldn't be
+    /// called on the render path.
+    pub fn to_bgra_batch(frames: &[VideoFrame]) -> Vec<VideoFrame> {
+        frames.par_iter().map(VideoFrame::to_bgra).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PixelFormat, VideoColorSpace};
+    use std::time::Duration;
+
+    #[test]
+    fn test_to_bgra_batch() {
+        let frames: Vec<_> = (0..32u8)
+            .map(|index| {
+                let frame = match index % 3 {
+                    0 => VideoFrame::from_bgra(vec![index; 16], 2, 2),
+                    1 => VideoFrame::from_rgba(vec![index, 0, 255, 255].repeat(4), 2, 2),
+                    _ => VideoFrame::from_nv12(
+                        vec![index; 4],
+                        vec![64, 192],
+                        2,
+                        2,
+                        2,
+                        2,
+                        VideoColorSpace::Bt709,
+                    ),
+                };
+                frame.with_pts(Duration::from_millis(index as u64 * 40))
+            })
+            .collect();
+
+        let batch = VideoFrame::to_bgra_batch(&frames);
+        assert_eq!(batch.len(), frames.len());
+        for (converted, frame) in batch.iter().zip(&frames) {
+            assert_eq!(*converted, frame.to_bgra());
+            assert_eq!(converted.pts(), frame.pts());
+            assert_eq!(converted.pixel_format(), PixelFormat::Bgra8);
+        }
+    }
+}
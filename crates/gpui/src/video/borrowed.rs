@@ -0,0 +1,114 @@
+use super::{
+    PixelFormat, VideoFrame, VideoFrameError, row_size, validate_dimensions, validate_plane,
+};
+
+/// Tightly packed BGRA pixels borrowed from a buffer that is only valid for a
+/// limited time, such as the one passed to a capture callback. Obtained with
+/// [`VideoFrame::from_bgra_borrowed`].
+///
+/// The lifetime ties the frame to the borrowed buffer, so it can't be kept
+/// past the callback. GPUI renders a frame after the paint phase that queued
+/// it, so painting needs an owned frame: call [`Self::to_owned`] for the one
+/// copy that takes, or read the pixels in place with [`Self::rows`].
+#[derive(Debug, Clone, Copy)]
+pub struct BorrowedVideoFrame<'a> {
+    pixels: &'a [u8],
+    width: u32,
+    height: u32,
+}
+
+impl VideoFrame {
+    /// Borrow tightly packed BGRA pixels without copying them.
+    ///
+    /// The buffer should contain `width * height * 4` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer doesn't match the dimensions. Use
+    /// [`Self::try_from_bgra_borrowed`] for buffers that come from untrusted
+    /// sources.
+    pub fn from_bgra_borrowed(buffer: &[u8], width: u32, height: u32) -> BorrowedVideoFrame<'_> {
+        Self::try_from_bgra_borrowed(buffer, width, height).expect("invalid BGRA buffer")
+    }
+
+    /// Borrow tightly packed BGRA pixels without copying them, validating the
+    /// buffer size.
+    pub fn try_from_bgra_borrowed(
+        buffer: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<BorrowedVideoFrame<'_>, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        let row_size = row_size(width, 4)?;
+        validate_plane(buffer.len(), row_size, row_size, height)?;
+        Ok(BorrowedVideoFrame {
+            pixels: &buffer[..row_size as usize * height as usize],
+            width,
+            height,
+        })
+    }
+}
+
+impl<'a> BorrowedVideoFrame<'a> {
+    /// The width of the frame in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the frame in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The borrowed pixels, `width * height * 4` bytes of BGRA.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.pixels
+    }
+
+    /// Iterate over the rows of the frame, top to bottom, each `width * 4`
+    /// bytes long.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + use<'a> {
+        self.pixels.chunks_exact(self.width as usize * 4)
+    }
+
+    /// Copy the pixels into an owned frame that can be painted and kept.
+    pub fn to_owned(&self) -> VideoFrame {
+        VideoFrame::from_packed(
+            self.pixels.to_vec(),
+            self.width,
+            self.height,
+            PixelFormat::Bgra8,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_borrowed_video_frame() {
+        let buffer: Vec<u8> = (0..16).collect();
+        let borrowed = VideoFrame::from_bgra_borrowed(&buffer, 2, 2);
+        assert_eq!((borrowed.width(), borrowed.height()), (2, 2));
+        assert_eq!(borrowed.as_bytes().as_ptr(), buffer.as_ptr());
+        let rows: Vec<_> = borrowed.rows().collect();
+        assert_eq!(rows, [&buffer[..8], &buffer[8..]]);
+
+        let owned = borrowed.to_owned();
+        drop(buffer);
+        assert_eq!(owned.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(
+            owned.as_bytes(),
+            Some((0..16).collect::<Vec<u8>>().as_slice())
+        );
+
+        assert_eq!(
+            VideoFrame::try_from_bgra_borrowed(&[0; 15], 2, 2).unwrap_err(),
+            VideoFrameError::BufferTooSmall {
+                expected: 16,
+                actual: 15
+            }
+        );
+    }
+}
@@ -0,0 +1,483 @@
+use super::{ColorRange, PixelFormat, VideoColorSpace, VideoFrame, VideoFrameData};
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+/// Identifies the pixels of a video frame, so that painting the same frame
+/// again can reuse what was uploaded for it.
+///
+/// Frames get a new buffer id when they're created and whenever their pixels
+/// change, so the key doesn't hold on to the buffer, and a buffer recycled
+/// through a [`VideoFramePool`](super::VideoFramePool) gets a new key.
+#[derive(Clone, PartialEq)]
+pub struct VideoCacheKey {
+    buffer_id: u64,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    color_space: VideoColorSpace,
+    color_range: ColorRange,
+}
+
+/// Get an id for a frame's pixels that no other pixels have had.
+pub(crate) fn next_buffer_id() -> u64 {
+    static NEXT_BUFFER_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_BUFFER_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+impl VideoFrame {
+    /// Get the key under which a renderer caches the upload of this frame.
+    ///
+    /// Clones of a frame share its key, including clones with a different
+    /// alpha mode or display rotation, which are applied as the upload is
+    /// drawn. Returns `None` for frames the renderer samples directly,
+    /// without an upload.
+    pub fn cache_key(&self) -> Option<VideoCacheKey> {
+        match &self.data {
+            VideoFrameData::Bgra { .. }
+            | VideoFrameData::Rgba { .. }
+            | VideoFrameData::Rgb { .. }
+            | VideoFrameData::Yuyv { .. }
+            | VideoFrameData::Rgba16 { .. }
+            | VideoFrameData::Nv12 { .. }
+            | VideoFrameData::I420 { .. }
+            | VideoFrameData::P010 { .. } => {}
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => return None,
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => return None,
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => {}
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => return None,
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => {}
+        }
+        Some(VideoCacheKey {
+            buffer_id: self.buffer_id,
+            width: self.width,
+            height: self.height,
+            format: self.pixel_format(),
+            color_space: self.color_space,
+            color_range: self.color_range,
+        })
+    }
+
+    /// Convert this frame to the packed pixels a renderer uploads for it.
+    ///
+    /// Renderers only call this when the upload of the frame isn't cached,
+    /// so that a frame painted again isn't converted again. The conversion
+    /// only depends on the frame's format, which is part of its
+    /// [`VideoCacheKey`]. The alpha mode and display rotation are applied as
+    /// the upload is drawn.
+    pub(crate) fn surface_pixels(&self) -> Option<SurfacePixels> {
+        // 16-bit RGBA frames are uploaded as is to keep their precision,
+        // unless they need converting for the renderer: Blade has no 16-bit
+        // unorm texture format.
+        if let VideoFrameData::Rgba16 { buffer, float } = &self.data
+            && (cfg!(target_os = "windows") || *float)
+        {
+            return Some(SurfacePixels {
+                buffer: buffer.clone(),
+                width: self.width,
+                height: self.height,
+                stride: self.bytes_per_row(),
+                format: self.pixel_format(),
+            });
+        }
+        // Renderers only upload packed CPU frames, so other CPU formats (such
+        // as NV12) are converted, and DMA-BUF frames are read back.
+        let frame = if self.packed_buffer().is_some() {
+            self.clone()
+        } else {
+            self.to_bgra()
+        };
+        let (buffer, stride, format) = frame.packed_buffer()?;
+        Some(SurfacePixels {
+            buffer: buffer.clone(),
+            width: frame.width,
+            height: frame.height,
+            stride,
+            format,
+        })
+    }
+}
+
+/// The packed pixels a renderer uploads for a frame painted as a surface.
+pub(crate) struct SurfacePixels {
+    pub(crate) buffer: Arc<Vec<u8>>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// Bytes between the start of consecutive rows.
+    pub(crate) stride: u32,
+    pub(crate) format: PixelFormat,
+}
+
+/// What a renderer has to do to draw a frame painted as a surface, as
+/// returned by [`VideoCache::prepare_upload`].
+pub(crate) enum SurfaceUpload<T> {
+    /// The frame was uploaded before, so its upload can be drawn as is.
+    Cached(T),
+    /// The frame has to be uploaded and inserted into the cache under `key`.
+    Upload {
+        key: VideoCacheKey,
+        pixels: SurfacePixels,
+        /// The upload of the frame painted in this one's place, taken out of
+        /// the cache, when only the region of `PartialUpload` needs updating
+        /// in it. Otherwise a new upload has to be created.
+        update: Option<(T, PartialUpload)>,
+    },
+}
+
+/// The region of a frame that is all that changed since `previous` was
+/// painted in its place, so that a renderer holding the upload of `previous`
+/// can update it in place instead of uploading the whole frame.
+///
+/// The previous frame is kept to look up its upload by its
+/// [`VideoFrame::cache_key`].
+#[derive(Clone)]
+pub(crate) struct PartialUpload {
+    pub(crate) previous: VideoFrame,
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+/// Keeps the GPU textures of recently painted video frames, so that a frame
+/// painted again, such as a paused video or a static screen share, isn't
+/// converted or uploaded again.
+///
+/// The renderers keep one of these for the frames painted by the
+/// [`Video`](crate::Video) element and [`Window::paint_video_surface`](crate::Window::paint_video_surface),
+/// and custom renderers can keep their own, holding whatever `T` they upload
+/// frames to.
+///
+/// Entries are evicted least recently used first once their total footprint
+/// exceeds the capacity, and once a frame has been drawn without painting
+/// them. Evicted values are handed back to the renderer to release.
+pub struct VideoCache<T> {
+    /// Entries ordered from least to most recently used.
+    entries: Vec<VideoCacheEntry<T>>,
+    capacity: usize,
+    footprint: usize,
+    /// Counts the calls to [`Self::remove_stale`], one per frame drawn.
+    generation: u64,
+}
+
+struct VideoCacheEntry<T> {
+    key: VideoCacheKey,
+    value: T,
+    footprint: usize,
+    /// The [`VideoCache::generation`] the entry was last used in.
+    used: u64,
+}
+
+impl<T> VideoCache<T> {
+    /// Create an empty cache holding up to `capacity` bytes of uploads.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity,
+            footprint: 0,
+            generation: 0,
+        }
+    }
+
+    /// Look up the upload of a frame, marking it as the most recently used.
+    pub fn get(&mut self, key: &VideoCacheKey) -> Option<&T> {
+        let index = self.entries.iter().position(|entry| entry.key == *key)?;
+        let mut entry = self.entries.remove(index);
+        entry.used = self.generation;
+        self.entries.push(entry);
+        self.entries.last().map(|entry| &entry.value)
+    }
+
+    /// Add the upload of a frame that takes `footprint` bytes, returning the
+    /// values evicted to make room for it. The new entry is kept even if it
+    /// exceeds the capacity on its own.
+    pub fn insert(&mut self, key: VideoCacheKey, value: T, footprint: usize) -> Vec<T> {
+        let mut evicted = Vec::new();
+        if let Some(index) = self.entries.iter().position(|entry| entry.key == key) {
+            evicted.push(self.remove(index));
+        }
+        while !self.entries.is_empty() && self.footprint + footprint > self.capacity {
+            evicted.push(self.remove(0));
+        }
+        self.footprint += footprint;
+        self.entries.push(VideoCacheEntry {
+            key,
+            value,
+            footprint,
+            used: self.generation,
+        });
+        evicted
+    }
+
+    /// Take the upload of the frame with the `previous` key out of the cache,
+    /// so that it can be updated in place and inserted again under `key`.
+    ///
+    /// Returns `None` if there's no such upload, or if its size or pixel
+    /// format differs from that of `key`, in which case the frame has to be
+    /// uploaded in full.
+    pub fn take_for_update(&mut self, previous: &VideoCacheKey, key: &VideoCacheKey) -> Option<T> {
+        if (previous.width, previous.height, previous.format) != (key.width, key.height, key.format)
+        {
+            return None;
+        }
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.key == *previous)?;
+        Some(self.remove(index))
+    }
+
+    /// Remove the entries that weren't used since the previous call, returning
+    /// their values.
+    ///
+    /// Renderers call this before drawing each frame, which paints every
+    /// visible surface again, so this evicts the uploads of frames that are
+    /// no longer painted.
+    pub fn remove_stale(&mut self) -> Vec<T> {
+        let mut evicted = Vec::new();
+        let mut index = 0;
+        while index < self.entries.len() {
+            if self.entries[index].used == self.generation {
+                index += 1;
+            } else {
+                evicted.push(self.remove(index));
+            }
+        }
+        self.generation += 1;
+        evicted
+    }
+
+    /// Remove every entry, returning their values.
+    pub fn clear(&mut self) -> Vec<T> {
+        self.footprint = 0;
+        self.entries.drain(..).map(|entry| entry.value).collect()
+    }
+
+    fn remove(&mut self, index: usize) -> T {
+        let entry = self.entries.remove(index);
+        self.footprint -= entry.footprint;
+        entry.value
+    }
+}
+
+impl<T: Clone> VideoCache<T> {
+    /// Decide how to draw a frame painted as a surface, converting it only
+    /// when its upload isn't cached.
+    ///
+    /// When only a region of the frame changed since the frame of
+    /// `partial_upload` was painted in its place, the upload of that frame is
+    /// taken out of the cache to be updated in place, if its size and format
+    /// still match. Returns `None` for frames the renderer samples directly,
+    /// and for frames that can't be converted.
+    pub(crate) fn prepare_upload(
+        &mut self,
+        frame: &VideoFrame,
+        partial_upload: Option<&PartialUpload>,
+    ) -> Option<SurfaceUpload<T>> {
+        let key = frame.cache_key()?;
+        if let Some(cached) = self.get(&key) {
+            return Some(SurfaceUpload::Cached(cached.clone()));
+        }
+        let pixels = frame.surface_pixels()?;
+        let update = partial_upload.and_then(|partial_upload| {
+            let previous = partial_upload.previous.cache_key()?;
+            self.take_for_update(&previous, &key)
+                .map(|value| (value, partial_upload.clone()))
+        });
+        Some(SurfaceUpload::Upload {
+            key,
+            pixels,
+            update,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AlphaMode, Rotation, VideoFramePool};
+
+    /// Paints frames through a cache the way the renderers do, numbering
+    /// each upload.
+    struct Renderer {
+        cache: VideoCache<u32>,
+        uploads: u32,
+        uploaded_bytes: usize,
+    }
+
+    impl Renderer {
+        fn new(capacity: usize) -> Self {
+            Self {
+                cache: VideoCache::new(capacity),
+                uploads: 0,
+                uploaded_bytes: 0,
+            }
+        }
+
+        /// Paint a frame, returning the uploads evicted to make room for it.
+        fn paint(
+            &mut self,
+            frame: &VideoFrame,
+            partial_upload: Option<&PartialUpload>,
+        ) -> Vec<u32> {
+            let Some(SurfaceUpload::Upload {
+                key,
+                pixels,
+                update,
+            }) = self.cache.prepare_upload(frame, partial_upload)
+            else {
+                return Vec::new();
+            };
+            let upload = match update {
+                Some((upload, partial_upload)) => {
+                    self.uploaded_bytes +=
+                        partial_upload.width as usize * partial_upload.height as usize * 4;
+                    upload
+                }
+                None => {
+                    self.uploads += 1;
+                    self.uploaded_bytes += pixels.buffer.len();
+                    self.uploads - 1
+                }
+            };
+            self.cache.insert(key, upload, pixels.buffer.len())
+        }
+    }
+
+    #[test]
+    fn test_video_cache() {
+        let mut renderer = Renderer::new(32);
+        let frame = VideoFrame::from_bgra(vec![0; 2 * 2 * 4], 2, 2);
+        renderer.paint(&frame, None);
+        assert_eq!(renderer.uploads, 1);
+
+        renderer.paint(&frame.clone(), None);
+        renderer.paint(
+            &frame
+                .clone()
+                .with_alpha_mode(AlphaMode::Premultiplied)
+                .with_display_rotation(Rotation::Cw90),
+            None,
+        );
+        assert_eq!(renderer.uploads, 1);
+
+        let other = VideoFrame::from_bgra(vec![0; 2 * 2 * 4], 2, 2);
+        assert_eq!(renderer.paint(&other, None), Vec::<u32>::new());
+        let third = VideoFrame::from_bgra(vec![0; 2 * 2 * 4], 2, 2);
+        assert_eq!(renderer.paint(&third, None), vec![0]);
+
+        assert_eq!(renderer.cache.remove_stale(), Vec::<u32>::new());
+        renderer.paint(&third, None);
+        assert_eq!(renderer.cache.remove_stale(), vec![1]);
+        assert_eq!(renderer.cache.clear(), vec![2]);
+    }
+
+    #[test]
+    fn test_cached_frame_buffers_are_recycled() {
+        let pool = VideoFramePool::new();
+        let mut renderer = Renderer::new(1024);
+        let frame = pool
+            .build_frame(pool.acquire(2, 2, PixelFormat::Bgra8).unwrap(), 2, 2)
+            .unwrap();
+        let allocation = frame.as_bytes().unwrap().as_ptr();
+        renderer.paint(&frame, None);
+        let key = frame.cache_key();
+        drop(frame);
+
+        let recycled = pool
+            .build_frame(pool.acquire(2, 2, PixelFormat::Bgra8).unwrap(), 2, 2)
+            .unwrap();
+        assert_eq!(recycled.as_bytes().unwrap().as_ptr(), allocation);
+        assert!(recycled.cache_key() != key);
+        renderer.paint(&recycled, None);
+        assert_eq!(renderer.uploads, 2);
+    }
+
+    #[test]
+    fn test_modified_frame_is_uploaded_again() {
+        let mut renderer = Renderer::new(1024);
+        let mut frame = VideoFrame::from_bgra(vec![0; 2 * 2 * 4], 2, 2);
+        renderer.paint(&frame, None);
+        if let Some(bytes) = frame.as_bytes_mut() {
+            bytes[0] = 255;
+        }
+        renderer.paint(&frame, None);
+        assert_eq!(renderer.uploads, 2);
+    }
+
+    #[test]
+    fn test_static_frame_is_converted_and_uploaded_once() {
+        let mut renderer = Renderer::new(1024);
+        let frame =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        // Ten seconds at 60 frames per second.
+        for _ in 0..600 {
+            renderer.paint(&frame, None);
+        }
+        assert_eq!(renderer.uploads, 1);
+        assert_eq!(renderer.uploaded_bytes, 16);
+        assert!(matches!(
+            renderer.cache.prepare_upload(&frame, None),
+            Some(SurfaceUpload::Cached(0))
+        ));
+    }
+
+    #[test]
+    fn test_partial_upload() {
+        let mut renderer = Renderer::new(1024);
+        let previous = VideoFrame::solid_color(4, 4, [0, 0, 0, 255]);
+        renderer.paint(&previous, None);
+        assert_eq!(renderer.uploaded_bytes, 64);
+
+        let mut pixels = previous.as_bytes().unwrap().to_vec();
+        pixels[20..24].copy_from_slice(&[255; 4]);
+        let frame = VideoFrame::from_bgra(pixels, 4, 4);
+        let (x, y, width, height) = frame.diff_bounds(&previous).unwrap();
+        let partial_upload = PartialUpload {
+            previous: previous.clone(),
+            x,
+            y,
+            width,
+            height,
+        };
+        renderer.paint(&frame, Some(&partial_upload));
+        assert_eq!(renderer.uploads, 1);
+        assert_eq!(renderer.uploaded_bytes, 68);
+        renderer.paint(&frame, None);
+        assert_eq!(renderer.uploaded_bytes, 68);
+
+        renderer.paint(&previous, None);
+        assert_eq!(renderer.uploads, 2);
+        assert_eq!(renderer.uploaded_bytes, 132);
+
+        let resized = VideoFrame::solid_color(2, 2, [0, 0, 0, 255]);
+        let partial_upload = PartialUpload {
+            previous,
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+        renderer.paint(&resized, Some(&partial_upload));
+        assert_eq!(renderer.uploads, 3);
+        assert_eq!(renderer.uploaded_bytes, 148);
+    }
+
+    #[test]
+    fn test_surface_pixels_are_not_rewritten_for_alpha() {
+        let frame = VideoFrame::from_bgra(vec![32, 64, 96, 128], 1, 1)
+            .with_alpha_mode(AlphaMode::Premultiplied);
+        let pixels = frame
+            .surface_pixels()
+            .expect("packed frames have surface pixels");
+        let (buffer, _, _) = frame.packed_buffer().expect("BGRA frames are packed");
+        assert!(Arc::ptr_eq(&pixels.buffer, buffer));
+        assert_eq!(pixels.buffer.as_slice(), &[32, 64, 96, 128]);
+    }
+}
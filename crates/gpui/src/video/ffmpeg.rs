@@ -0,0 +1,352 @@
+use super::{
+    ColorRange, PixelFormat, VideoColorSpace, VideoFrame, VideoFrameData, VideoFrameError,
+    Yuv420Planes, YuvToRgb, row_size, swap_red_blue, validate_dimensions,
+};
+use ffmpeg_next::{color, ffi, format::Pixel, frame};
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+
+impl VideoFrame {
+    /// Create a video frame from a frame decoded by FFmpeg.
+    ///
+    /// BGRA, RGBA, NV12 and YUV420P frames are supported. Frames whose buffers
+    /// are reference counted and stored top down, as decoders return them,
+    /// are wrapped without copying: the frame takes a new reference to the
+    /// buffers, so the decoder doesn't reuse them until the frame and all of
+    /// its clones have been dropped. Like other
+    /// [hardware-backed](Self::is_hardware_backed) frames, their pixels are
+    /// converted when they're painted or read back with [`Self::to_bgra`], and
+    /// [`Self::detach`] copies them into buffers of their own. Other frames
+    /// have their planes copied, so the `AVFrame` can be reused afterwards.
+    ///
+    /// The color space and range are taken from the frame, falling back to the
+    /// usual default for the format and to full range when FFmpeg leaves them
+    /// unspecified. The PTS is carried over when the frame has a time base.
+    pub fn from_av_frame(source: &frame::Video) -> Result<VideoFrame, VideoFrameError> {
+        let (width, height) = (source.width(), source.height());
+        let planes = av_frame_planes(source, width, height)?;
+        let reference = if planes.iter().all(|plane| plane.stride >= 0) {
+            reference_av_frame(source)
+        } else {
+            None
+        };
+        let frame = match reference {
+            Some(reference) => {
+                VideoFrame::new(VideoFrameData::FFmpeg(Arc::new(reference)), width, height)
+            }
+            None => copy_planes(source.format(), &planes, width, height)?,
+        };
+        let frame = match frame.pixel_format() {
+            PixelFormat::Nv12 | PixelFormat::I420 => {
+                frame.with_color_space(yuv_color_space(source.color_space()))
+            }
+            _ => frame,
+        };
+        let frame = frame.with_color_range(match source.color_range() {
+            color::Range::MPEG => ColorRange::Limited,
+            _ => ColorRange::Full,
+        });
+        Ok(match presentation_time(source) {
+            Some(pts) => frame.with_pts(pts),
+            None => frame,
+        })
+    }
+}
+
+/// A plane of an `AVFrame`: its first row, the bytes from the start of one
+/// row to the next, which are negative for planes stored bottom up, and the
+/// size and number of the rows that hold pixels.
+struct AvPlane<'a> {
+    data: *const u8,
+    stride: i32,
+    row_size: u32,
+    rows: u32,
+    frame: PhantomData<&'a frame::Video>,
+}
+
+impl<'a> AvPlane<'a> {
+    fn row(&self, index: u32) -> &'a [u8] {
+        // SAFETY: the `AVFrame` outlives `'a`, and holds `rows` rows of at
+        // least `row_size` bytes, `stride` bytes apart.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.data.offset(index as isize * self.stride as isize),
+                self.row_size as usize,
+            )
+        }
+    }
+
+    /// Get the bytes of a plane stored top down, from its first row to the
+    /// end of its last, with its stride.
+    fn bytes(&self) -> Result<(&'a [u8], u32), VideoFrameError> {
+        let stride = u32::try_from(self.stride).map_err(|_| VideoFrameError::InvalidStride {
+            stride: self.stride.unsigned_abs(),
+            row_size: self.row_size,
+        })?;
+        let len = stride as usize * (self.rows as usize - 1) + self.row_size as usize;
+        // SAFETY: as in `Self::row`, and the rows follow each other.
+        let bytes = unsafe { std::slice::from_raw_parts(self.data, len) };
+        Ok((bytes, stride))
+    }
+
+    /// Copy the rows of the plane into a tightly packed buffer.
+    fn to_packed(&self) -> Vec<u8> {
+        let mut packed = Vec::with_capacity(self.row_size as usize * self.rows as usize);
+        for index in 0..self.rows {
+            packed.extend_from_slice(self.row(index));
+        }
+        packed
+    }
+}
+
+/// Get the planes holding the `width` by `height` region at the origin of a
+/// frame in one of the supported formats.
+fn av_frame_planes(
+    source: &frame::Video,
+    width: u32,
+    height: u32,
+) -> Result<Vec<AvPlane<'_>>, VideoFrameError> {
+    validate_dimensions(width, height)?;
+    if width > source.width() || height > source.height() {
+        return Err(VideoFrameError::OutOfBounds);
+    }
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    let layout = match source.format() {
+        Pixel::BGRA | Pixel::RGBA => vec![(row_size(width, 4)?, height)],
+        Pixel::NV12 => vec![(width, height), (row_size(chroma_width, 2)?, chroma_height)],
+        Pixel::YUV420P => vec![
+            (width, height),
+            (chroma_width, chroma_height),
+            (chroma_width, chroma_height),
+        ],
+        format => {
+            return Err(VideoFrameError::UnsupportedSourceFormat(format!(
+                "{format:?}"
+            )));
+        }
+    };
+    // SAFETY: `source` owns a valid `AVFrame` for the duration of the borrow.
+    let av_frame = unsafe { &*source.as_ptr() };
+    layout
+        .into_iter()
+        .enumerate()
+        .map(|(index, (row_size, rows))| {
+            let data = av_frame.data[index];
+            let stride = av_frame.linesize[index];
+            if data.is_null() {
+                return Err(VideoFrameError::SourceUnavailable(format!(
+                    "plane {index} of the AVFrame has no data"
+                )));
+            }
+            if stride.unsigned_abs() < row_size {
+                return Err(VideoFrameError::InvalidStride {
+                    stride: stride.unsigned_abs(),
+                    row_size,
+                });
+            }
+            Ok(AvPlane {
+                data,
+                stride,
+                row_size,
+                rows,
+                frame: PhantomData,
+            })
+        })
+        .collect()
+}
+
+/// Take a new reference to the buffers of a frame, or `None` if they aren't
+/// reference counted, in which case FFmpeg would copy them.
+fn reference_av_frame(source: &frame::Video) -> Option<frame::Video> {
+    // SAFETY: `source` owns a valid `AVFrame`, and the clone is a new frame
+    // that the returned `frame::Video` frees.
+    unsafe {
+        if (*source.as_ptr()).buf[0].is_null() {
+            return None;
+        }
+        let reference = ffi::av_frame_clone(source.as_ptr());
+        (!reference.is_null()).then(|| frame::Video::wrap(reference))
+    }
+}
+
+/// Copy the planes of a frame into tightly packed buffers of its own.
+fn copy_planes(
+    format: Pixel,
+    planes: &[AvPlane<'_>],
+    width: u32,
+    height: u32,
+) -> Result<VideoFrame, VideoFrameError> {
+    let mut buffers = planes.iter().map(AvPlane::to_packed);
+    let mut next_buffer = || buffers.next().unwrap_or_default();
+    let stride = |index: usize| planes.get(index).map_or(0, |plane| plane.row_size);
+    match format {
+        Pixel::BGRA => VideoFrame::try_from_bgra(next_buffer(), width, height),
+        Pixel::RGBA => VideoFrame::try_from_rgba(next_buffer(), width, height),
+        Pixel::NV12 => VideoFrame::try_from_nv12(
+            next_buffer(),
+            next_buffer(),
+            stride(0),
+            stride(1),
+            width,
+            height,
+            VideoColorSpace::Bt601,
+        ),
+        Pixel::YUV420P => VideoFrame::try_from_i420(
+            next_buffer(),
+            next_buffer(),
+            next_buffer(),
+            stride(0),
+            stride(1),
+            stride(2),
+            width,
+            height,
+            VideoColorSpace::Bt601,
+        ),
+        format => Err(VideoFrameError::UnsupportedSourceFormat(format!(
+            "{format:?}"
+        ))),
+    }
+}
+
+/// Check that a wrapped frame holds the `width` by `height` region at its
+/// origin, as for [`VideoFrame::validate`].
+pub(super) fn validate_av_frame(
+    source: &frame::Video,
+    width: u32,
+    height: u32,
+) -> Result<(), VideoFrameError> {
+    av_frame_planes(source, width, height).map(|_| ())
+}
+
+/// Copy the `width` by `height` region at the origin of a wrapped frame into
+/// buffers of its own, as for [`VideoFrame::detach`].
+pub(super) fn copy_av_frame(
+    source: &frame::Video,
+    width: u32,
+    height: u32,
+) -> Result<VideoFrameData, VideoFrameError> {
+    let planes = av_frame_planes(source, width, height)?;
+    Ok(copy_planes(source.format(), &planes, width, height)?.data)
+}
+
+/// Convert the `width` by `height` region at the origin of a wrapped frame
+/// to a tightly packed BGRA buffer.
+pub(super) fn av_frame_to_bgra(
+    source: &frame::Video,
+    width: u32,
+    height: u32,
+    conversion: YuvToRgb,
+) -> Result<Vec<u8>, VideoFrameError> {
+    let planes = av_frame_planes(source, width, height)?;
+    match (source.format(), planes.as_slice()) {
+        (Pixel::BGRA, [plane]) => Ok(plane.to_packed()),
+        (Pixel::RGBA, [plane]) => {
+            let mut bgra = plane.to_packed();
+            swap_red_blue(&mut bgra);
+            Ok(bgra)
+        }
+        (Pixel::NV12, [y_plane, uv_plane]) => {
+            let (y_plane, y_stride) = y_plane.bytes()?;
+            let (uv_plane, uv_stride) = uv_plane.bytes()?;
+            Ok(Yuv420Planes::nv12(y_plane, y_stride, uv_plane, uv_stride)
+                .to_bgra(width, height, conversion))
+        }
+        (Pixel::YUV420P, [y, u, v]) => {
+            let (y, y_stride) = y.bytes()?;
+            let (u, u_stride) = u.bytes()?;
+            let (v, v_stride) = v.bytes()?;
+            Ok(Yuv420Planes::i420(y, y_stride, u, u_stride, v, v_stride)
+                .to_bgra(width, height, conversion))
+        }
+        (format, _) => Err(VideoFrameError::UnsupportedSourceFormat(format!(
+            "{format:?}"
+        ))),
+    }
+}
+
+/// Map the FFmpeg pixel formats that frames can wrap to their pixel format.
+pub(super) fn av_pixel_format(format: Pixel) -> Option<PixelFormat> {
+    match format {
+        Pixel::BGRA => Some(PixelFormat::Bgra8),
+        Pixel::RGBA => Some(PixelFormat::Rgba8),
+        Pixel::NV12 => Some(PixelFormat::Nv12),
+        Pixel::YUV420P => Some(PixelFormat::I420),
+        _ => None,
+    }
+}
+
+/// Map an FFmpeg color space to the matrix used for YUV conversion.
+fn yuv_color_space(space: color::Space) -> VideoColorSpace {
+    match space {
+        color::Space::BT709 => VideoColorSpace::Bt709,
+        color::Space::BT2020NCL | color::Space::BT2020CL => VideoColorSpace::Bt2020,
+        _ => VideoColorSpace::Bt601,
+    }
+}
+
+/// Convert the frame's PTS from its time base, if it has both.
+fn presentation_time(source: &frame::Video) -> Option<Duration> {
+    let pts = u64::try_from(source.pts()?).ok()?;
+    // SAFETY: `source` owns a valid `AVFrame` for the duration of the borrow.
+    let time_base = unsafe { (*source.as_ptr()).time_base };
+    let numerator = u64::try_from(time_base.num).ok()?;
+    let denominator = u64::try_from(time_base.den).ok().filter(|den| *den != 0)?;
+    let nanos = pts as u128 * numerator as u128 * 1_000_000_000 / denominator as u128;
+    Some(Duration::from_nanos(u64::try_from(nanos).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VideoFrameBackend;
+
+    #[test]
+    fn test_from_av_frame() {
+        let mut source = frame::Video::new(Pixel::NV12, 4, 2);
+        source.data_mut(0).fill(16);
+        source.data_mut(1).fill(128);
+        source.set_color_space(color::Space::BT709);
+        source.set_color_range(color::Range::MPEG);
+        let frame = VideoFrame::from_av_frame(&source).unwrap();
+        assert_eq!(frame.backend(), VideoFrameBackend::FFmpeg);
+        assert_eq!(frame.pixel_format(), PixelFormat::Nv12);
+        assert_eq!(frame.size(), (4, 2));
+        assert_eq!(frame.color_space(), VideoColorSpace::Bt709);
+        assert_eq!(frame.color_range(), ColorRange::Limited);
+        assert_eq!(
+            frame.to_bgra().as_bytes().unwrap()[..4],
+            [0, 0, 0, 255],
+            "limited-range black converts to black"
+        );
+
+        let detached = frame.detach();
+        assert_eq!(detached.backend(), VideoFrameBackend::CpuNv12);
+        assert_eq!(detached.nv12_planes().unwrap().0, &[16; 8]);
+        drop(source);
+        assert_eq!(frame.to_bgra(), detached.to_bgra());
+
+        let source = frame::Video::new(Pixel::GRAY8, 4, 2);
+        assert_eq!(
+            VideoFrame::from_av_frame(&source).unwrap_err(),
+            VideoFrameError::UnsupportedSourceFormat("GRAY8".into())
+        );
+    }
+
+
+    #[test]
+    fn test_from_bottom_up_av_frame() {
+        let mut source = frame::Video::new(Pixel::BGRA, 1, 2);
+        let stride = source.stride(0);
+        source.data_mut(0)[..4].copy_from_slice(&[1, 2, 3, 255]);
+        source.data_mut(0)[stride..stride + 4].copy_from_slice(&[4, 5, 6, 255]);
+        // SAFETY: the second row is within the frame's buffer, and stepping
+        // back from it by the stride reaches the first.
+        unsafe {
+            let av_frame = &mut *source.as_mut_ptr();
+            av_frame.data[0] = av_frame.data[0].add(stride);
+            av_frame.linesize[0] = -av_frame.linesize[0];
+        }
+        let frame = VideoFrame::from_av_frame(&source).unwrap();
+        assert_eq!(frame.backend(), VideoFrameBackend::CpuBgra);
+        assert_eq!(frame.as_bytes(), Some(&[4, 5, 6, 255, 1, 2, 3, 255][..]));
+    }
+}
@@ -0,0 +1,96 @@
+use std::{collections::VecDeque, time::Duration};
+
+use super::{VideoFrame, VideoFrameError};
+
+/// Estimates the rate at which frames arrive from their PTS, such as for an
+/// overlay showing a stream's frame rate.
+///
+/// The estimate covers the frames whose PTS is within a sliding window of the
+/// latest one, so it follows changes in the rate and tolerates irregular
+/// arrival. A gap longer than the window, or a PTS that goes backwards, such
+/// as after seeking, starts the estimate over.
+pub struct FpsEstimator {
+    /// PTS of the frames in the window, oldest first.
+    timestamps: VecDeque<Duration>,
+    window: Duration,
+}
+
+impl FpsEstimator {
+    /// Create an estimator averaging over the frames within `window` of the
+    /// latest one.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            timestamps: VecDeque::new(),
+            window,
+        }
+    }
+
+    /// Record a frame, which must have a PTS.
+    pub fn push(&mut self, frame: &VideoFrame) -> Result<(), VideoFrameError> {
+        let pts = frame.pts().ok_or(VideoFrameError::MissingPts)?;
+        self.push_pts(pts);
+        Ok(())
+    }
+
+    /// Record the PTS of a frame.
+    pub fn push_pts(&mut self, pts: Duration) {
+        if let Some(&last) = self.timestamps.back()
+            && (pts < last || pts - last > self.window)
+        {
+            self.timestamps.clear();
+        }
+        self.timestamps.push_back(pts);
+        while let Some(&first) = self.timestamps.front()
+            && pts - first > self.window
+        {
+            self.timestamps.pop_front();
+        }
+    }
+
+    /// The estimated frames per second, or zero until two frames with
+    /// distinct PTS have been recorded since the last reset.
+    pub fn current_fps(&self) -> f32 {
+        let (Some(first), Some(last)) = (self.timestamps.front(), self.timestamps.back()) else {
+            return 0.;
+        };
+        let elapsed = (*last - *first).as_secs_f32();
+        if elapsed <= 0. {
+            return 0.;
+        }
+        (self.timestamps.len() - 1) as f32 / elapsed
+    }
+
+    /// Forget every recorded frame.
+    pub fn reset(&mut self) {
+        self.timestamps.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fps_estimator() {
+        let mut estimator = FpsEstimator::new(Duration::from_secs(1));
+        assert_eq!(estimator.current_fps(), 0.);
+        for index in 0..90 {
+            let frame = VideoFrame::from_bgra(vec![0; 4], 1, 1)
+                .with_pts(Duration::from_micros(index * 33_333));
+            estimator.push(&frame).unwrap();
+        }
+        assert!((estimator.current_fps() - 30.).abs() < 0.1);
+
+        estimator.push_pts(Duration::from_secs(10));
+        assert_eq!(estimator.current_fps(), 0.);
+        for index in 1..=10 {
+            estimator.push_pts(Duration::from_secs(10) + Duration::from_millis(index * 100));
+        }
+        assert!((estimator.current_fps() - 10.).abs() < 0.1);
+
+        assert_eq!(
+            estimator.push(&VideoFrame::from_bgra(vec![0; 4], 1, 1)),
+            Err(VideoFrameError::MissingPts)
+        );
+    }
+}
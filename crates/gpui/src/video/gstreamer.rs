@@ -0,0 +1,227 @@
+use super::{
+    ColorRange, VideoColorSpace, VideoFrame, VideoFrameError, pack_rows, row_size, validate_plane,
+};
+use ::gstreamer as gst;
+use gstreamer_video::{self as gst_video, prelude::VideoFrameExt as _};
+use std::time::Duration;
+
+impl VideoFrame {
+    /// Create a video frame from a GStreamer buffer, such as one pulled from an
+    /// `appsink`, described by `info`.
+    ///
+    /// BGRA, RGBA, NV12 and I420 buffers are supported. On Linux, BGRA, RGBA
+    /// and contiguous NV12 buffers backed by a single DMA-BUF memory are
+    /// wrapped without copying, and the buffer is kept alive until the frame
+    /// and all of its clones have been dropped, so that its pool doesn't reuse
+    /// the memory for a later frame while this one is still shown. Other
+    /// buffers are mapped, copied with their strides, and unmapped before this
+    /// returns. The buffer's PTS and the color range of `info` are carried
+    /// over.
+    pub fn from_gst_buffer(
+        buffer: &gst::Buffer,
+        info: &gst_video::VideoInfo,
+    ) -> Result<VideoFrame, VideoFrameError> {
+        #[cfg(target_os = "linux")]
+        let frame = match dma_buf_frame(buffer, info)? {
+            Some(frame) => frame,
+            None => mapped_frame(buffer, info)?,
+        };
+        #[cfg(not(target_os = "linux"))]
+        let frame = mapped_frame(buffer, info)?;
+        let frame = frame.with_color_range(match info.colorimetry().range() {
+            gst_video::VideoColorRange::Range16_235 => ColorRange::Limited,
+            _ => ColorRange::Full,
+        });
+        Ok(match buffer.pts() {
+            Some(pts) => frame.with_pts(Duration::from_nanos(pts.nseconds())),
+            None => frame,
+        })
+    }
+}
+
+/// Copy the planes of a buffer out of a mapping that is released on return.
+fn mapped_frame(
+    buffer: &gst::Buffer,
+    info: &gst_video::VideoInfo,
+) -> Result<VideoFrame, VideoFrameError> {
+    let (width, height) = (info.width(), info.height());
+    let mapped = gst_video::VideoFrameRef::from_buffer_ref_readable(buffer.as_ref(), info)
+        .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string()))?;
+    let plane = |index: u32| -> Result<(Vec<u8>, u32), VideoFrameError> {
+        let bytes = mapped
+            .plane_data(index)
+            .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string()))?;
+        let stride = mapped.plane_stride()[index as usize];
+        Ok((bytes.to_vec(), stride.max(0) as u32))
+    };
+    match info.format() {
+        gst_video::VideoFormat::Bgra => {
+            let (buffer, stride) = plane(0)?;
+            VideoFrame::try_from_bgra_with_stride(buffer, width, height, stride)
+        }
+        gst_video::VideoFormat::Rgba => {
+            let (buffer, stride) = plane(0)?;
+            let row_size = row_size(width, 4)?;
+            validate_plane(buffer.len(), row_size, stride, height)?;
+            let buffer = pack_rows(&buffer, stride, row_size, height);
+            VideoFrame::try_from_rgba(buffer, width, height)
+        }
+        gst_video::VideoFormat::Nv12 => {
+            let (y_plane, y_stride) = plane(0)?;
+            let (uv_plane, uv_stride) = plane(1)?;
+            VideoFrame::try_from_nv12(
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+                width,
+                height,
+                yuv_color_space(info),
+            )
+        }
+        gst_video::VideoFormat::I420 => {
+            let (y, y_stride) = plane(0)?;
+            let (u, u_stride) = plane(1)?;
+            let (v, v_stride) = plane(2)?;
+            VideoFrame::try_from_i420(
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+                width,
+                height,
+                yuv_color_space(info),
+            )
+        }
+        format => Err(VideoFrameError::UnsupportedSourceFormat(
+            format.to_str().to_string(),
+        )),
+    }
+}
+
+/// Wrap a buffer that consists of a single linear DMA-BUF memory, keeping the
+/// pixels on the GPU. Returns `None` for buffers that have to be mapped.
+#[cfg(target_os = "linux")]
+fn dma_buf_frame(
+    buffer: &gst::Buffer,
+    info: &gst_video::VideoInfo,
+) -> Result<Option<VideoFrame>, VideoFrameError> {
+    use std::os::fd::BorrowedFd;
+
+    if buffer.n_memory() != 1 {
+        return Ok(None);
+    }
+    let Some(memory) = buffer
+        .peek_memory(0)
+        .downcast_memory_ref::<gstreamer_allocators::DmaBufMemory>()
+    else {
+        return Ok(None);
+    };
+    let (stride, offset) = (info.stride(), info.offset());
+    let fourcc = match info.format() {
+        gst_video::VideoFormat::Bgra => super::DRM_FORMAT_ARGB8888,
+        gst_video::VideoFormat::Rgba => super::DRM_FORMAT_ABGR8888,
+        gst_video::VideoFormat::Nv12
+            if stride[1] == stride[0]
+                && offset[1] == offset[0] + stride[0].max(0) as usize * info.height() as usize =>
+        {
+            super::DRM_FORMAT_NV12
+        }
+        _ => return Ok(None),
+    };
+    // SAFETY: the memory keeps its file descriptor open while it's borrowed,
+    // and the frame owns a duplicate of it.
+    let fd = unsafe { BorrowedFd::borrow_raw(memory.fd()) }
+        .try_clone_to_owned()
+        .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string()))?;
+    let frame = VideoFrame::from_dma_buf(
+        fd,
+        info.width(),
+        info.height(),
+        fourcc,
+        super::DRM_FORMAT_MOD_LINEAR,
+        stride[0].max(0) as u32,
+        (memory.offset() + offset[0]) as u32,
+    )?;
+    let buffer = buffer.clone();
+    let frame = frame.with_drop_callback(move || drop(buffer));
+    Ok(Some(if fourcc == super::DRM_FORMAT_NV12 {
+        frame.with_color_space(yuv_color_space(info))
+    } else {
+        frame
+    }))
+}
+
+/// The matrix used to convert the buffer's YUV samples to RGB.
+fn yuv_color_space(info: &gst_video::VideoInfo) -> VideoColorSpace {
+    match info.colorimetry().matrix() {
+        gst_video::VideoColorMatrix::Bt709 => VideoColorSpace::Bt709,
+        gst_video::VideoColorMatrix::Bt2020 => VideoColorSpace::Bt2020,
+        _ => VideoColorSpace::Bt601,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PixelFormat;
+
+    #[test]
+    fn test_from_gst_buffer() {
+        gst::init().unwrap();
+        let info = gst_video::VideoInfo::builder(gst_video::VideoFormat::I420, 4, 2)
+            .build()
+            .unwrap();
+        let mut buffer = gst::Buffer::with_size(info.size()).unwrap();
+        buffer
+            .get_mut()
+            .unwrap()
+            .set_pts(gst::ClockTime::from_mseconds(40));
+        let frame = VideoFrame::from_gst_buffer(&buffer, &info).unwrap();
+        assert_eq!(frame.pixel_format(), PixelFormat::I420);
+        assert_eq!(frame.size(), (4, 2));
+        assert_eq!(frame.pts(), Some(Duration::from_millis(40)));
+
+        let info = gst_video::VideoInfo::builder(gst_video::VideoFormat::Gray8, 4, 2)
+            .build()
+            .unwrap();
+        let buffer = gst::Buffer::with_size(info.size()).unwrap();
+        assert_eq!(
+            VideoFrame::from_gst_buffer(&buffer, &info).unwrap_err(),
+            VideoFrameError::UnsupportedSourceFormat("GRAY8".into())
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_dma_buf_frame_keeps_buffer_alive() {
+        use gstreamer_allocators::prelude::*;
+        use std::os::fd::{FromRawFd as _, OwnedFd};
+
+        gst::init().unwrap();
+        let info = gst_video::VideoInfo::builder(gst_video::VideoFormat::Bgra, 2, 2)
+            .build()
+            .unwrap();
+        let raw_fd = unsafe { libc::memfd_create(c"gst-dma-buf-test".as_ptr(), 0) };
+        assert!(raw_fd >= 0);
+        let file = std::fs::File::from(unsafe { OwnedFd::from_raw_fd(raw_fd) });
+        file.set_len(info.size() as u64).unwrap();
+        let memory = unsafe {
+            gstreamer_allocators::DmaBufAllocator::new()
+                .alloc_dmabuf(file, info.size())
+                .unwrap()
+        };
+        let mut buffer = gst::Buffer::new();
+        buffer.get_mut().unwrap().append_memory(memory);
+
+        let frame = VideoFrame::from_gst_buffer(&buffer, &info).unwrap();
+        assert_eq!(frame.backend(), crate::VideoFrameBackend::DmaBuf);
+        let clone = frame.clone();
+        drop(frame);
+        assert!(!buffer.is_writable());
+        drop(clone);
+        assert!(buffer.is_writable());
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+
+use super::VideoFrame;
+
+/// How many frames a [`VideoFrameHistory`] keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HistoryLimit {
+    /// Keep up to this many frames.
+    Frames(usize),
+    /// Keep frames until their total [`VideoFrame::memory_footprint`] exceeds
+    /// this many bytes. The newest frame is kept even if it exceeds the
+    /// budget on its own.
+    Bytes(usize),
+}
+
+/// A bounded history of the most recent frames, such as for scrubbing back
+/// through a screen recording while it's being captured.
+///
+/// Frames are evicted oldest first once the limit is reached. The history
+/// only holds references to the frames it's given, so the buffers of frames
+/// built by a [`super::VideoFramePool`] return to the pool once they've been
+/// evicted and every other clone of them has been dropped.
+pub struct VideoFrameHistory {
+    /// Frames ordered from oldest to newest.
+    frames: VecDeque<VideoFrame>,
+    limit: HistoryLimit,
+    footprint: usize,
+}
+
+impl VideoFrameHistory {
+    /// Create an empty history that keeps frames up to `limit`.
+    pub fn new(limit: HistoryLimit) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            limit,
+            footprint: 0,
+        }
+    }
+
+    /// Add the newest frame, evicting the oldest frames that no longer fit.
+    pub fn push(&mut self, frame: VideoFrame) {
+        self.footprint += frame.memory_footprint();
+        self.frames.push_back(frame);
+        while self.frames.len() > 1 && self.exceeds_limit() {
+            if let Some(evicted) = self.frames.pop_front() {
+                self.footprint -= evicted.memory_footprint();
+            }
+        }
+    }
+
+    /// Get a frame by how many frames ago it was pushed, with `0` being the
+    /// newest frame.
+    pub fn get(&self, index_from_newest: usize) -> Option<&VideoFrame> {
+        let index = self
+            .frames
+            .len()
+            .checked_sub(index_from_newest)?
+            .checked_sub(1)?;
+        self.frames.get(index)
+    }
+
+    /// The number of frames in the history.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Whether the history holds no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The total memory footprint of the frames in the history, in bytes.
+    pub fn memory_footprint(&self) -> usize {
+        self.footprint
+    }
+
+    /// Drop every frame in the history.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.footprint = 0;
+    }
+
+    fn exceeds_limit(&self) -> bool {
+        match self.limit {
+            HistoryLimit::Frames(count) => self.frames.len() > count,
+            HistoryLimit::Bytes(bytes) => self.footprint > bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PixelFormat, VideoFramePool};
+    use std::time::Duration;
+
+    fn frame_at(milliseconds: u64) -> VideoFrame {
+        VideoFrame::from_bgra(vec![0; 4], 1, 1).with_pts(Duration::from_millis(milliseconds))
+    }
+
+    fn pts_at(history: &VideoFrameHistory, index_from_newest: usize) -> Option<u64> {
+        history
+            .get(index_from_newest)
+            .and_then(|frame| frame.pts())
+            .map(|pts| pts.as_millis() as u64)
+    }
+
+    #[test]
+    fn test_count_bounded_history() {
+        let mut history = VideoFrameHistory::new(HistoryLimit::Frames(3));
+        assert!(history.is_empty());
+        for pts in [0, 40, 80, 120] {
+            history.push(frame_at(pts));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(pts_at(&history, 0), Some(120));
+        assert_eq!(pts_at(&history, 2), Some(40));
+        assert_eq!(pts_at(&history, 3), None);
+        assert_eq!(history.memory_footprint(), 12);
+    }
+
+    #[test]
+    fn test_byte_bounded_history() {
+        let mut history = VideoFrameHistory::new(HistoryLimit::Bytes(10));
+        history.push(frame_at(0));
+        history.push(frame_at(40));
+        assert_eq!(history.len(), 2);
+        history.push(frame_at(80));
+        assert_eq!(history.len(), 2);
+        assert_eq!(pts_at(&history, 1), Some(40));
+
+        history.push(VideoFrame::from_bgra(vec![0; 16], 2, 2));
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.memory_footprint(), 16);
+    }
+
+    #[test]
+    fn test_history_recycles_pool_buffers() {
+        let pool = VideoFramePool::new();
+        let mut history = VideoFrameHistory::new(HistoryLimit::Frames(1));
+        for _ in 0..3 {
+            let buffer = pool.acquire(1, 1, PixelFormat::Bgra8).unwrap();
+            history.push(pool.build_frame(buffer, 1, 1).unwrap());
+        }
+        assert_eq!(pool.buffer_count(), 2);
+    }
+}
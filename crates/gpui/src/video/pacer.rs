@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use super::{VideoFrame, VideoFrameError};
+
+/// Reorders decoded frames into presentation order and hands out each one
+/// when it's due, for decoders that emit frames out of PTS order, such as
+/// those decoding B-frames.
+///
+/// Frames are added with [`Self::push`] as they're decoded and taken with
+/// [`Self::pace`] on every paint, passing the current position of the
+/// playback clock in the same time base as the frames' PTS.
+pub struct FramePacer {
+    /// Queued frames, ordered by PTS.
+    frames: Vec<(Duration, VideoFrame)>,
+    capacity: usize,
+    late_threshold: Duration,
+    last_pts: Option<Duration>,
+    dropped_frames: usize,
+}
+
+impl FramePacer {
+    /// Create a pacer that holds up to `capacity` frames for reordering, and
+    /// drops frames once their PTS is more than `late_threshold` behind the
+    /// clock.
+    ///
+    /// `capacity` should be at least the decoder's reorder depth, which is
+    /// the number of frames it may emit ahead of an earlier one.
+    pub fn new(capacity: usize, late_threshold: Duration) -> Self {
+        Self {
+            frames: Vec::with_capacity(capacity),
+            capacity: capacity.max(1),
+            late_threshold,
+            last_pts: None,
+            dropped_frames: 0,
+        }
+    }
+
+    /// Queue a decoded frame, which must have a PTS.
+    ///
+    /// Frames whose PTS is at or before that of a frame already handed out
+    /// can no longer be shown in order and are dropped. When the queue is
+    /// full, the frame with the earliest PTS is dropped to make room.
+    pub fn push(&mut self, frame: VideoFrame) -> Result<(), VideoFrameError> {
+        let pts = frame.pts().ok_or(VideoFrameError::MissingPts)?;
+        if self.last_pts.is_some_and(|last_pts| pts <= last_pts) {
+            self.dropped_frames += 1;
+            return Ok(());
+        }
+        let index = self
+            .frames
+            .partition_point(|(queued_pts, _)| *queued_pts <= pts);
+        self.frames.insert(index, (pts, frame));
+        if self.frames.len() > self.capacity {
+            self.frames.remove(0);
+            self.dropped_frames += 1;
+        }
+        Ok(())
+    }
+
+    /// Take the queued frame with the earliest PTS if it's due at `now`.
+    ///
+    /// Frames more than the late threshold behind `now` are dropped first.
+    /// Returns `None` while the next frame isn't due yet, in which case the
+    /// previously returned frame should stay on screen.
+    pub fn pace(&mut self, now: Duration) -> Option<VideoFrame> {
+        let late = self
+            .frames
+            .partition_point(|(pts, _)| *pts + self.late_threshold < now);
+        if late > 0 {
+            self.last_pts = Some(self.frames[late - 1].0);
+            self.frames.drain(..late);
+            self.dropped_frames += late;
+        }
+        let (pts, _) = self.frames.first()?;
+        if *pts > now {
+            return None;
+        }
+        let (pts, frame) = self.frames.remove(0);
+        self.last_pts = Some(pts);
+        Some(frame)
+    }
+
+    /// The number of frames waiting to be presented.
+    pub fn queued_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The number of frames dropped so far for arriving or being presented
+    /// too late, or for overflowing the queue.
+    pub fn dropped_frames(&self) -> usize {
+        self.dropped_frames
+    }
+
+    /// Drop every queued frame and forget the last presented PTS, such as
+    /// after seeking.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.last_pts = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_at(milliseconds: u64) -> VideoFrame {
+        VideoFrame::from_bgra(vec![0; 4], 1, 1).with_pts(Duration::from_millis(milliseconds))
+    }
+
+    fn pace_at(pacer: &mut FramePacer, milliseconds: u64) -> Option<u64> {
+        pacer
+            .pace(Duration::from_millis(milliseconds))
+            .and_then(|frame| frame.pts())
+            .map(|pts| pts.as_millis() as u64)
+    }
+
+    #[test]
+    fn test_frame_pacer() {
+        let mut pacer = FramePacer::new(4, Duration::from_millis(50));
+        for pts in [0, 120, 40, 80] {
+            pacer.push(frame_at(pts)).unwrap();
+        }
+        assert_eq!(pace_at(&mut pacer, 0), Some(0));
+        assert_eq!(pace_at(&mut pacer, 30), None);
+        assert_eq!(pace_at(&mut pacer, 45), Some(40));
+        assert_eq!(pace_at(&mut pacer, 90), Some(80));
+        assert_eq!(pacer.queued_frames(), 1);
+
+        pacer.push(frame_at(60)).unwrap();
+        assert_eq!(pacer.queued_frames(), 1);
+        assert_eq!(pacer.dropped_frames(), 1);
+
+        pacer.push(frame_at(160)).unwrap();
+        assert_eq!(pace_at(&mut pacer, 200), Some(160));
+        assert_eq!(pacer.dropped_frames(), 2);
+        assert_eq!(pace_at(&mut pacer, 240), None);
+
+        assert_eq!(
+            pacer.push(VideoFrame::from_bgra(vec![0; 4], 1, 1)),
+            Err(VideoFrameError::MissingPts)
+        );
+    }
+}
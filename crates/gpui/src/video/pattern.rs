@@ -0,0 +1,119 @@
+use super::VideoFrame;
+
+/// The seven 75% intensity bars of the SMPTE color bar pattern, from left to
+/// right, as BGRA pixels.
+const COLOR_BARS: [[u8; 4]; 7] = [
+    [191, 191, 191, 255],
+    [0, 191, 191, 255],
+    [191, 191, 0, 255],
+    [0, 191, 0, 255],
+    [191, 0, 191, 255],
+    [0, 0, 191, 255],
+    [191, 0, 0, 255],
+];
+
+impl VideoFrame {
+    /// Create a BGRA frame filled with a single `[b, g, r, a]` color.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is zero.
+    pub fn solid_color(width: u32, height: u32, color: [u8; 4]) -> Self {
+        Self::from_pattern(width, height, |_, _| color)
+    }
+
+    /// Create a BGRA frame with a checkerboard of `tile` by `tile` squares,
+    /// alternating between the `[b, g, r, a]` colors `a` and `b`, starting with
+    /// `a` in the top left corner.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension or `tile` is zero.
+    pub fn checkerboard(width: u32, height: u32, tile: u32, a: [u8; 4], b: [u8; 4]) -> Self {
+        assert!(tile > 0, "checkerboard tile size must be non-zero");
+        Self::from_pattern(width, height, |column, row| {
+            if (column / tile + row / tile).is_multiple_of(2) {
+                a
+            } else {
+                b
+            }
+        })
+    }
+
+    /// Create a BGRA frame of SMPTE-style vertical color bars: white, yellow,
+    /// cyan, green, magenta, red and blue at 75% intensity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is zero.
+    pub fn color_bars(width: u32, height: u32) -> Self {
+        Self::from_pattern(width, height, |column, _| {
+            COLOR_BARS[(column as u64 * COLOR_BARS.len() as u64 / width as u64) as usize]
+        })
+    }
+
+    fn from_pattern(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> Self {
+        let mut buffer = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in 0..height {
+            for column in 0..width {
+                buffer.extend_from_slice(&pixel(column, row));
+            }
+        }
+        Self::from_bgra(buffer, width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PixelFormat;
+
+    fn pixel(frame: &VideoFrame, column: u32, row: u32) -> &[u8] {
+        let offset = (row * frame.bytes_per_row() + column * 4) as usize;
+        &frame.as_bytes().unwrap()[offset..offset + 4]
+    }
+
+    #[test]
+    fn test_solid_color() {
+        let frame = VideoFrame::solid_color(3, 2, [1, 2, 3, 4]);
+        assert_eq!(frame.size(), (3, 2));
+        assert_eq!(frame.pixel_format(), PixelFormat::Bgra8);
+        assert!(
+            frame
+                .as_bytes()
+                .unwrap()
+                .chunks(4)
+                .all(|color| color == [1, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let a = [0, 0, 0, 255];
+        let b = [255, 255, 255, 255];
+        let frame = VideoFrame::checkerboard(5, 4, 2, a, b);
+        assert_eq!(pixel(&frame, 0, 0), a);
+        assert_eq!(pixel(&frame, 1, 1), a);
+        assert_eq!(pixel(&frame, 2, 0), b);
+        assert_eq!(pixel(&frame, 0, 2), b);
+        assert_eq!(pixel(&frame, 2, 2), a);
+        assert_eq!(pixel(&frame, 4, 3), b);
+
+        let flipped = frame.flip_horizontal().unwrap();
+        assert_eq!(pixel(&flipped, 0, 0), a);
+        assert_eq!(pixel(&flipped, 1, 0), b);
+    }
+
+    #[test]
+    fn test_color_bars() {
+        let frame = VideoFrame::color_bars(14, 1);
+        for (bar, color) in COLOR_BARS.iter().enumerate() {
+            assert_eq!(pixel(&frame, bar as u32 * 2, 0), color);
+            assert_eq!(pixel(&frame, bar as u32 * 2 + 1, 0), color);
+        }
+
+        let narrow = VideoFrame::color_bars(3, 1);
+        assert_eq!(pixel(&narrow, 0, 0), COLOR_BARS[0]);
+        assert_eq!(pixel(&narrow, 2, 0), COLOR_BARS[4]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+
+use super::{
+    PixelFormat, VideoFrame, VideoFrameError, VideoStat, record_stat, row_size, validate_dimensions,
+};
+
+/// Recycles the pixel buffers of packed BGRA and RGBA frames, so that
+/// producing a frame per vsync doesn't allocate a fresh buffer every time.
+///
+/// Buffers handed out by [`Self::acquire`] are filled by the caller and turned
+/// into frames with [`Self::build_frame`]. The pool keeps a reference to the
+/// buffer of every frame it builds, and reuses a buffer once every other
+/// reference to it, including clones of the frame and any copies held by the
+/// renderer, has been dropped.
+#[derive(Default)]
+pub struct VideoFramePool {
+    buffers: Mutex<Vec<Arc<Vec<u8>>>>,
+}
+
+/// A buffer obtained from a [`VideoFramePool`], sized for one packed frame.
+pub struct PooledBuffer {
+    bytes: Vec<u8>,
+    format: PixelFormat,
+}
+
+impl VideoFramePool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a zeroed buffer of `width * height * 4` bytes for a frame in the
+    /// given format, reusing a buffer from a dropped frame when one is free.
+    ///
+    /// Only [`PixelFormat::Bgra8`] and [`PixelFormat::Rgba8`] are supported.
+    pub fn acquire(
+        &self,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> Result<PooledBuffer, VideoFrameError> {
+        if !matches!(format, PixelFormat::Bgra8 | PixelFormat::Rgba8) {
+            return Err(VideoFrameError::UnsupportedFormat(format));
+        }
+        validate_dimensions(width, height)?;
+        let len = row_size(width, 4)? as usize * height as usize;
+
+        let mut bytes = match self.take_free_buffer(len) {
+            Some(bytes) => {
+                record_stat(VideoStat::PoolHit);
+                bytes
+            }
+            None => {
+                record_stat(VideoStat::PoolMiss);
+                Vec::new()
+            }
+        };
+        bytes.clear();
+        bytes.resize(len, 0);
+        Ok(PooledBuffer { bytes, format })
+    }
+
+    /// Turn a filled buffer into a frame. The buffer returns to the pool once
+    /// the frame and all of its clones have been dropped.
+    pub fn build_frame(
+        &self,
+        buffer: PooledBuffer,
+        width: u32,
+        height: u32,
+    ) -> Result<VideoFrame, VideoFrameError> {
+        let frame = VideoFrame::from_raw(buffer.bytes, width, height, buffer.format)?;
+        if let Some((buffer, _, _)) = frame.packed_buffer() {
+            self.buffers.lock().push(buffer.clone());
+        }
+        Ok(frame)
+    }
+
+    /// The number of buffers owned by the pool, whether in use or free.
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.lock().len()
+    }
+
+    /// Remove a buffer that no frame references anymore, preferring one that
+    /// can hold `len` bytes without reallocating.
+    fn take_free_buffer(&self, len: usize) -> Option<Vec<u8>> {
+        let mut buffers = self.buffers.lock();
+        let mut free = buffers
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| Arc::strong_count(buffer) == 1 && Arc::weak_count(buffer) == 0)
+            .map(|(index, buffer)| (index, buffer.capacity()));
+        let index = free
+            .clone()
+            .find(|(_, capacity)| *capacity >= len)
+            .or_else(|| free.next())
+            .map(|(index, _)| index)?;
+        Arc::try_unwrap(buffers.swap_remove(index)).ok()
+    }
+}
+
+impl PooledBuffer {
+    /// The pixel format the buffer was acquired for.
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_reuses_buffers() {
+        let pool = VideoFramePool::new();
+
+        let mut buffer = pool.acquire(2, 2, PixelFormat::Bgra8).unwrap();
+        buffer.fill(7);
+        let frame = pool.build_frame(buffer, 2, 2).unwrap();
+        let first_allocation = frame.as_bytes().unwrap().as_ptr();
+        assert_eq!(frame.as_bytes(), Some([7; 16].as_slice()));
+
+        // The first buffer is still referenced, so a second one is allocated.
+        let clone = frame.clone();
+        drop(frame);
+        let second = pool
+            .build_frame(pool.acquire(2, 2, PixelFormat::Bgra8).unwrap(), 2, 2)
+            .unwrap();
+        assert_ne!(second.as_bytes().unwrap().as_ptr(), first_allocation);
+        assert_eq!(pool.buffer_count(), 2);
+
+        drop(clone);
+        for _ in 0..3 {
+            let buffer = pool.acquire(2, 2, PixelFormat::Rgba8).unwrap();
+            assert_eq!(&*buffer, &[0; 16]);
+            let frame = pool.build_frame(buffer, 2, 2).unwrap();
+            assert_eq!(frame.pixel_format(), PixelFormat::Rgba8);
+            assert_eq!(frame.as_bytes().unwrap().as_ptr(), first_allocation);
+        }
+        assert_eq!(pool.buffer_count(), 2);
+
+        assert_eq!(
+            pool.acquire(2, 2, PixelFormat::Nv12).err(),
+            Some(VideoFrameError::UnsupportedFormat(PixelFormat::Nv12))
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use super::{
+    AlphaMode, ColorRange, PixelFormat, Rotation, VideoColorSpace, VideoFrame, VideoFrameData,
+};
+use collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{fmt, time::Duration};
+
+/// The wire representation of a [`VideoFrame`].
+///
+/// Pixels use the tightly packed layout accepted by [`VideoFrame::from_raw`],
+/// except that [`PixelFormat::Rgb8`] frames stay RGB.
+#[derive(Serialize, Deserialize)]
+struct SerializedVideoFrame {
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    pixels: PixelBytes,
+    pts: Option<Duration>,
+    duration: Option<Duration>,
+    color_space: VideoColorSpace,
+    #[serde(default)]
+    color_range: ColorRange,
+    alpha_mode: AlphaMode,
+    #[serde(default)]
+    display_rotation: Rotation,
+    #[serde(default)]
+    metadata: Option<HashMap<String, Vec<u8>>>,
+}
+
+/// Pixel data, serialized as a byte string rather than a sequence of numbers.
+struct PixelBytes(Vec<u8>);
+
+/// The most bytes reserved up front for pixels deserialized as a sequence.
+/// The length the input claims can't be trusted, so the buffer grows as the
+/// bytes actually arrive beyond this.
+const MAX_PREALLOCATED_PIXEL_BYTES: usize = 4096;
+
+impl Serialize for PixelBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for PixelBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PixelBytesVisitor;
+
+        impl<'de> de::Visitor<'de> for PixelBytesVisitor {
+            type Value = PixelBytes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a byte buffer")
+            }
+
+            fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<PixelBytes, E> {
+                Ok(PixelBytes(bytes.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<PixelBytes, E> {
+                Ok(PixelBytes(bytes))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<PixelBytes, A::Error> {
+                let mut bytes = Vec::with_capacity(
+                    seq.size_hint()
+                        .unwrap_or(0)
+                        .min(MAX_PREALLOCATED_PIXEL_BYTES),
+                );
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(PixelBytes(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(PixelBytesVisitor)
+    }
+}
+
+impl VideoFrame {
+    /// Copy the visible pixels of this CPU frame into the layout expected by
+    /// [`Self::from_raw`], reading hardware frames back as BGRA first.
+    fn packed_bytes(&self) -> (PixelFormat, Vec<u8>) {
+        let Some(planes) = self.cpu_planes() else {
+            return self.to_bgra().packed_bytes();
+        };
+        let format = self.pixel_format();
+        let mut bytes = Vec::new();
+        for plane in planes {
+            for row in plane.rows() {
+                if let VideoFrameData::P010 { .. } = self.data {
+                    for sample in row.chunks_exact(2) {
+                        let sample = u16::from_ne_bytes([sample[0], sample[1]]);
+                        bytes.extend_from_slice(&sample.to_le_bytes());
+                    }
+                } else {
+                    bytes.extend_from_slice(row);
+                }
+            }
+        }
+        (format, bytes)
+    }
+}
+
+/// Frames are serialized with their size, pixel format, packed pixels,
+/// timing and color metadata, and any attached metadata. Hardware-backed frames are read back to BGRA
+/// first, so only CPU frames come out of deserialization.
+impl Serialize for VideoFrame {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (format, pixels) = self.packed_bytes();
+        SerializedVideoFrame {
+            width: self.width,
+            height: self.height,
+            format,
+            pixels: PixelBytes(pixels),
+            pts: self.pts,
+            duration: self.duration,
+            color_space: self.color_space,
+            color_range: self.color_range,
+            alpha_mode: self.alpha_mode,
+            display_rotation: self.display_rotation,
+            metadata: self.metadata().cloned(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for VideoFrame {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let frame = SerializedVideoFrame::deserialize(deserializer)?;
+        let PixelBytes(pixels) = frame.pixels;
+        let mut result = match frame.format {
+            PixelFormat::Rgb8 => VideoFrame::try_from_rgb8(pixels, frame.width, frame.height),
+            format => VideoFrame::from_raw(pixels, frame.width, frame.height, format),
+        }
+        .map_err(<D::Error as de::Error>::custom)?
+        .with_color_space(frame.color_space)
+        .with_color_range(frame.color_range)
+        .with_alpha_mode(frame.alpha_mode)
+        .with_display_rotation(frame.display_rotation);
+        result.pts = frame.pts;
+        result.duration = frame.duration;
+        if let Some(metadata) = frame.metadata {
+            result = result.with_metadata(metadata);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_round_trip() {
+        let frame = VideoFrame::from_bgra_with_stride(
+            [[1, 2, 3, 4], [5, 6, 7, 8], [0, 0, 0, 0]]
+                .concat()
+                .repeat(2),
+            2,
+            2,
+            12,
+        )
+        .with_pts(Duration::from_millis(40))
+        .with_alpha_mode(AlphaMode::Premultiplied);
+        let json = serde_json::to_string(&frame).unwrap();
+        let decoded: VideoFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.size(), frame.size());
+        assert_eq!(decoded.bytes_per_row(), 8);
+        assert_eq!(
+            decoded.as_bytes().unwrap(),
+            [1, 2, 3, 4, 5, 6, 7, 8].repeat(2)
+        );
+        assert_eq!(decoded.pts(), Some(Duration::from_millis(40)));
+        assert_eq!(decoded.duration(), None);
+        assert_eq!(decoded.alpha_mode(), AlphaMode::Premultiplied);
+        assert_eq!(decoded.color_space(), VideoColorSpace::Srgb);
+
+        let nv12 = VideoFrame::from_raw(vec![16; 6], 2, 2, PixelFormat::Nv12)
+            .unwrap()
+            .with_color_space(VideoColorSpace::Bt709);
+        let decoded: VideoFrame =
+            serde_json::from_str(&serde_json::to_string(&nv12).unwrap()).unwrap();
+        assert_eq!(decoded.pixel_format(), PixelFormat::Nv12);
+        assert_eq!(decoded.color_space(), VideoColorSpace::Bt709);
+        assert_eq!(decoded.content_hash(), nv12.content_hash());
+
+        let json = json.replace("\"width\":2", "\"width\":3");
+        assert!(serde_json::from_str::<VideoFrame>(&json).is_err());
+    }
+}
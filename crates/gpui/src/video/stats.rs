@@ -0,0 +1,95 @@
+#[cfg(feature = "video-stats")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counts of the work done on video frames since the process started, such
+/// as for confirming that a zero-copy pipeline doesn't copy frames after all.
+///
+/// The counters are only kept when the `video-stats` feature is enabled, and
+/// cost nothing otherwise.
+#[cfg(feature = "video-stats")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VideoStats {
+    /// Frames whose pixels were converted or repacked on the CPU.
+    pub cpu_conversions: u64,
+    /// Hardware-backed frames whose pixels were read back to the CPU.
+    pub gpu_readbacks: u64,
+    /// Frames uploaded to a texture, in full or in part, by a renderer.
+    pub texture_uploads: u64,
+    /// Buffers a [`super::VideoFramePool`] handed out by reusing a free one.
+    pub pool_hits: u64,
+    /// Buffers a [`super::VideoFramePool`] had to allocate.
+    pub pool_misses: u64,
+}
+
+#[cfg(feature = "video-stats")]
+impl VideoStats {
+    /// Read the current value of every counter.
+    ///
+    /// The counters are shared by the whole process, so compare two
+    /// snapshots to measure the work done in between.
+    pub fn snapshot() -> Self {
+        let load = |stat: VideoStat| COUNTERS[stat as usize].load(Ordering::Relaxed);
+        Self {
+            cpu_conversions: load(VideoStat::CpuConversion),
+            gpu_readbacks: load(VideoStat::GpuReadback),
+            texture_uploads: load(VideoStat::TextureUpload),
+            pool_hits: load(VideoStat::PoolHit),
+            pool_misses: load(VideoStat::PoolMiss),
+        }
+    }
+}
+
+/// A kind of work counted by [`VideoStats`].
+#[derive(Clone, Copy)]
+pub(crate) enum VideoStat {
+    CpuConversion,
+    GpuReadback,
+    #[cfg_attr(
+        all(target_os = "macos", not(feature = "macos-blade")),
+        allow(dead_code)
+    )]
+    TextureUpload,
+    PoolHit,
+    PoolMiss,
+}
+
+#[cfg(feature = "video-stats")]
+static COUNTERS: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+
+/// Count one occurrence of `stat`, if the `video-stats` feature is enabled.
+#[inline]
+#[cfg_attr(not(feature = "video-stats"), allow(unused_variables))]
+pub(crate) fn record_stat(stat: VideoStat) {
+    #[cfg(feature = "video-stats")]
+    COUNTERS[stat as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(all(test, feature = "video-stats", target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::video::{DRM_FORMAT_MOD_LINEAR, DRM_FORMAT_XRGB8888, VideoFrame};
+    use std::os::fd::{FromRawFd as _, OwnedFd};
+
+    #[test]
+    fn test_readback_is_counted() {
+        let raw_fd = unsafe { libc::memfd_create(c"video-stats-test".as_ptr(), 0) };
+        assert!(raw_fd >= 0);
+        let file = std::fs::File::from(unsafe { OwnedFd::from_raw_fd(raw_fd) });
+        file.set_len(4).unwrap();
+        let frame = VideoFrame::from_dma_buf(
+            file.into(),
+            1,
+            1,
+            DRM_FORMAT_XRGB8888,
+            DRM_FORMAT_MOD_LINEAR,
+            4,
+            0,
+        )
+        .unwrap();
+
+        let before = VideoStats::snapshot();
+        frame.to_bgra();
+        let after = VideoStats::snapshot();
+        assert!(after.gpu_readbacks > before.gpu_readbacks);
+    }
+}
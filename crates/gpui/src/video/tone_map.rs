@@ -0,0 +1,492 @@
+use super::{
+    ColorRange, PixelFormat, VideoColorSpace, VideoFrame, VideoFrameData, VideoFrameError,
+    Yuv420Planes,
+};
+
+type Matrix = [[f32; 3]; 3];
+
+/// The curve used by [`VideoFrame::tone_map_to_sdr`] to compress HDR
+/// highlights into the range of an SDR display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ToneMap {
+    /// `x / (1 + x)`. Never clips, but dims midtones noticeably.
+    Reinhard,
+    /// John Hable's filmic curve, which keeps midtones closer to their SDR
+    /// brightness and rolls highlights off smoothly.
+    #[default]
+    Hable,
+}
+
+/// How samples with more than 8 bits are rounded when they're reduced to 8
+/// bits, as in [`VideoFrame::tone_map_to_sdr`] and
+/// [`VideoFrame::to_bgra_with_dither`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Dither {
+    /// Round every sample to the nearest 8-bit value, which can leave visible
+    /// bands in smooth gradients.
+    #[default]
+    None,
+    /// Offset every sample by the threshold of an 8×8 Bayer matrix at its
+    /// position before rounding, trading bands for a fine, fixed pattern. The
+    /// pattern is the same for every frame, so results are deterministic.
+    Ordered,
+}
+
+/// The thresholds of an ordered dither, from 0 to 63.
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+impl Dither {
+    /// Round `value`, on the 0–255 scale, to an 8-bit sample at the given
+    /// position.
+    fn quantize(self, value: f32, column: usize, row: usize) -> u8 {
+        let offset = match self {
+            Dither::None => 0.0,
+            Dither::Ordered => (BAYER_8X8[row % 8][column % 8] as f32 + 0.5) / 64.0 - 0.5,
+        };
+        (value + offset).round().clamp(0.0, 255.0) as u8
+    }
+}
+
+/// The luminance of SDR reference white, in nits, per ITU-R BT.2408.
+const SDR_WHITE_NITS: f32 = 203.0;
+
+/// The luminance that a full-scale PQ signal encodes, in nits.
+const PQ_PEAK_NITS: f32 = 10_000.0;
+
+/// Converts linear BT.2020 RGB to linear BT.709 RGB, per ITU-R BT.2087.
+const BT2020_TO_BT709: Matrix = [
+    [1.6605, -0.5876, -0.0728],
+    [-0.1246, 1.1329, -0.0083],
+    [-0.0182, -0.1006, 1.1187],
+];
+
+impl ToneMap {
+    /// Map a linear value relative to SDR white to the displayable 0–1 range.
+    fn apply(self, value: f32) -> f32 {
+        match self {
+            ToneMap::Reinhard => value / (1.0 + value),
+            ToneMap::Hable => {
+                const WHITE: f32 = 11.2;
+                const EXPOSURE_BIAS: f32 = 2.0;
+                fn curve(x: f32) -> f32 {
+                    const A: f32 = 0.15;
+                    const B: f32 = 0.50;
+                    const C: f32 = 0.10;
+                    const D: f32 = 0.20;
+                    const E: f32 = 0.02;
+                    const F: f32 = 0.30;
+                    (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+                }
+                curve(value * EXPOSURE_BIAS) / curve(WHITE)
+            }
+        }
+    }
+}
+
+impl VideoFrame {
+    /// Convert an HDR frame to an 8-bit sRGB BGRA frame for displays and
+    /// screenshots that can't show HDR.
+    ///
+    /// Frames with more than 8 bits per sample in [`VideoColorSpace::Bt2020`] are
+    /// treated as PQ-encoded HDR: their samples are decoded to absolute
+    /// luminance, scaled so that 203 nits becomes SDR white, mapped from the
+    /// BT.2020 gamut to BT.709, compressed with `method`, and encoded as sRGB
+    /// with 8 bits per channel, rounded as `dither` says. Chroma is upsampled
+    /// from the nearest sample, as in [`Self::to_bgra`].
+    ///
+    /// Other frames are converted with [`Self::to_bgra_with_dither`].
+    /// Hardware-backed frames aren't supported.
+    pub fn tone_map_to_sdr(
+        &self,
+        method: ToneMap,
+        dither: Dither,
+    ) -> Result<VideoFrame, VideoFrameError> {
+        if self.cpu_planes().is_none() {
+            return Err(VideoFrameError::UnsupportedForHardwareFrame);
+        }
+        let VideoFrameData::P010 {
+            y_plane,
+            uv_plane,
+            y_stride,
+            uv_stride,
+        } = &self.data
+        else {
+            return Ok(self.to_bgra_with_dither(dither));
+        };
+        if self.bit_depth() <= 8 || self.color_space != VideoColorSpace::Bt2020 {
+            return Ok(self.to_bgra_with_dither(dither));
+        }
+
+        let (kr, kb) = self.color_space.luma_coefficients();
+        let kg = 1.0 - kr - kb;
+        let (cr_to_r, cb_to_b) = (2.0 * (1.0 - kr), 2.0 * (1.0 - kb));
+        let (cb_to_g, cr_to_g) = (cb_to_b * kb / kg, cr_to_r * kr / kg);
+        let scale = (1u32 << (self.bit_depth() - 8)) as f32;
+        let (luma_offset, luma_range, chroma_range) = match self.color_range {
+            ColorRange::Limited => (16.0 * scale, 219.0 * scale, 224.0 * scale),
+            ColorRange::Full => {
+                let max_sample = ((1u32 << self.bit_depth()) - 1) as f32;
+                (0.0, max_sample, max_sample)
+            }
+        };
+        let sample = |sample: u16| (sample >> (16 - self.bit_depth())) as f32;
+        let normalize_luma = |luma: u16| (sample(luma) - luma_offset) / luma_range;
+        let normalize_chroma = |chroma: u16| (sample(chroma) - 128.0 * scale) / chroma_range;
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let (y_stride, uv_stride) = (*y_stride as usize, *uv_stride as usize);
+        let mut bgra = Vec::with_capacity(width * height * 4);
+        for row in 0..height {
+            let luma_row = &y_plane[row * y_stride..][..width];
+            let chroma_row = &uv_plane[row / 2 * uv_stride..];
+            for (column, &luma) in luma_row.iter().enumerate() {
+                let luma = normalize_luma(luma);
+                let cb = normalize_chroma(chroma_row[column / 2 * 2]);
+                let cr = normalize_chroma(chroma_row[column / 2 * 2 + 1]);
+                let encoded = [
+                    luma + cr_to_r * cr,
+                    luma - cb_to_g * cb - cr_to_g * cr,
+                    luma + cb_to_b * cb,
+                ];
+                let linear = encoded.map(|channel| pq_to_nits(channel) / SDR_WHITE_NITS);
+                let [red, green, blue] = BT2020_TO_BT709.map(|coefficients| {
+                    let value = coefficients[0] * linear[0]
+                        + coefficients[1] * linear[1]
+                        + coefficients[2] * linear[2];
+                    let encoded = encode_srgb(method.apply(value.max(0.0)));
+                    dither.quantize(encoded * 255.0, column, row)
+                });
+                bgra.extend_from_slice(&[blue, green, red, 255]);
+            }
+        }
+        Ok(self
+            .with_packed_buffer(bgra, self.width, self.height, PixelFormat::Bgra8)
+            .with_color_space(VideoColorSpace::Srgb))
+    }
+
+    /// Convert this frame into a CPU-backed BGRA frame as in
+    /// [`Self::to_bgra`], rounding the samples of P010 and 16-bit RGBA frames
+    /// to 8 bits as `dither` says, to avoid banding in smooth gradients.
+    ///
+    /// Frames with 8 bits per sample and hardware-backed frames are converted
+    /// with [`Self::to_bgra`], since they have no extra precision to dither.
+    pub fn to_bgra_with_dither(&self, dither: Dither) -> VideoFrame {
+        let bgra = match &self.data {
+            VideoFrameData::P010 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } if dither != Dither::None => {
+                let reduce = |samples: &[u16], stride: u32, samples_per_pixel: usize| {
+                    let stride = (stride as usize).max(1);
+                    samples
+                        .iter()
+                        .enumerate()
+                        .map(|(index, sample)| {
+                            let (row, column) = (index / stride, index % stride);
+                            dither.quantize(*sample as f32 / 256.0, column / samples_per_pixel, row)
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let y_plane = reduce(y_plane, *y_stride, 1);
+                let uv_plane = reduce(uv_plane, *uv_stride, 2);
+                Yuv420Planes::nv12(&y_plane, *y_stride, &uv_plane, *uv_stride).to_bgra(
+                    self.width,
+                    self.height,
+                    self.yuv_to_rgb(),
+                )
+            }
+            VideoFrameData::Rgba16 { buffer, float } if dither != Dither::None => {
+                let width = self.width as usize;
+                let mut bgra = Vec::with_capacity(buffer.len() / 2);
+                for (index, pixel) in buffer.chunks_exact(8).enumerate() {
+                    let (row, column) = (index / width, index % width);
+                    let [red, green, blue, alpha] = std::array::from_fn(|channel| {
+                        let value =
+                            u16::from_le_bytes([pixel[channel * 2], pixel[channel * 2 + 1]]);
+                        let value = if *float {
+                            super::f16_to_f32(value).clamp(0.0, 1.0) * 255.0
+                        } else {
+                            value as f32 / 257.0
+                        };
+                        dither.quantize(value, column, row)
+                    });
+                    bgra.extend_from_slice(&[blue, green, red, alpha]);
+                }
+                bgra
+            }
+            _ => return self.to_bgra(),
+        };
+        self.with_packed_buffer(bgra, self.width, self.height, PixelFormat::Bgra8)
+            .with_color_space(VideoColorSpace::Srgb)
+    }
+
+    /// Convert the colors of a BGRA or RGBA frame from the gamut of its color
+    /// space to that of `target`, so that frames from different sources can
+    /// be composited onto one canvas without their colors drifting.
+    ///
+    /// Samples are decoded with the sRGB transfer function, converted through
+    /// CIE XYZ in linear light, and encoded again. Colors outside the target
+    /// gamut are clipped, and alpha is kept as is. Frames already in `target`
+    /// are returned unchanged; [`VideoColorSpace::Srgb`] and [`VideoColorSpace::Bt709`]
+    /// share primaries, so converting between them only relabels the frame.
+    pub fn convert_color_space(
+        &self,
+        target: VideoColorSpace,
+    ) -> Result<VideoFrame, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        if self.color_space == target {
+            return Ok(self.clone());
+        }
+        let matrix = multiply(invert(target.rgb_to_xyz()), self.color_space.rgb_to_xyz());
+        let (red, blue) = match format {
+            PixelFormat::Rgba8 => (0, 2),
+            _ => (2, 0),
+        };
+        let decode: [f32; 256] = std::array::from_fn(|sample| srgb_to_linear(sample as u8));
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut converted = Vec::with_capacity(width * height * 4);
+        for row in pixels.chunks(stride).take(height) {
+            for pixel in row[..width * 4].chunks_exact(4) {
+                let linear = [
+                    decode[pixel[red] as usize],
+                    decode[pixel[1] as usize],
+                    decode[pixel[blue] as usize],
+                ];
+                let [r, g, b] = matrix.map(|coefficients| {
+                    linear_to_srgb(
+                        coefficients[0] * linear[0]
+                            + coefficients[1] * linear[1]
+                            + coefficients[2] * linear[2],
+                    )
+                });
+                let mut output = [0, g, 0, pixel[3]];
+                output[red] = r;
+                output[blue] = b;
+                converted.extend_from_slice(&output);
+            }
+        }
+        Ok(self
+            .with_packed_buffer(converted, self.width, self.height, format)
+            .with_color_space(target))
+    }
+}
+
+/// Multiply two 3x3 matrices.
+fn multiply(left: Matrix, right: Matrix) -> Matrix {
+    std::array::from_fn(|row| {
+        std::array::from_fn(|column| (0..3).map(|k| left[row][k] * right[k][column]).sum())
+    })
+}
+
+/// Invert a 3x3 matrix through its adjugate. The matrices inverted here are
+/// primaries-to-XYZ conversions, which are never singular.
+fn invert(matrix: Matrix) -> Matrix {
+    let cofactor = |row: usize, column: usize| {
+        let (top, bottom) = ((row + 1) % 3, (row + 2) % 3);
+        let (left, right) = ((column + 1) % 3, (column + 2) % 3);
+        matrix[top][left] * matrix[bottom][right] - matrix[top][right] * matrix[bottom][left]
+    };
+    let determinant = (0..3)
+        .map(|column| matrix[0][column] * cofactor(0, column))
+        .sum::<f32>();
+    std::array::from_fn(|row| std::array::from_fn(|column| cofactor(column, row) / determinant))
+}
+
+/// Decode a PQ (SMPTE ST 2084) signal in 0–1 to absolute luminance in nits.
+fn pq_to_nits(signal: f32) -> f32 {
+    const M1: f32 = 2610.0 / 16384.0;
+    const M2: f32 = 2523.0 / 4096.0 * 128.0;
+    const C1: f32 = 3424.0 / 4096.0;
+    const C2: f32 = 2413.0 / 4096.0 * 32.0;
+    const C3: f32 = 2392.0 / 4096.0 * 32.0;
+    let power = signal.clamp(0.0, 1.0).powf(1.0 / M2);
+    let linear = ((power - C1).max(0.0) / (C2 - C3 * power)).powf(1.0 / M1);
+    linear * PQ_PEAK_NITS
+}
+
+/// Decode an sRGB-encoded sample to a linear value in 0–1.
+fn srgb_to_linear(sample: u8) -> f32 {
+    let value = sample as f32 / 255.0;
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a linear value in 0–1 with the sRGB transfer function.
+fn linear_to_srgb(value: f32) -> u8 {
+    (encode_srgb(value) * 255.0).round() as u8
+}
+
+/// Encode a linear value in 0–1 with the sRGB transfer function, keeping the
+/// result in 0–1.
+fn encode_srgb(value: f32) -> f32 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x2 P010 frame of a single neutral color with the given 10-bit luma.
+    fn neutral_hdr_frame(luma: u16) -> VideoFrame {
+        VideoFrame::from_p010(
+            vec![luma << 6; 4],
+            vec![512 << 6; 2],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt2020,
+        )
+    }
+
+    #[test]
+    fn test_tone_map_to_sdr() {
+        for method in [ToneMap::Reinhard, ToneMap::Hable] {
+            let peak = neutral_hdr_frame(1023)
+                .tone_map_to_sdr(method, Dither::None)
+                .unwrap();
+            assert_eq!(peak.pixel_format(), PixelFormat::Bgra8);
+            assert_eq!(peak.color_space(), VideoColorSpace::Srgb);
+            let pixel = &peak.as_bytes().unwrap()[..4];
+            assert!(
+                pixel[..3].iter().all(|channel| *channel >= 250),
+                "{pixel:?}"
+            );
+            assert_eq!(pixel[0], pixel[2]);
+
+            let black = neutral_hdr_frame(0)
+                .tone_map_to_sdr(method, Dither::None)
+                .unwrap();
+            assert_eq!(&black.as_bytes().unwrap()[..4], [0, 0, 0, 255]);
+
+            let sdr_white = neutral_hdr_frame(593)
+                .tone_map_to_sdr(method, Dither::None)
+                .unwrap();
+            let pixel = &sdr_white.as_bytes().unwrap()[..4];
+            assert!((150..250).contains(&pixel[1]), "{pixel:?}");
+        }
+
+        let sdr = VideoFrame::from_rgba(vec![1, 2, 3, 4], 1, 1);
+        assert_eq!(
+            sdr.tone_map_to_sdr(ToneMap::Hable, Dither::None).unwrap(),
+            sdr.to_bgra()
+        );
+    }
+
+    #[test]
+    fn test_convert_color_space() {
+        let red = VideoFrame::from_bgra(vec![0, 0, 255, 255], 1, 1)
+            .with_color_space(VideoColorSpace::Bt2020);
+        let converted = red.convert_color_space(VideoColorSpace::Bt709).unwrap();
+        assert_eq!(converted.color_space(), VideoColorSpace::Bt709);
+        assert_eq!(converted.as_bytes().unwrap(), [0, 0, 255, 255]);
+
+        let red = VideoFrame::from_rgba(vec![255, 0, 0, 128], 1, 1)
+            .with_color_space(VideoColorSpace::Bt709);
+        let converted = red.convert_color_space(VideoColorSpace::Bt2020).unwrap();
+        assert_eq!(converted.pixel_format(), PixelFormat::Rgba8);
+        let pixel = converted.as_bytes().unwrap();
+        for (channel, expected) in pixel.iter().zip([207, 74, 34, 128]) {
+            assert!(channel.abs_diff(expected) <= 1, "{pixel:?}");
+        }
+        let round_trip = converted
+            .convert_color_space(VideoColorSpace::Bt709)
+            .unwrap();
+        for (channel, expected) in round_trip.as_bytes().unwrap().iter().zip([255, 0, 0, 128]) {
+            assert!(
+                channel.abs_diff(expected) <= 2,
+                "{:?}",
+                round_trip.as_bytes()
+            );
+        }
+
+        let srgb = VideoFrame::from_bgra(vec![10, 20, 30, 255], 1, 1);
+        assert_eq!(
+            srgb.convert_color_space(VideoColorSpace::Srgb).unwrap(),
+            srgb
+        );
+        assert_eq!(
+            srgb.convert_color_space(VideoColorSpace::Bt709)
+                .unwrap()
+                .as_bytes(),
+            srgb.as_bytes()
+        );
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(
+            nv12.convert_color_space(VideoColorSpace::Bt709),
+            Err(VideoFrameError::UnsupportedFormat(PixelFormat::Nv12))
+        );
+    }
+
+    #[test]
+    fn test_dither() {
+        // A horizontal 10-bit ramp over four 8-bit levels.
+        let (width, height) = (128usize, 8usize);
+        let ramp = (0..width).map(|column| (512 + (column * 16 / width) as u16) << 6);
+        let y_plane = ramp.collect::<Vec<_>>().repeat(height);
+        let uv_plane = vec![512 << 6; width * height / 2];
+        let frame = VideoFrame::from_p010(
+            y_plane,
+            uv_plane,
+            width as u32,
+            width as u32,
+            width as u32,
+            height as u32,
+            VideoColorSpace::Bt2020,
+        );
+
+        // Count the edges between columns whose average brightness differs
+        // by a whole 8-bit level or more.
+        let band_edges = |frame: VideoFrame| {
+            let pixels = frame.as_bytes().unwrap().to_vec();
+            let averages = (0..width)
+                .map(|column| {
+                    (0..height)
+                        .map(|row| pixels[(row * width + column) * 4 + 1] as f32)
+                        .sum::<f32>()
+                        / height as f32
+                })
+                .collect::<Vec<_>>();
+            averages
+                .windows(2)
+                .filter(|pair| (pair[1] - pair[0]).abs() >= 1.0)
+                .count()
+        };
+        assert_eq!(frame.to_bgra_with_dither(Dither::None), frame.to_bgra());
+        let banded = band_edges(frame.to_bgra_with_dither(Dither::None));
+        let dithered = band_edges(frame.to_bgra_with_dither(Dither::Ordered));
+        assert!(dithered < banded, "{dithered} >= {banded}");
+        assert_eq!(
+            frame.to_bgra_with_dither(Dither::Ordered),
+            frame.to_bgra_with_dither(Dither::Ordered)
+        );
+
+        let hdr = frame
+            .tone_map_to_sdr(ToneMap::Hable, Dither::Ordered)
+            .unwrap();
+        assert_eq!(hdr.pixel_format(), PixelFormat::Bgra8);
+        let sdr = VideoFrame::from_rgba(vec![1, 2, 3, 4], 1, 1);
+        assert_eq!(sdr.to_bgra_with_dither(Dither::Ordered), sdr.to_bgra());
+    }
+}
@@ -0,0 +1,1661 @@
+use super::{
+    AlphaMode, FrameLimits, PixelFormat, VideoFrame, VideoFrameError, validate_dimensions,
+};
+use crate::{BackgroundExecutor, Task};
+use serde::{Deserialize, Serialize};
+use util::ResultExt as _;
+
+/// The resampling filter used by [`VideoFrame::scale`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScaleFilter {
+    /// Pick the closest source pixel. Fast, but blocky when upscaling and prone
+    /// to aliasing when downscaling.
+    Nearest,
+    /// Blend the four closest source pixels.
+    Bilinear,
+}
+
+/// The layout of the tensor produced by [`VideoFrame::preprocess_for_ml`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MlFormat {
+    /// One luma value per pixel, row by row.
+    Luma,
+    /// All red values, then all green values, then all blue values, each
+    /// row by row, as in the `CHW` layout.
+    RgbPlanar,
+    /// The red, green and blue values of each pixel next to each other, row
+    /// by row, as in the `HWC` layout.
+    RgbInterleaved,
+}
+
+/// A clockwise rotation applied by [`VideoFrame::rotate`], or deferred until
+/// paint by [`VideoFrame::with_display_rotation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Rotation {
+    /// Leave the frame as is.
+    #[default]
+    None,
+    /// Rotate by 90 degrees clockwise.
+    Cw90,
+    /// Rotate by 180 degrees.
+    Cw180,
+    /// Rotate by 270 degrees clockwise.
+    Cw270,
+}
+
+impl Rotation {
+    /// Whether the rotation turns the frame on its side, swapping its width
+    /// and height.
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(self, Rotation::Cw90 | Rotation::Cw270)
+    }
+}
+
+/// Where [`VideoFrame::pad_to`] places a frame within its canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Alignment {
+    /// Against the top left corner.
+    TopLeft,
+    /// Against the top edge, centered horizontally.
+    Top,
+    /// Against the top right corner.
+    TopRight,
+    /// Against the left edge, centered vertically.
+    Left,
+    /// Centered in both directions.
+    #[default]
+    Center,
+    /// Against the right edge, centered vertically.
+    Right,
+    /// Against the bottom left corner.
+    BottomLeft,
+    /// Against the bottom edge, centered horizontally.
+    Bottom,
+    /// Against the bottom right corner.
+    BottomRight,
+}
+
+impl Alignment {
+    /// The offset of the frame within the canvas, given how much wider and
+    /// taller the canvas is than the frame.
+    fn offset(self, free_width: u32, free_height: u32) -> (u32, u32) {
+        let x = match self {
+            Alignment::TopLeft | Alignment::Left | Alignment::BottomLeft => 0,
+            Alignment::Top | Alignment::Center | Alignment::Bottom => free_width / 2,
+            Alignment::TopRight | Alignment::Right | Alignment::BottomRight => free_width,
+        };
+        let y = match self {
+            Alignment::TopLeft | Alignment::Top | Alignment::TopRight => 0,
+            Alignment::Left | Alignment::Center | Alignment::Right => free_height / 2,
+            Alignment::BottomLeft | Alignment::Bottom | Alignment::BottomRight => free_height,
+        };
+        (x, y)
+    }
+}
+
+impl VideoFrame {
+    /// Copy a rectangular region of this frame into a new, tightly packed frame.
+    ///
+    /// Only the rows inside the region are copied. The region must lie
+    /// entirely within the frame and have a non-zero size. Only packed CPU
+    /// frames can be cropped; call [`Self::to_bgra`] first for other frames.
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Result<Self, VideoFrameError> {
+        let view = self.view(x, y, width, height)?;
+        let mut cropped = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in view.rows() {
+            cropped.extend_from_slice(row);
+        }
+        Ok(self.with_packed_buffer(cropped, width, height, view.pixel_format()))
+    }
+
+    /// Place this frame on a larger canvas filled with `background`, without
+    /// scaling it, such as to give every participant in a call the same
+    /// aspect ratio.
+    ///
+    /// `background` is given in the frame's channel order, as for
+    /// [`Self::fill_rect`], and the frame is positioned according to
+    /// `alignment`. Returns [`VideoFrameError::OutOfBounds`] if the frame is
+    /// larger than the canvas in either direction. Only packed CPU frames can
+    /// be padded; call [`Self::to_bgra`] first for other frames.
+    pub fn pad_to(
+        &self,
+        canvas_width: u32,
+        canvas_height: u32,
+        background: [u8; 4],
+        alignment: Alignment,
+    ) -> Result<Self, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        if self.width > canvas_width || self.height > canvas_height {
+            return Err(VideoFrameError::OutOfBounds);
+        }
+        let (x, y) = alignment.offset(canvas_width - self.width, canvas_height - self.height);
+        let (canvas_stride, row_len) = (canvas_width as usize * 4, self.width as usize * 4);
+        let mut canvas = background.repeat(canvas_width as usize * canvas_height as usize);
+        for (row, source) in pixels.chunks(stride).take(self.height as usize).enumerate() {
+            let offset = (y as usize + row) * canvas_stride + x as usize * 4;
+            canvas[offset..][..row_len].copy_from_slice(&source[..row_len]);
+        }
+        Ok(self.with_packed_buffer(canvas, canvas_width, canvas_height, format))
+    }
+
+    /// Tile `frames` into a single BGRA frame of `columns` by `rows` cells,
+    /// each `cell_width` by `cell_height` pixels, such as for recording a
+    /// video call with one cell per participant.
+    ///
+    /// Frames fill the cells left to right, then top to bottom, and frames
+    /// beyond the last cell are left out. Each frame is scaled to the largest
+    /// size that fits its cell while keeping its aspect ratio, and centered on
+    /// the `[b, g, r, a]` `background`, which also fills the cells left over
+    /// when there are fewer frames than cells. Frames are converted with
+    /// [`Self::to_bgra`] first, and their pixels are copied rather than
+    /// blended. Returns [`VideoFrameError::ExceedsLimits`] if the grid is
+    /// larger than the default [`FrameLimits`].
+    pub fn compose_grid(
+        frames: &[VideoFrame],
+        columns: u32,
+        rows: u32,
+        cell_width: u32,
+        cell_height: u32,
+        background: [u8; 4],
+    ) -> Result<VideoFrame, VideoFrameError> {
+        let (Some(canvas_width), Some(canvas_height)) = (
+            columns.checked_mul(cell_width),
+            rows.checked_mul(cell_height),
+        ) else {
+            return Err(VideoFrameError::ExceedsLimits);
+        };
+        validate_dimensions(canvas_width, canvas_height)?;
+        FrameLimits::default().check(canvas_width, canvas_height, PixelFormat::Bgra8)?;
+
+        let canvas_stride = canvas_width as usize * 4;
+        let mut canvas = background.repeat(canvas_width as usize * canvas_height as usize);
+        let cell_count = columns as usize * rows as usize;
+        for (index, frame) in frames.iter().take(cell_count).enumerate() {
+            let ratio = (cell_width as f64 / frame.width as f64)
+                .min(cell_height as f64 / frame.height as f64);
+            let fitted = |length: u32, cell_length: u32| {
+                ((length as f64 * ratio).round() as u32).clamp(1, cell_length)
+            };
+            let (width, height) = (
+                fitted(frame.width, cell_width),
+                fitted(frame.height, cell_height),
+            );
+            let scaled = frame
+                .to_bgra()
+                .scale(width, height, ScaleFilter::Bilinear)?;
+            let (pixels, stride, _) = scaled.packed_pixels()?;
+
+            let (x, y) = Alignment::Center.offset(cell_width - width, cell_height - height);
+            let column = index as u32 % columns;
+            let row = index as u32 / columns;
+            let (x, y) = (column * cell_width + x, row * cell_height + y);
+            let row_len = width as usize * 4;
+            for (source_row, source) in pixels.chunks(stride).take(height as usize).enumerate() {
+                let offset = (y as usize + source_row) * canvas_stride + x as usize * 4;
+                canvas[offset..][..row_len].copy_from_slice(&source[..row_len]);
+            }
+        }
+        Ok(Self::from_packed(
+            canvas,
+            canvas_width,
+            canvas_height,
+            PixelFormat::Bgra8,
+        ))
+    }
+
+    /// Resample this frame to a new size, producing a tightly packed frame in
+    /// the same pixel format.
+    ///
+    /// Only packed CPU frames can be scaled; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn scale(
+        &self,
+        new_width: u32,
+        new_height: u32,
+        filter: ScaleFilter,
+    ) -> Result<Self, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        if new_width == 0 || new_height == 0 {
+            return Err(VideoFrameError::ZeroDimension);
+        }
+
+        let source = PackedPixels {
+            pixels,
+            stride,
+            width: self.width as usize,
+            height: self.height as usize,
+        };
+        let mut scaled = Vec::with_capacity(new_width as usize * new_height as usize * 4);
+        match filter {
+            ScaleFilter::Nearest => {
+                for row in 0..new_height as usize {
+                    let source_row = row * source.height / new_height as usize;
+                    for column in 0..new_width as usize {
+                        let source_column = column * source.width / new_width as usize;
+                        scaled.extend_from_slice(source.pixel(source_column, source_row));
+                    }
+                }
+            }
+            ScaleFilter::Bilinear => {
+                let x_ratio = source.width as f32 / new_width as f32;
+                let y_ratio = source.height as f32 / new_height as f32;
+                for row in 0..new_height {
+                    let (top, bottom, y_weight) = sample_position(row, y_ratio, source.height);
+                    for column in 0..new_width {
+                        let (left, right, x_weight) =
+                            sample_position(column, x_ratio, source.width);
+                        let top_left = source.pixel(left, top);
+                        let top_right = source.pixel(right, top);
+                        let bottom_left = source.pixel(left, bottom);
+                        let bottom_right = source.pixel(right, bottom);
+                        for channel in 0..4 {
+                            let upper = lerp(top_left[channel], top_right[channel], x_weight);
+                            let lower = lerp(bottom_left[channel], bottom_right[channel], x_weight);
+                            let value = upper + (lower - upper) * y_weight;
+                            scaled.push(value.round().clamp(0.0, 255.0) as u8);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(self.with_packed_buffer(scaled, new_width, new_height, format))
+    }
+
+    /// Shrink this frame to the largest size that fits within `max_width` by
+    /// `max_height` while keeping its aspect ratio, such as for thumbnails.
+    ///
+    /// Frames that already fit are returned unchanged, sharing the same buffer;
+    /// frames are never upscaled. Frames that aren't packed CPU frames are
+    /// converted with [`Self::to_bgra`] before scaling.
+    pub fn downscale_to_fit(&self, max_width: u32, max_height: u32, filter: ScaleFilter) -> Self {
+        if self.width <= max_width && self.height <= max_height {
+            return self.clone();
+        }
+        let ratio =
+            (max_width as f64 / self.width as f64).min(max_height as f64 / self.height as f64);
+        let fitted = |length: u32, max_length: u32| {
+            ((length as f64 * ratio).round() as u32).clamp(1, max_length.max(1))
+        };
+        let (width, height) = (
+            fitted(self.width, max_width),
+            fitted(self.height, max_height),
+        );
+
+        let converted;
+        let frame = if self.packed_buffer().is_some() {
+            self
+        } else {
+            converted = self.to_bgra();
+            &converted
+        };
+        frame
+            .scale(width, height, filter)
+            .log_err()
+            .unwrap_or_else(|| frame.clone())
+    }
+
+    /// Resample this frame for a video encoder, rounding `target_width` and
+    /// `target_height` down to multiples of `align` first.
+    ///
+    /// Encoders such as H.264 and VP9 reject frames with odd dimensions, so
+    /// `align` is usually 2; some hardware encoders need 16. Dimensions smaller
+    /// than `align` are rounded up to it instead of down to zero. Frames that
+    /// aren't packed CPU frames are converted with [`Self::to_bgra`] before
+    /// scaling.
+    pub fn scale_for_encode(
+        &self,
+        target_width: u32,
+        target_height: u32,
+        filter: ScaleFilter,
+        align: u32,
+    ) -> Self {
+        let align = align.max(1);
+        let aligned = |length: u32| (length / align * align).max(align);
+        let (width, height) = (aligned(target_width), aligned(target_height));
+
+        let converted;
+        let frame = if self.packed_buffer().is_some() {
+            self
+        } else {
+            converted = self.to_bgra();
+            &converted
+        };
+        frame
+            .scale(width, height, filter)
+            .log_err()
+            .unwrap_or_else(|| frame.clone())
+    }
+
+    /// Resample this frame to `target_width` by `target_height` and normalize
+    /// it to a tensor of values in 0–1 laid out as `output` says, such as for
+    /// the input of a segmentation or face detection model.
+    ///
+    /// Scaling is bilinear and happens in the same pass as normalizing, so
+    /// packed CPU frames are read without intermediate buffers. Other frames
+    /// are converted to BGRA first. Luma is weighted by the coefficients of
+    /// the frame's color space, and alpha is dropped. Returns
+    /// [`VideoFrameError::ZeroDimension`] if either target dimension is zero.
+    pub fn preprocess_for_ml(
+        &self,
+        target_width: u32,
+        target_height: u32,
+        output: MlFormat,
+    ) -> Result<Vec<f32>, VideoFrameError> {
+        if target_width == 0 || target_height == 0 {
+            return Err(VideoFrameError::ZeroDimension);
+        }
+        let converted;
+        let (pixels, stride, format) = match self.packed_pixels() {
+            Ok(packed) => packed,
+            Err(_) => {
+                converted = self
+                    .bgra_buffer()
+                    .map_err(|error| VideoFrameError::SourceUnavailable(error.to_string()))?;
+                (
+                    converted.as_slice(),
+                    self.width as usize * 4,
+                    PixelFormat::Bgra8,
+                )
+            }
+        };
+        let source = PackedPixels {
+            pixels,
+            stride,
+            width: self.width as usize,
+            height: self.height as usize,
+        };
+        let (red, blue) = match format {
+            PixelFormat::Rgba8 => (0, 2),
+            _ => (2, 0),
+        };
+        let (kr, kb) = self.color_space.luma_coefficients();
+        let kg = 1.0 - kr - kb;
+
+        let pixel_count = target_width as usize * target_height as usize;
+        let mut tensor = match output {
+            MlFormat::Luma => vec![0.0; pixel_count],
+            MlFormat::RgbPlanar | MlFormat::RgbInterleaved => vec![0.0; pixel_count * 3],
+        };
+        let x_ratio = source.width as f32 / target_width as f32;
+        let y_ratio = source.height as f32 / target_height as f32;
+        for row in 0..target_height {
+            let (top, bottom, y_weight) = sample_position(row, y_ratio, source.height);
+            for column in 0..target_width {
+                let (left, right, x_weight) = sample_position(column, x_ratio, source.width);
+                let top_left = source.pixel(left, top);
+                let top_right = source.pixel(right, top);
+                let bottom_left = source.pixel(left, bottom);
+                let bottom_right = source.pixel(right, bottom);
+                let [r, g, b] = [red, 1, blue].map(|channel| {
+                    let upper = lerp(top_left[channel], top_right[channel], x_weight);
+                    let lower = lerp(bottom_left[channel], bottom_right[channel], x_weight);
+                    (upper + (lower - upper) * y_weight) / 255.0
+                });
+                let index = row as usize * target_width as usize + column as usize;
+                match output {
+                    MlFormat::Luma => tensor[index] = kr * r + kg * g + kb * b,
+                    MlFormat::RgbPlanar => {
+                        tensor[index] = r;
+                        tensor[pixel_count + index] = g;
+                        tensor[pixel_count * 2 + index] = b;
+                    }
+                    MlFormat::RgbInterleaved => {
+                        tensor[index * 3..index * 3 + 3].copy_from_slice(&[r, g, b]);
+                    }
+                }
+            }
+        }
+        Ok(tensor)
+    }
+
+    /// Build the mip chain of this frame: BGRA frames starting at its full
+    /// size, each half the size of the previous one, down to 1x1.
+    ///
+    /// Each level is a 2x2 box filter of the previous one, with odd widths and
+    /// heights rounded down, so a frame drawn much smaller than its size can
+    /// be sampled from a level close to its drawn size without aliasing.
+    /// Frames in other formats are converted with [`Self::to_bgra`] first.
+    pub fn generate_mip_chain(&self) -> Vec<VideoFrame> {
+        let mut chain = vec![self.to_bgra()];
+        while let Some(level) = chain.last().and_then(|level| level.halve()) {
+            chain.push(level);
+        }
+        chain
+    }
+
+    /// Get the index of the smallest level of this frame's mip chain that is
+    /// still at least `width` by `height`, as described in
+    /// [`Self::generate_mip_chain`], where level 0 is the frame itself.
+    ///
+    /// Only packed CPU frames are downsampled, so other frames always get 0.
+    pub(crate) fn mip_level_index_for(&self, width: u32, height: u32) -> u32 {
+        if self.packed_pixels().is_err() {
+            return 0;
+        }
+        let (mut level_width, mut level_height) = self.size();
+        let mut index = 0;
+        while level_width / 2 >= width.max(1) && level_height / 2 >= height.max(1) {
+            (level_width, level_height) = (level_width / 2, level_height / 2);
+            index += 1;
+        }
+        index
+    }
+
+    /// Get level `index` of this frame's mip chain, as described in
+    /// [`Self::generate_mip_chain`], or its smallest level if the chain is
+    /// shorter.
+    ///
+    /// Only packed CPU frames are downsampled; other frames are returned as is.
+    pub(crate) fn mip_level(&self, index: u32) -> Self {
+        let mut level = self.clone();
+        for _ in 0..index {
+            match level.halve() {
+                Some(halved) => level = halved,
+                None => break,
+            }
+        }
+        level
+    }
+
+    /// Downsample a packed frame to half its size with a 2x2 box filter.
+    ///
+    /// Returns `None` for 1x1 frames and frames that aren't packed.
+    fn halve(&self) -> Option<Self> {
+        if self.width <= 1 && self.height <= 1 {
+            return None;
+        }
+        let (pixels, stride, format) = self.packed_pixels().ok()?;
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let (last_column, last_row) = (self.width as usize - 1, self.height as usize - 1);
+        let mut halved = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in 0..height as usize {
+            let rows = [row * 2, (row * 2 + 1).min(last_row)];
+            for column in 0..width as usize {
+                let columns = [column * 2, (column * 2 + 1).min(last_column)];
+                for channel in 0..4 {
+                    let sum: u32 = rows
+                        .iter()
+                        .flat_map(|row| {
+                            columns.iter().map(move |column| {
+                                pixels[row * stride + column * 4 + channel] as u32
+                            })
+                        })
+                        .sum();
+                    halved.push(((sum + 2) / 4) as u8);
+                }
+            }
+        }
+        Some(self.with_packed_buffer(halved, width, height, format))
+    }
+
+    /// Resample this frame on a background thread, as described in
+    /// [`Self::scale`].
+    ///
+    /// The resampling is cancelled if the returned task is dropped, so it must
+    /// be awaited or detached.
+    pub fn scale_async(
+        &self,
+        new_width: u32,
+        new_height: u32,
+        filter: ScaleFilter,
+        executor: &BackgroundExecutor,
+    ) -> Task<Result<Self, VideoFrameError>> {
+        let frame = self.clone();
+        executor.spawn(async move { frame.scale(new_width, new_height, filter) })
+    }
+
+    /// Rotate this frame clockwise, producing a tightly packed frame in the
+    /// same pixel format. Width and height are swapped for quarter turns.
+    ///
+    /// The rotation is baked into the pixels; see
+    /// [`Self::with_display_rotation`] to defer it to paint instead. Only
+    /// packed CPU frames can be rotated; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn rotate(&self, rotation: Rotation) -> Result<Self, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        let source = PackedPixels {
+            pixels,
+            stride,
+            width: self.width as usize,
+            height: self.height as usize,
+        };
+        let (new_width, new_height) = match rotation {
+            Rotation::None | Rotation::Cw180 => (self.width, self.height),
+            Rotation::Cw90 | Rotation::Cw270 => (self.height, self.width),
+        };
+
+        let mut rotated = Vec::with_capacity(source.width * source.height * 4);
+        for row in 0..new_height as usize {
+            for column in 0..new_width as usize {
+                let (source_column, source_row) = match rotation {
+                    Rotation::None => (column, row),
+                    Rotation::Cw90 => (row, source.height - 1 - column),
+                    Rotation::Cw180 => (source.width - 1 - column, source.height - 1 - row),
+                    Rotation::Cw270 => (source.width - 1 - row, column),
+                };
+                rotated.extend_from_slice(source.pixel(source_column, source_row));
+            }
+        }
+        Ok(self.with_packed_buffer(rotated, new_width, new_height, format))
+    }
+
+    /// Copy the pixels of this frame into a buffer whose rows are padded to a
+    /// multiple of `alignment` bytes, returning the buffer and its stride.
+    ///
+    /// GPU upload APIs often require this; wgpu's `Queue::write_texture`, for
+    /// example, needs `bytes_per_row` to be a multiple of 256. Only packed CPU
+    /// frames can be copied; call [`Self::to_bgra`] first for other frames.
+    pub fn to_aligned_rows(&self, alignment: u32) -> Result<(Vec<u8>, u32), VideoFrameError> {
+        let (pixels, stride, _) = self.packed_pixels()?;
+        let row_len = self.width * 4;
+        let aligned_stride = match alignment {
+            0 | 1 => row_len,
+            _ => row_len.checked_next_multiple_of(alignment).ok_or(
+                VideoFrameError::InvalidStride {
+                    stride: alignment,
+                    row_size: row_len,
+                },
+            )?,
+        };
+
+        let mut aligned = vec![0; aligned_stride as usize * self.height as usize];
+        for (source_row, row) in pixels
+            .chunks(stride)
+            .zip(aligned.chunks_exact_mut(aligned_stride as usize))
+        {
+            row[..row_len as usize].copy_from_slice(&source_row[..row_len as usize]);
+        }
+        Ok((aligned, aligned_stride))
+    }
+
+    /// Multiply the color channels of this frame by its alpha channel,
+    /// producing an [`AlphaMode::Premultiplied`] frame.
+    ///
+    /// Frames that are already premultiplied or opaque are returned unchanged.
+    /// Only packed CPU frames can be converted; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn premultiply(&self) -> Result<Self, VideoFrameError> {
+        if self.alpha_mode != AlphaMode::Straight {
+            return Ok(self.clone());
+        }
+        let converted = self.map_packed_pixels(|pixel| {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+            }
+        })?;
+        Ok(converted.with_alpha_mode(AlphaMode::Premultiplied))
+    }
+
+    /// Divide the color channels of this frame by its alpha channel, producing
+    /// an [`AlphaMode::Straight`] frame. Fully transparent pixels become black.
+    ///
+    /// Frames that are already straight or opaque are returned unchanged. Only
+    /// packed CPU frames can be converted; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn unpremultiply(&self) -> Result<Self, VideoFrameError> {
+        if self.alpha_mode != AlphaMode::Premultiplied {
+            return Ok(self.clone());
+        }
+        let converted = self.map_packed_pixels(|pixel| {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[..3] {
+                *channel = match alpha {
+                    0 => 0,
+                    _ => ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8,
+                };
+            }
+        })?;
+        Ok(converted.with_alpha_mode(AlphaMode::Straight))
+    }
+
+    /// Apply `f` to every visible pixel of this frame, producing a tightly
+    /// packed frame in the same format.
+    ///
+    /// Each pixel is passed in the frame's channel order, so `[b, g, r, a]` for
+    /// BGRA frames and `[r, g, b, a]` for RGBA frames. For example, converting a
+    /// BGRA frame to grayscale:
+    ///
+    /// ```
+    /// let gray = frame.map_pixels(|[b, g, r, a]| {
+    ///     let luma = ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8;
+    ///     [luma, luma, luma, a]
+    /// })?;
+    /// ```
+    ///
+    /// Only packed CPU frames can be mapped; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn map_pixels(&self, f: impl Fn([u8; 4]) -> [u8; 4]) -> Result<Self, VideoFrameError> {
+        self.map_packed_pixels(|pixel| {
+            let mapped = f([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            pixel.copy_from_slice(&mapped);
+        })
+    }
+
+    /// Alpha blend `overlay` onto this frame with its top left corner at `x`,
+    /// `y`, for watermarks and picture-in-picture self-views.
+    ///
+    /// The overlay is blended according to its [`AlphaMode`], and the result is
+    /// stored in this frame's alpha mode. Parts of the overlay that fall outside
+    /// this frame are clipped. If this frame's buffer is shared with other
+    /// frames it's copied first, so they're unaffected.
+    ///
+    /// Both frames must be packed CPU frames, though they may differ in channel
+    /// order; call [`Self::to_bgra`] first for other frames.
+    pub fn composite(
+        &mut self,
+        overlay: &VideoFrame,
+        x: u32,
+        y: u32,
+    ) -> Result<(), VideoFrameError> {
+        let (overlay_pixels, overlay_stride, overlay_format) = overlay.packed_pixels()?;
+        let (width, height, alpha_mode) = (self.width, self.height, self.alpha_mode);
+        let (pixels, stride, format) = self.packed_pixels_mut()?;
+        let visible_width = overlay.width.min(width.saturating_sub(x)) as usize;
+        let visible_height = overlay.height.min(height.saturating_sub(y)) as usize;
+        let (x, y) = (x as usize, y as usize);
+
+        for row in 0..visible_height {
+            let source_row = &overlay_pixels[row * overlay_stride..][..visible_width * 4];
+            let destination_row = &mut pixels[(y + row) * stride + x * 4..][..visible_width * 4];
+            for (source, destination) in source_row
+                .chunks_exact(4)
+                .zip(destination_row.chunks_exact_mut(4))
+            {
+                let mut source = [source[0], source[1], source[2], source[3]];
+                if overlay_format != format {
+                    source.swap(0, 2);
+                }
+                blend_over(source, overlay.alpha_mode, destination, alpha_mode);
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill a rectangle of this frame with a solid `color`, given in the frame's
+    /// channel order (`[b, g, r, a]` for BGRA frames), such as for letterbox
+    /// bars or blanking a region after a dropped frame.
+    ///
+    /// The rectangle is clipped to the frame, and row padding is left as is.
+    /// If this frame's buffer is shared with other frames it's copied first, so
+    /// they're unaffected. Only packed CPU frames can be filled.
+    pub fn fill_rect(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        color: [u8; 4],
+    ) -> Result<(), VideoFrameError> {
+        let (frame_width, frame_height) = (self.width, self.height);
+        let (pixels, stride, _) = self.packed_pixels_mut()?;
+        let visible_width = width.min(frame_width.saturating_sub(x)) as usize;
+        let visible_height = height.min(frame_height.saturating_sub(y)) as usize;
+        let (x, y) = (x as usize, y as usize);
+        for row in 0..visible_height {
+            let row = &mut pixels[(y + row) * stride + x * 4..][..visible_width * 4];
+            for pixel in row.chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color);
+            }
+        }
+        Ok(())
+    }
+
+    /// Blur a rectangle of this frame, such as to hide a window's contents in
+    /// a screen share or to soften a background.
+    ///
+    /// The blur approximates a Gaussian with three passes of a box blur of the
+    /// given `radius` in each direction, and only samples pixels inside the
+    /// rectangle. The rectangle is clipped to the frame, row padding is left
+    /// as is, and a `radius` of 0 leaves the frame unchanged. If this frame's
+    /// buffer is shared with other frames it's copied first, so they're
+    /// unaffected. Only packed CPU frames can be blurred.
+    pub fn blur_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        radius: u32,
+    ) -> Result<(), VideoFrameError> {
+        let (frame_width, frame_height) = (self.width, self.height);
+        let (pixels, stride, _) = self.packed_pixels_mut()?;
+        let visible_width = width.min(frame_width.saturating_sub(x)) as usize;
+        let visible_height = height.min(frame_height.saturating_sub(y)) as usize;
+        if radius == 0 || visible_width == 0 || visible_height == 0 {
+            return Ok(());
+        }
+        let radius = (radius as usize).min(visible_width.max(visible_height));
+        let (x, y) = (x as usize, y as usize);
+        for _ in 0..3 {
+            box_blur(
+                pixels,
+                visible_height,
+                visible_width,
+                radius,
+                |row, column| (y + row) * stride + (x + column) * 4,
+            );
+            box_blur(
+                pixels,
+                visible_width,
+                visible_height,
+                radius,
+                |column, row| (y + row) * stride + (x + column) * 4,
+            );
+        }
+        Ok(())
+    }
+
+    /// Fill the whole frame with a solid `color`, as described in
+    /// [`Self::fill_rect`].
+    pub fn clear(&mut self, color: [u8; 4]) -> Result<(), VideoFrameError> {
+        self.fill_rect(0, 0, self.width, self.height, color)
+    }
+
+    /// Adjust the brightness, contrast and gamma of the color channels of this
+    /// frame, such as for the controls of a video settings panel, producing a
+    /// tightly packed frame.
+    ///
+    /// Each channel is mapped from `value` in 0–1 to
+    /// `((value - 0.5) * contrast + 0.5 + brightness).powf(1.0 / gamma)`,
+    /// clamped to 0–1, through a lookup table computed once per call. A
+    /// `brightness` of 0, `contrast` of 1 and `gamma` of 1 leave the frame
+    /// unchanged; `gamma` above 1 brightens midtones. Alpha is left as is.
+    ///
+    /// Frames that aren't packed CPU frames are converted with
+    /// [`Self::to_bgra`] first.
+    pub fn adjust(&self, brightness: f32, contrast: f32, gamma: f32) -> Self {
+        let mut table = [0u8; 256];
+        for (value, entry) in table.iter_mut().enumerate() {
+            let adjusted = ((value as f32 / 255.0 - 0.5) * contrast + 0.5 + brightness)
+                .clamp(0.0, 1.0)
+                .powf(1.0 / gamma);
+            *entry = (adjusted.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+
+        let converted;
+        let frame = if self.packed_buffer().is_some() {
+            self
+        } else {
+            converted = self.to_bgra();
+            &converted
+        };
+        frame
+            .map_packed_pixels(|pixel| {
+                for channel in &mut pixel[..3] {
+                    *channel = table[*channel as usize];
+                }
+            })
+            .log_err()
+            .unwrap_or_else(|| frame.clone())
+    }
+
+    /// Make the pixels of this frame that are close to the `key` color, given
+    /// as `[r, g, b]`, transparent, such as for a green screen, producing a
+    /// BGRA frame with [`AlphaMode::Straight`] to composite over a background.
+    ///
+    /// A pixel is keyed out when none of its channels differ from the key by
+    /// more than `tolerance`. Other pixels keep their alpha, so opaque pixels
+    /// stay opaque. Use [`Self::chroma_key_with_softness`] to fade the edges
+    /// of the keyed region.
+    ///
+    /// CPU frames in any format are converted with [`Self::to_bgra`] first.
+    /// Hardware-backed frames aren't supported.
+    pub fn chroma_key(&self, key: [u8; 3], tolerance: u8) -> Result<Self, VideoFrameError> {
+        self.chroma_key_with_softness(key, tolerance, 0)
+    }
+
+    /// Key out the pixels close to the `key` color as in [`Self::chroma_key`],
+    /// and make pixels up to `softness` further from the key partially
+    /// transparent, their opacity increasing with their distance.
+    pub fn chroma_key_with_softness(
+        &self,
+        key: [u8; 3],
+        tolerance: u8,
+        softness: u8,
+    ) -> Result<Self, VideoFrameError> {
+        if self.cpu_planes().is_none() {
+            return Err(VideoFrameError::UnsupportedForHardwareFrame);
+        }
+        let opaque = self.alpha_mode == AlphaMode::Opaque;
+        let frame = self.to_bgra().unpremultiply()?;
+        let (tolerance, softness) = (tolerance as u32, softness as u32);
+        let keyed = frame.map_packed_pixels(|pixel| {
+            let distance = [pixel[2], pixel[1], pixel[0]]
+                .iter()
+                .zip(key)
+                .map(|(channel, key)| channel.abs_diff(key) as u32)
+                .max()
+                .unwrap_or(0);
+            let alpha = if opaque { 255 } else { pixel[3] as u32 };
+            pixel[3] = if distance <= tolerance {
+                0
+            } else if distance <= tolerance + softness {
+                ((alpha * (distance - tolerance) + softness / 2) / softness) as u8
+            } else {
+                alpha as u8
+            };
+        })?;
+        Ok(keyed.with_alpha_mode(AlphaMode::Straight))
+    }
+
+    /// Set the alpha channel of every pixel to fully opaque, for painting
+    /// [`AlphaMode::Opaque`] frames with renderers that always blend.
+    pub(crate) fn to_opaque_alpha(&self) -> Result<Self, VideoFrameError> {
+        self.map_packed_pixels(|pixel| pixel[3] = 255)
+    }
+
+    /// Copy this frame into a tightly packed frame, applying `f` to each
+    /// four-byte pixel.
+    fn map_packed_pixels(&self, mut f: impl FnMut(&mut [u8])) -> Result<Self, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        let row_len = self.width as usize * 4;
+        let mut mapped = Vec::with_capacity(row_len * self.height as usize);
+        for row in pixels.chunks(stride).take(self.height as usize) {
+            mapped.extend_from_slice(&row[..row_len]);
+        }
+        for pixel in mapped.chunks_exact_mut(4) {
+            f(pixel);
+        }
+        Ok(self.with_packed_buffer(mapped, self.width, self.height, format))
+    }
+
+    /// Mirror this frame left to right, as is conventional for self-view.
+    ///
+    /// Only packed CPU frames can be flipped; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn flip_horizontal(&self) -> Result<Self, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        let row_len = self.width as usize * 4;
+        let mut flipped = Vec::with_capacity(row_len * self.height as usize);
+        for row in pixels.chunks(stride).take(self.height as usize) {
+            for pixel in row[..row_len].chunks_exact(4).rev() {
+                flipped.extend_from_slice(pixel);
+            }
+        }
+        Ok(self.with_packed_buffer(flipped, self.width, self.height, format))
+    }
+
+    /// Mirror this frame top to bottom.
+    ///
+    /// Only packed CPU frames can be flipped; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn flip_vertical(&self) -> Result<Self, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        let row_len = self.width as usize * 4;
+        let mut flipped = Vec::with_capacity(row_len * self.height as usize);
+        for row in pixels.chunks(stride).take(self.height as usize).rev() {
+            flipped.extend_from_slice(&row[..row_len]);
+        }
+        Ok(self.with_packed_buffer(flipped, self.width, self.height, format))
+    }
+}
+
+/// A borrowed view of packed four-byte pixels.
+struct PackedPixels<'a> {
+    pixels: &'a [u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+}
+
+impl PackedPixels<'_> {
+    fn pixel(&self, column: usize, row: usize) -> &[u8] {
+        let offset = row * self.stride + column * 4;
+        &self.pixels[offset..offset + 4]
+    }
+}
+
+/// Replace each of `length` pixels along each of `lines` lines with the average
+/// of the pixels within `radius` of it on the same line, repeating the pixels
+/// at either end. `offset` gives the position in `pixels` of a pixel by line
+/// and index along the line.
+fn box_blur(
+    pixels: &mut [u8],
+    lines: usize,
+    length: usize,
+    radius: usize,
+    offset: impl Fn(usize, usize) -> usize,
+) {
+    let window = 2 * radius as u32 + 1;
+    let (radius, last) = (radius as isize, length as isize - 1);
+    let mut line = Vec::with_capacity(length);
+    for line_index in 0..lines {
+        line.clear();
+        line.extend((0..length).map(|position| {
+            let start = offset(line_index, position);
+            [
+                pixels[start],
+                pixels[start + 1],
+                pixels[start + 2],
+                pixels[start + 3],
+            ]
+        }));
+        let sample = |position: isize| line[position.clamp(0, last) as usize];
+
+        let mut sum = [0u32; 4];
+        for position in -radius..=radius {
+            for (total, channel) in sum.iter_mut().zip(sample(position)) {
+                *total += channel as u32;
+            }
+        }
+        for position in 0..length {
+            let start = offset(line_index, position);
+            for (channel, total) in pixels[start..start + 4].iter_mut().zip(&sum) {
+                *channel = ((total + window / 2) / window) as u8;
+            }
+            let incoming = sample(position as isize + radius + 1);
+            let outgoing = sample(position as isize - radius);
+            for ((total, incoming), outgoing) in sum.iter_mut().zip(incoming).zip(outgoing) {
+                *total = *total + incoming as u32 - outgoing as u32;
+            }
+        }
+    }
+}
+
+/// Map a destination coordinate to the two neighbouring source coordinates and
+/// the weight of the second one, sampling at pixel centers.
+fn sample_position(destination: u32, ratio: f32, source_len: usize) -> (usize, usize, f32) {
+    let position = ((destination as f32 + 0.5) * ratio - 0.5).max(0.0);
+    let lower = (position.floor() as usize).min(source_len - 1);
+    let upper = (lower + 1).min(source_len - 1);
+    (lower, upper, position - lower as f32)
+}
+
+fn lerp(from: u8, to: u8, weight: f32) -> f32 {
+    from as f32 + (to as f32 - from as f32) * weight
+}
+
+/// Blend a `source` pixel over a `destination` pixel, each interpreted
+/// according to its alpha mode, storing the result in the destination's mode.
+fn blend_over(
+    source: [u8; 4],
+    source_alpha_mode: AlphaMode,
+    destination: &mut [u8],
+    destination_alpha_mode: AlphaMode,
+) {
+    let premultiplied = |pixel: &[u8], alpha_mode: AlphaMode| {
+        let alpha = match alpha_mode {
+            AlphaMode::Opaque => 1.0,
+            AlphaMode::Straight | AlphaMode::Premultiplied => pixel[3] as f32 / 255.0,
+        };
+        let color_scale = match alpha_mode {
+            AlphaMode::Premultiplied => 1.0 / 255.0,
+            AlphaMode::Straight | AlphaMode::Opaque => alpha / 255.0,
+        };
+        [
+            pixel[0] as f32 * color_scale,
+            pixel[1] as f32 * color_scale,
+            pixel[2] as f32 * color_scale,
+            alpha,
+        ]
+    };
+    let source = premultiplied(&source, source_alpha_mode);
+    let background = premultiplied(destination, destination_alpha_mode);
+    let coverage = 1.0 - source[3];
+    let alpha = source[3] + background[3] * coverage;
+    let color_scale = match destination_alpha_mode {
+        AlphaMode::Premultiplied => 255.0,
+        AlphaMode::Straight | AlphaMode::Opaque if alpha > 0.0 => 255.0 / alpha,
+        AlphaMode::Straight | AlphaMode::Opaque => 0.0,
+    };
+    for channel in 0..3 {
+        let value = (source[channel] + background[channel] * coverage) * color_scale;
+        destination[channel] = value.round().clamp(0.0, 255.0) as u8;
+    }
+    if destination_alpha_mode != AlphaMode::Opaque {
+        destination[3] = (alpha * 255.0).round() as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PixelFormat, VideoColorSpace};
+
+    /// A frame where each pixel's channels are `[column, row, 0, 255]`.
+    fn coordinate_frame(width: u32, height: u32, stride: u32) -> VideoFrame {
+        let mut buffer = vec![0; (stride * height) as usize];
+        for (row, pixels) in buffer.chunks_mut(stride as usize).enumerate() {
+            for (column, pixel) in pixels.chunks_mut(4).take(width as usize).enumerate() {
+                pixel.copy_from_slice(&[column as u8, row as u8, 0, 255]);
+            }
+        }
+        VideoFrame::from_bgra_with_stride(buffer, width, height, stride)
+    }
+
+    #[test]
+    fn test_crop() {
+        let frame = coordinate_frame(4, 3, 24);
+        let cropped = frame.crop(1, 1, 2, 2).unwrap();
+
+        assert_eq!(cropped.size(), (2, 2));
+        assert_eq!(cropped.bytes_per_row(), 8);
+        assert_eq!(cropped.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(
+            cropped.as_bytes(),
+            Some([1, 1, 0, 255, 2, 1, 0, 255, 1, 2, 0, 255, 2, 2, 0, 255].as_slice())
+        );
+    }
+
+    #[test]
+    fn test_crop_validation() {
+        let frame = coordinate_frame(4, 3, 16);
+
+        assert_eq!(
+            frame.crop(3, 0, 2, 1).unwrap_err(),
+            VideoFrameError::OutOfBounds
+        );
+        assert_eq!(
+            frame.crop(0, 0, 4, 4).unwrap_err(),
+            VideoFrameError::OutOfBounds
+        );
+        assert_eq!(
+            frame.crop(0, 0, 0, 1).unwrap_err(),
+            VideoFrameError::ZeroDimension
+        );
+        assert!(frame.crop(0, 0, 4, 3).is_ok());
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(
+            nv12.crop(0, 0, 1, 1).unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::Nv12)
+        );
+    }
+
+    #[test]
+    fn test_scale() {
+        let frame = coordinate_frame(4, 2, 20);
+
+        let nearest = frame.scale(2, 1, ScaleFilter::Nearest).unwrap();
+        assert_eq!(nearest.size(), (2, 1));
+        assert_eq!(nearest.bytes_per_row(), 8);
+        assert_eq!(
+            nearest.as_bytes(),
+            Some([0, 0, 0, 255, 2, 0, 0, 255].as_slice())
+        );
+
+        let bilinear = frame.scale(2, 1, ScaleFilter::Bilinear).unwrap();
+        assert_eq!(
+            bilinear.as_bytes(),
+            Some([1, 1, 0, 255, 3, 1, 0, 255].as_slice())
+        );
+
+        let rgba = VideoFrame::from_rgba(vec![10, 20, 30, 40], 1, 1);
+        let upscaled = rgba.scale(3, 2, ScaleFilter::Bilinear).unwrap();
+        assert_eq!(upscaled.pixel_format(), PixelFormat::Rgba8);
+        assert_eq!(
+            upscaled.as_bytes(),
+            Some([10, 20, 30, 40].repeat(6).as_slice())
+        );
+
+        assert_eq!(
+            frame.scale(0, 1, ScaleFilter::Nearest).unwrap_err(),
+            VideoFrameError::ZeroDimension
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        // 2 columns by 3 rows:
+        //   (0,0) (1,0)
+        //   (0,1) (1,1)
+        //   (0,2) (1,2)
+        let frame = coordinate_frame(2, 3, 8);
+        let pixels = |frame: &VideoFrame| {
+            frame
+                .as_bytes()
+                .unwrap()
+                .chunks(4)
+                .map(|pixel| (pixel[0], pixel[1]))
+                .collect::<Vec<_>>()
+        };
+
+        let rotated = frame.rotate(Rotation::Cw90).unwrap();
+        assert_eq!(rotated.size(), (3, 2));
+        assert_eq!(rotated.bytes_per_row(), 12);
+        assert_eq!(
+            pixels(&rotated),
+            [(0, 2), (0, 1), (0, 0), (1, 2), (1, 1), (1, 0)]
+        );
+
+        let rotated = frame.rotate(Rotation::Cw270).unwrap();
+        assert_eq!(rotated.size(), (3, 2));
+        assert_eq!(
+            pixels(&rotated),
+            [(1, 0), (1, 1), (1, 2), (0, 0), (0, 1), (0, 2)]
+        );
+
+        let rotated = frame.rotate(Rotation::Cw180).unwrap();
+        assert_eq!(rotated.size(), (2, 3));
+        assert_eq!(
+            pixels(&rotated),
+            [(1, 2), (0, 2), (1, 1), (0, 1), (1, 0), (0, 0)]
+        );
+
+        assert_eq!(
+            frame.rotate(Rotation::None).unwrap().as_bytes(),
+            frame.as_bytes()
+        );
+
+        let i420 = VideoFrame::from_i420(
+            vec![0; 4],
+            vec![128],
+            vec![128],
+            2,
+            1,
+            1,
+            2,
+            2,
+            VideoColorSpace::Bt601,
+        );
+        assert_eq!(
+            i420.rotate(Rotation::Cw90).unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::I420)
+        );
+    }
+
+    #[test]
+    fn test_flip() {
+        let frame = coordinate_frame(3, 2, 16);
+        let coordinates = |frame: VideoFrame| {
+            frame
+                .as_bytes()
+                .unwrap()
+                .chunks(4)
+                .map(|pixel| (pixel[0], pixel[1]))
+                .collect::<Vec<_>>()
+        };
+
+        let flipped = frame.flip_horizontal().unwrap();
+        assert_eq!(flipped.bytes_per_row(), 12);
+        assert_eq!(
+            coordinates(flipped),
+            [(2, 0), (1, 0), (0, 0), (2, 1), (1, 1), (0, 1)]
+        );
+
+        let flipped = frame.flip_vertical().unwrap();
+        assert_eq!(flipped.bytes_per_row(), 12);
+        assert_eq!(
+            coordinates(flipped),
+            [(0, 1), (1, 1), (2, 1), (0, 0), (1, 0), (2, 0)]
+        );
+
+        let rgba = VideoFrame::from_rgba(vec![1, 2, 3, 4, 5, 6, 7, 8], 2, 1);
+        let flipped = rgba.flip_horizontal().unwrap();
+        assert_eq!(flipped.pixel_format(), PixelFormat::Rgba8);
+        assert_eq!(
+            flipped.as_bytes(),
+            Some([5, 6, 7, 8, 1, 2, 3, 4].as_slice())
+        );
+    }
+
+    #[test]
+    fn test_to_aligned_rows() {
+        let frame = coordinate_frame(3, 2, 12);
+        let (aligned, stride) = frame.to_aligned_rows(256).unwrap();
+        assert_eq!(stride, 256);
+        assert_eq!(aligned.len(), 512);
+        assert_eq!(&aligned[..12], &frame.as_bytes().unwrap()[..12]);
+        assert!(aligned[12..256].iter().all(|byte| *byte == 0));
+        assert_eq!(&aligned[256..268], &frame.as_bytes().unwrap()[12..]);
+
+        let (packed, stride) = frame.to_aligned_rows(4).unwrap();
+        assert_eq!(stride, 12);
+        assert_eq!(Some(packed.as_slice()), frame.as_bytes());
+    }
+
+    #[test]
+    fn test_transforms_preserve_timing() {
+        let pts = std::time::Duration::from_millis(1500);
+        let duration = std::time::Duration::from_millis(16);
+        let frame = coordinate_frame(4, 2, 16)
+            .with_pts(pts)
+            .with_duration(duration);
+        assert_eq!(frame.pts(), Some(pts));
+        assert_eq!(frame.duration(), Some(duration));
+
+        for transformed in [
+            frame.crop(1, 0, 2, 2).unwrap(),
+            frame.scale(2, 1, ScaleFilter::Bilinear).unwrap(),
+            frame.rotate(Rotation::Cw90).unwrap(),
+            frame.flip_horizontal().unwrap(),
+            frame.to_bgra(),
+        ] {
+            assert_eq!(transformed.pts(), Some(pts));
+            assert_eq!(transformed.duration(), Some(duration));
+        }
+
+        assert_eq!(coordinate_frame(1, 1, 4).pts(), None);
+    }
+
+    #[test]
+    fn test_premultiply_round_trip() {
+        let straight = VideoFrame::from_bgra(
+            vec![
+                200, 100, 50, 128, 255, 255, 255, 255, 10, 20, 30, 0, 90, 180, 240, 17,
+            ],
+            2,
+            2,
+        );
+        assert_eq!(straight.alpha_mode(), AlphaMode::Straight);
+
+        let premultiplied = straight.premultiply().unwrap();
+        assert_eq!(premultiplied.alpha_mode(), AlphaMode::Premultiplied);
+        assert_eq!(
+            &premultiplied.as_bytes().unwrap()[..12],
+            &[100, 50, 25, 128, 255, 255, 255, 255, 0, 0, 0, 0]
+        );
+        assert_eq!(premultiplied.premultiply().unwrap(), premultiplied);
+
+        let round_tripped = premultiplied.unpremultiply().unwrap();
+        assert_eq!(round_tripped.alpha_mode(), AlphaMode::Straight);
+        for (original, converted) in straight
+            .as_bytes()
+            .unwrap()
+            .chunks(4)
+            .zip(round_tripped.as_bytes().unwrap().chunks(4))
+        {
+            let alpha = original[3];
+            if alpha == 0 {
+                continue;
+            }
+            // Premultiplying loses precision in proportion to how transparent
+            // the pixel is.
+            let tolerance = 255 / alpha as i32 + 1;
+            for channel in 0..3 {
+                let difference = original[channel] as i32 - converted[channel] as i32;
+                assert!(
+                    difference.abs() <= tolerance,
+                    "{original:?} became {converted:?}"
+                );
+            }
+            assert_eq!(original[3], converted[3]);
+        }
+
+        let opaque = straight.with_alpha_mode(AlphaMode::Opaque);
+        assert_eq!(opaque.premultiply().unwrap().as_bytes(), opaque.as_bytes());
+    }
+
+    #[test]
+    fn test_map_pixels_grayscale() {
+        let frame = VideoFrame::from_bgra_with_stride(
+            vec![
+                0, 0, 255, 255, 255, 255, 255, 255, 0, 0, 255, 0, 0, 128, 0, 0, 0, 0,
+            ],
+            2,
+            1,
+            12,
+        )
+        .with_pts(std::time::Duration::from_millis(5));
+        let gray = frame
+            .map_pixels(|[b, g, r, a]| {
+                let luma = ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8;
+                [luma, luma, luma, a]
+            })
+            .unwrap();
+
+        assert_eq!(gray.size(), (2, 1));
+        assert_eq!(gray.bytes_per_row(), 8);
+        assert_eq!(gray.pts(), Some(std::time::Duration::from_millis(5)));
+        assert_eq!(
+            gray.as_bytes().unwrap(),
+            &[76, 76, 76, 255, 255, 255, 255, 255]
+        );
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(
+            nv12.map_pixels(|pixel| pixel).unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::Nv12)
+        );
+    }
+
+    #[test]
+    fn test_composite() {
+        let mut frame = VideoFrame::solid_color(4, 4, [0, 0, 0, 255]);
+        let untouched = frame.clone();
+        let overlay = VideoFrame::solid_color(2, 2, [0, 0, 255, 128]);
+        frame.composite(&overlay, 1, 1).unwrap();
+
+        assert_eq!(untouched.as_bytes().unwrap()[20..24], [0, 0, 0, 255]);
+        for (index, pixel) in frame.as_bytes().unwrap().chunks(4).enumerate() {
+            let (column, row) = (index % 4, index / 4);
+            if (1..3).contains(&column) && (1..3).contains(&row) {
+                assert_eq!(pixel, [0, 0, 128, 255]);
+            } else {
+                assert_eq!(pixel, [0, 0, 0, 255]);
+            }
+        }
+
+        let mut clipped = VideoFrame::solid_color(4, 4, [0, 0, 0, 255]);
+        let rgba_overlay = VideoFrame::from_rgba([255, 0, 0, 255].repeat(4), 2, 2);
+        clipped.composite(&rgba_overlay, 3, 3).unwrap();
+        let changed: Vec<_> = clipped
+            .as_bytes()
+            .unwrap()
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, pixel)| *pixel != [0, 0, 0, 255])
+            .collect();
+        assert_eq!(changed, [(15, &[0, 0, 255, 255][..])]);
+        clipped.composite(&rgba_overlay, 10, 10).unwrap();
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(
+            frame.composite(&nv12, 0, 0).unwrap_err(),
+            VideoFrameError::UnsupportedFormat(PixelFormat::Nv12)
+        );
+    }
+
+    #[test]
+    fn test_fill_rect() {
+        let mut frame = coordinate_frame(3, 3, 16);
+        let original = frame.clone();
+        frame.fill_rect(1, 2, 5, 5, [9, 9, 9, 9]).unwrap();
+
+        let bytes = frame.as_bytes().unwrap();
+        assert_eq!(bytes.len(), 48);
+        assert_eq!(
+            &bytes[32..48],
+            &[0, 2, 0, 255, 9, 9, 9, 9, 9, 9, 9, 9, 0, 0, 0, 0]
+        );
+        assert_eq!(&bytes[..32], &original.as_bytes().unwrap()[..32]);
+        assert_ne!(frame, original);
+
+        frame.clear([1, 2, 3, 4]).unwrap();
+        for row in frame.as_bytes().unwrap().chunks(16) {
+            assert_eq!(&row[..12], [1, 2, 3, 4].repeat(3).as_slice());
+        }
+    }
+
+    #[test]
+    fn test_downscale_to_fit() {
+        let landscape = coordinate_frame(8, 4, 32);
+        let fitted = landscape.downscale_to_fit(4, 4, ScaleFilter::Nearest);
+        assert_eq!(fitted.size(), (4, 2));
+        assert_eq!(fitted, landscape.scale(4, 2, ScaleFilter::Nearest).unwrap());
+
+        let portrait = coordinate_frame(4, 8, 16);
+        let fitted = portrait.downscale_to_fit(4, 4, ScaleFilter::Bilinear);
+        assert_eq!(fitted.size(), (2, 4));
+
+        let small = coordinate_frame(2, 2, 8);
+        let unchanged = small.downscale_to_fit(4, 4, ScaleFilter::Bilinear);
+        assert_eq!(unchanged.size(), (2, 2));
+        assert!(std::sync::Arc::ptr_eq(
+            unchanged.packed_buffer().unwrap().0,
+            small.packed_buffer().unwrap().0
+        ));
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![0; 16],
+            vec![128; 8],
+            4,
+            4,
+            4,
+            4,
+            VideoColorSpace::Bt601,
+        );
+        let fitted = nv12.downscale_to_fit(2, 2, ScaleFilter::Nearest);
+        assert_eq!(fitted.size(), (2, 2));
+        assert_eq!(fitted.pixel_format(), PixelFormat::Bgra8);
+    }
+
+    #[test]
+    fn test_adjust() {
+        let frame = VideoFrame::from_bgra_with_stride(
+            [&[0, 64, 128, 200, 255, 1, 2, 3][..], &[0xff; 4]].concat(),
+            2,
+            1,
+            12,
+        );
+        let unchanged = frame.adjust(0.0, 1.0, 1.0);
+        assert_eq!(unchanged, frame);
+        assert_eq!(unchanged.bytes_per_row(), 8);
+
+        let brighter = frame.adjust(0.5, 1.0, 1.0);
+        assert_eq!(
+            brighter.as_bytes(),
+            Some([128, 192, 255, 200, 255, 129, 130, 3].as_slice())
+        );
+        let flat = frame.adjust(0.0, 0.0, 1.0);
+        assert_eq!(
+            flat.as_bytes(),
+            Some([128, 128, 128, 200, 128, 128, 128, 3].as_slice())
+        );
+        let gamma = frame.adjust(0.0, 1.0, 2.0);
+        assert_eq!(&gamma.as_bytes().unwrap()[..3], [0, 128, 181]);
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        let adjusted = nv12.adjust(0.0, 1.0, 1.0);
+        assert_eq!(adjusted.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(adjusted, nv12.to_bgra());
+    }
+
+    #[test]
+    fn test_blur_region() {
+        let edge = |frame: &VideoFrame| {
+            let row = frame.rows().unwrap().next().unwrap();
+            row.chunks_exact(4)
+                .zip(row.chunks_exact(4).skip(1))
+                .map(|(left, right)| left[0].abs_diff(right[0]))
+                .max()
+                .unwrap()
+        };
+        let buffer = [[0, 0, 0, 255].repeat(4), vec![255; 16], vec![7; 4]]
+            .concat()
+            .repeat(2);
+        let mut frame = VideoFrame::from_bgra_with_stride(buffer, 8, 2, 36);
+        assert_eq!(edge(&frame), 255);
+
+        let original = frame.clone();
+        frame.blur_region(0, 0, 8, 2, 0).unwrap();
+        assert_eq!(frame, original);
+
+        frame.blur_region(0, 0, 100, 100, 2).unwrap();
+        assert!(edge(&frame) < 100, "{}", edge(&frame));
+        assert_eq!(&frame.as_bytes().unwrap()[32..36], [7; 4]);
+        assert_eq!(original.as_bytes().unwrap()[16], 255);
+
+        let mut frame = original.clone();
+        frame.blur_region(4, 0, 4, 2, 3).unwrap();
+        assert_eq!(frame, original);
+    }
+
+    #[test]
+    fn test_chroma_key() {
+        let frame = VideoFrame::from_rgba(
+            [
+                [0, 255, 0, 255],
+                [10, 250, 5, 255],
+                [255, 0, 0, 255],
+                [0, 200, 0, 255],
+            ]
+            .concat(),
+            4,
+            1,
+        )
+        .with_alpha_mode(AlphaMode::Opaque);
+        let keyed = frame.chroma_key([0, 255, 0], 16).unwrap();
+        assert_eq!(keyed.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(keyed.alpha_mode(), AlphaMode::Straight);
+        let alpha: Vec<_> = keyed
+            .as_bytes()
+            .unwrap()
+            .chunks(4)
+            .map(|pixel| pixel[3])
+            .collect();
+        assert_eq!(alpha, [0, 0, 255, 255]);
+        assert_eq!(&keyed.as_bytes().unwrap()[8..12], [0, 0, 255, 255]);
+
+        let softened = frame.chroma_key_with_softness([0, 255, 0], 16, 78).unwrap();
+        let alpha: Vec<_> = softened
+            .as_bytes()
+            .unwrap()
+            .chunks(4)
+            .map(|pixel| pixel[3])
+            .collect();
+        assert_eq!(alpha, [0, 0, 255, 128]);
+    }
+
+    #[test]
+    fn test_pad_to() {
+        let frame = VideoFrame::from_bgra_with_stride([[1, 2, 3, 4], [0; 4]].concat(), 1, 1, 8)
+            .with_pts(std::time::Duration::from_millis(40));
+        let background = [9, 9, 9, 255];
+
+        let centered = frame.pad_to(3, 3, background, Alignment::Center).unwrap();
+        assert_eq!(centered.size(), (3, 3));
+        assert_eq!(centered.pts(), frame.pts());
+        let mut expected = background.repeat(9);
+        expected[16..20].copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(centered.as_bytes(), Some(expected.as_slice()));
+
+        let corner = frame
+            .pad_to(2, 2, background, Alignment::BottomRight)
+            .unwrap();
+        assert_eq!(
+            corner.as_bytes(),
+            Some(
+                [background, background, background, [1, 2, 3, 4]]
+                    .concat()
+                    .as_slice()
+            )
+        );
+        let corner = frame.pad_to(2, 2, background, Alignment::TopLeft).unwrap();
+        assert_eq!(
+            &corner.as_bytes().unwrap()[..8],
+            [[1, 2, 3, 4], background].concat()
+        );
+
+        assert_eq!(
+            frame
+                .pad_to(2, 0, background, Alignment::Center)
+                .unwrap_err(),
+            VideoFrameError::OutOfBounds
+        );
+    }
+
+    #[test]
+    fn test_generate_mip_chain() {
+        let mut checkerboard = Vec::new();
+        for row in 0..4 {
+            for column in 0..4 {
+                let value = if (row + column) % 2 == 0 { 0 } else { 255 };
+                checkerboard.extend_from_slice(&[value, value, value, 255]);
+            }
+        }
+        let frame = VideoFrame::from_bgra(checkerboard, 4, 4);
+        let chain = frame.generate_mip_chain();
+        let sizes: Vec<_> = chain.iter().map(|level| level.size()).collect();
+        assert_eq!(sizes, [(4, 4), (2, 2), (1, 1)]);
+        assert_eq!(chain[0], frame);
+        assert_eq!(chain[1].as_bytes().unwrap(), [128, 128, 128, 255].repeat(4));
+
+        assert_eq!(frame.mip_level_index_for(2, 2), 1);
+        assert_eq!(frame.mip_level_index_for(3, 1), 0);
+        assert_eq!(frame.mip_level_index_for(0, 0), 2);
+        assert_eq!(frame.mip_level(1), chain[1]);
+        assert_eq!(frame.mip_level(5).size(), (1, 1));
+    }
+
+    #[test]
+    fn test_scale_for_encode() {
+        let frame = VideoFrame::from_bgra(vec![0; 8 * 6 * 4], 8, 6);
+        let scaled = frame.scale_for_encode(5, 3, ScaleFilter::Bilinear, 2);
+        assert_eq!(scaled.size(), (4, 2));
+        assert_eq!(
+            frame.scale_for_encode(7, 6, ScaleFilter::Nearest, 4).size(),
+            (4, 4)
+        );
+        assert_eq!(
+            frame.scale_for_encode(1, 1, ScaleFilter::Nearest, 2).size(),
+            (2, 2)
+        );
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![0; 16],
+            vec![128; 8],
+            4,
+            4,
+            4,
+            4,
+            VideoColorSpace::Bt601,
+        );
+        let scaled = nv12.scale_for_encode(3, 3, ScaleFilter::Nearest, 2);
+        assert_eq!(scaled.size(), (2, 2));
+        assert_eq!(scaled.pixel_format(), PixelFormat::Bgra8);
+    }
+
+    #[test]
+    fn test_compose_grid() {
+        let colors = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [255, 255, 255, 255],
+        ];
+        let frames = colors
+            .iter()
+            .map(|color| VideoFrame::solid_color(8, 8, *color))
+            .collect::<Vec<_>>();
+        let grid = VideoFrame::compose_grid(&frames, 2, 2, 4, 4, [0, 0, 0, 255]).unwrap();
+        assert_eq!(grid.size(), (8, 8));
+        assert_eq!(grid.pixel_format(), PixelFormat::Bgra8);
+        let pixel = |frame: &VideoFrame, x: usize, y: usize| {
+            frame.as_bytes().unwrap()[(y * frame.width as usize + x) * 4..][..4].to_vec()
+        };
+        assert_eq!(pixel(&grid, 1, 1), colors[0]);
+        assert_eq!(pixel(&grid, 6, 1), colors[1]);
+        assert_eq!(pixel(&grid, 1, 6), colors[2]);
+        assert_eq!(pixel(&grid, 6, 6), colors[3]);
+
+        let wide = VideoFrame::solid_color(4, 2, colors[0]);
+        let grid = VideoFrame::compose_grid(&[wide], 2, 1, 4, 4, [9, 9, 9, 255]).unwrap();
+        assert_eq!(pixel(&grid, 1, 0), [9, 9, 9, 255]);
+        assert_eq!(pixel(&grid, 1, 1), colors[0]);
+        assert_eq!(pixel(&grid, 1, 3), [9, 9, 9, 255]);
+        assert_eq!(pixel(&grid, 6, 2), [9, 9, 9, 255]);
+
+        assert_eq!(
+            VideoFrame::compose_grid(&[], 0, 1, 4, 4, [0; 4]),
+            Err(VideoFrameError::ZeroDimension)
+        );
+    }
+
+    #[test]
+    fn test_preprocess_for_ml() {
+        let white = VideoFrame::solid_color(8, 6, [255; 4]);
+        let luma = white.preprocess_for_ml(4, 3, MlFormat::Luma).unwrap();
+        assert_eq!(luma.len(), 12);
+        assert!(
+            luma.iter().all(|value| (value - 1.0).abs() < 1e-6),
+            "{luma:?}"
+        );
+        let planar = white.preprocess_for_ml(4, 3, MlFormat::RgbPlanar).unwrap();
+        assert_eq!(planar, vec![1.0; 36]);
+
+        let red = VideoFrame::from_rgba([255, 0, 0, 255].repeat(4), 2, 2);
+        let planar = red.preprocess_for_ml(1, 2, MlFormat::RgbPlanar).unwrap();
+        assert_eq!(planar, [1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        let interleaved = red
+            .preprocess_for_ml(1, 2, MlFormat::RgbInterleaved)
+            .unwrap();
+        assert_eq!(interleaved, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+        let nv12 =
+            VideoFrame::from_nv12(vec![0; 4], vec![128; 2], 2, 2, 2, 2, VideoColorSpace::Bt601);
+        assert_eq!(
+            nv12.preprocess_for_ml(2, 2, MlFormat::Luma).unwrap(),
+            vec![0.0; 4]
+        );
+        assert_eq!(
+            white.preprocess_for_ml(0, 3, MlFormat::Luma),
+            Err(VideoFrameError::ZeroDimension)
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use super::{PixelFormat, VideoFrame, VideoFrameError};
+
+/// A borrowed rectangular region of a packed CPU [`VideoFrame`], for reading
+/// part of a frame without copying it. Obtained with [`VideoFrame::view`].
+#[derive(Debug, Clone, Copy)]
+pub struct VideoFrameView<'a> {
+    pixels: &'a [u8],
+    stride: usize,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+}
+
+impl VideoFrame {
+    /// Borrow the region of this frame with its top left corner at `x`, `y`.
+    ///
+    /// The region must lie entirely within the frame and have a non-zero size.
+    /// Only packed CPU frames can be viewed; call [`Self::to_bgra`] first for
+    /// other frames.
+    pub fn view(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<VideoFrameView<'_>, VideoFrameError> {
+        let (pixels, stride, format) = self.packed_pixels()?;
+        if width == 0 || height == 0 {
+            return Err(VideoFrameError::ZeroDimension);
+        }
+        let fits_horizontally = x
+            .checked_add(width)
+            .is_some_and(|right| right <= self.width);
+        let fits_vertically = y
+            .checked_add(height)
+            .is_some_and(|bottom| bottom <= self.height);
+        if !fits_horizontally || !fits_vertically {
+            return Err(VideoFrameError::OutOfBounds);
+        }
+
+        Ok(VideoFrameView {
+            pixels: &pixels[y as usize * stride + x as usize * 4..],
+            stride,
+            width,
+            height,
+            format,
+        })
+    }
+}
+
+impl<'a> VideoFrameView<'a> {
+    /// The width of the region in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the region in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The channel order of the pixels, [`PixelFormat::Bgra8`] or
+    /// [`PixelFormat::Rgba8`].
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// The number of bytes between the starts of consecutive rows in the
+    /// underlying frame.
+    pub fn bytes_per_row(&self) -> usize {
+        self.stride
+    }
+
+    /// Iterate over the rows of the region, top to bottom, each `width * 4`
+    /// bytes long.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + use<'a> {
+        let pixels = self.pixels;
+        let (stride, row_len) = (self.stride, self.width as usize * 4);
+        (0..self.height as usize).map(move |row| &pixels[row * stride..][..row_len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view() {
+        let frame = VideoFrame::from_bgra_with_stride((0..48).collect(), 3, 3, 16);
+        let view = frame.view(1, 1, 2, 2).unwrap();
+        assert_eq!((view.width(), view.height()), (2, 2));
+        assert_eq!(view.pixel_format(), PixelFormat::Bgra8);
+        assert_eq!(view.bytes_per_row(), 16);
+        let rows: Vec<_> = view.rows().collect();
+        assert_eq!(
+            rows,
+            [
+                &[20, 21, 22, 23, 24, 25, 26, 27][..],
+                &[36, 37, 38, 39, 40, 41, 42, 43][..]
+            ]
+        );
+
+        assert_eq!(
+            frame.view(2, 0, 2, 1).unwrap_err(),
+            VideoFrameError::OutOfBounds
+        );
+        assert_eq!(
+            frame.view(0, 0, 0, 1).unwrap_err(),
+            VideoFrameError::ZeroDimension
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use collections::HashMap;
+
+use super::{
+    AlphaMode, ColorRange, DropCallback, Rotation, VideoColorSpace, VideoFrame, VideoFrameData,
+};
+
+/// A reference to a [`VideoFrame`] that doesn't keep its pixels alive, such
+/// as for a cache that shouldn't hold on to frames nothing else uses.
+///
+/// Created with [`VideoFrame::downgrade`]. [`Self::upgrade`] gives the frame
+/// back for as long as some other frame still shares its buffers.
+///
+/// CoreVideo pixel buffers and D3D11 textures are reference counted by the
+/// platform, which has no weak references, so references to CoreVideo and
+/// D3D11 frames never upgrade. DMA-BUF frames upgrade while their file
+/// descriptor is open, and FFmpeg frames while a frame still references the
+/// decoder's buffers.
+#[derive(Clone)]
+pub struct WeakVideoFrame {
+    data: WeakVideoFrameData,
+    width: u32,
+    height: u32,
+    pts: Option<Duration>,
+    duration: Option<Duration>,
+    color_space: VideoColorSpace,
+    color_range: ColorRange,
+    alpha_mode: AlphaMode,
+    display_rotation: Rotation,
+    metadata: Option<Arc<HashMap<String, Vec<u8>>>>,
+    on_drop: Option<Weak<DropCallback>>,
+    buffer_id: u64,
+}
+
+/// The buffers of a frame, held weakly.
+#[derive(Clone)]
+enum WeakVideoFrameData {
+    Bgra {
+        buffer: Weak<Vec<u8>>,
+        stride: u32,
+    },
+    Rgba {
+        buffer: Weak<Vec<u8>>,
+        stride: u32,
+    },
+    Rgb {
+        buffer: Weak<Vec<u8>>,
+    },
+    Nv12 {
+        y_plane: Weak<Vec<u8>>,
+        uv_plane: Weak<Vec<u8>>,
+        y_stride: u32,
+        uv_stride: u32,
+    },
+    I420 {
+        y: Weak<Vec<u8>>,
+        u: Weak<Vec<u8>>,
+        v: Weak<Vec<u8>>,
+        y_stride: u32,
+        u_stride: u32,
+        v_stride: u32,
+    },
+    P010 {
+        y_plane: Weak<Vec<u16>>,
+        uv_plane: Weak<Vec<u16>>,
+        y_stride: u32,
+        uv_stride: u32,
+    },
+    Yuyv {
+        buffer: Weak<Vec<u8>>,
+        stride: u32,
+    },
+    Rgba16 {
+        buffer: Weak<Vec<u8>>,
+        float: bool,
+    },
+    #[cfg(target_os = "linux")]
+    DmaBuf {
+        fd: Weak<std::os::fd::OwnedFd>,
+        fourcc: u32,
+        modifier: u64,
+        stride: u32,
+        offset: u32,
+    },
+    #[cfg(feature = "ffmpeg")]
+    FFmpeg(Weak<ffmpeg_next::frame::Video>),
+    /// A hardware buffer that can't be referenced weakly.
+    #[cfg(any(target_os = "macos", target_os = "windows", feature = "wgpu"))]
+    Unavailable,
+}
+
+impl VideoFrame {
+    /// Get a weak reference to this frame, which doesn't keep its buffers
+    /// alive.
+    ///
+    /// See [`WeakVideoFrame`] for which frames can be upgraded again. While a
+    /// weak reference exists, [`Self::as_bytes_mut`] can't write to the
+    /// buffers in place.
+    pub fn downgrade(&self) -> WeakVideoFrame {
+        let data = match &self.data {
+            VideoFrameData::Bgra { buffer, stride } => WeakVideoFrameData::Bgra {
+                buffer: Arc::downgrade(buffer),
+                stride: *stride,
+            },
+            VideoFrameData::Rgba { buffer, stride } => WeakVideoFrameData::Rgba {
+                buffer: Arc::downgrade(buffer),
+                stride: *stride,
+            },
+            VideoFrameData::Rgb { buffer } => WeakVideoFrameData::Rgb {
+                buffer: Arc::downgrade(buffer),
+            },
+            VideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => WeakVideoFrameData::Nv12 {
+                y_plane: Arc::downgrade(y_plane),
+                uv_plane: Arc::downgrade(uv_plane),
+                y_stride: *y_stride,
+                uv_stride: *uv_stride,
+            },
+            VideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => WeakVideoFrameData::I420 {
+                y: Arc::downgrade(y),
+                u: Arc::downgrade(u),
+                v: Arc::downgrade(v),
+                y_stride: *y_stride,
+                u_stride: *u_stride,
+                v_stride: *v_stride,
+            },
+            VideoFrameData::P010 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => WeakVideoFrameData::P010 {
+                y_plane: Arc::downgrade(y_plane),
+                uv_plane: Arc::downgrade(uv_plane),
+                y_stride: *y_stride,
+                uv_stride: *uv_stride,
+            },
+            VideoFrameData::Yuyv { buffer, stride } => WeakVideoFrameData::Yuyv {
+                buffer: Arc::downgrade(buffer),
+                stride: *stride,
+            },
+            VideoFrameData::Rgba16 { buffer, float } => WeakVideoFrameData::Rgba16 {
+                buffer: Arc::downgrade(buffer),
+                float: *float,
+            },
+            #[cfg(target_os = "macos")]
+            VideoFrameData::CoreVideo(_) => WeakVideoFrameData::Unavailable,
+            #[cfg(target_os = "windows")]
+            VideoFrameData::D3D11 { .. } => WeakVideoFrameData::Unavailable,
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf {
+                fd,
+                fourcc,
+                modifier,
+                stride,
+                offset,
+            } => WeakVideoFrameData::DmaBuf {
+                fd: Arc::downgrade(fd),
+                fourcc: *fourcc,
+                modifier: *modifier,
+                stride: *stride,
+                offset: *offset,
+            },
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => WeakVideoFrameData::Unavailable,
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(source) => WeakVideoFrameData::FFmpeg(Arc::downgrade(source)),
+        };
+        WeakVideoFrame {
+            data,
+            width: self.width,
+            height: self.height,
+            pts: self.pts,
+            duration: self.duration,
+            color_space: self.color_space,
+            color_range: self.color_range,
+            alpha_mode: self.alpha_mode,
+            display_rotation: self.display_rotation,
+            metadata: self.metadata.clone(),
+            on_drop: self.on_drop.as_ref().map(Arc::downgrade),
+            buffer_id: self.buffer_id,
+        }
+    }
+}
+
+impl WeakVideoFrame {
+    /// Get the frame back, sharing its buffers, if some other frame still
+    /// holds them.
+    ///
+    /// Returns `None` once every frame sharing the buffers has been dropped,
+    /// and always for CoreVideo and D3D11 frames.
+    pub fn upgrade(&self) -> Option<VideoFrame> {
+        let data = match &self.data {
+            WeakVideoFrameData::Bgra { buffer, stride } => VideoFrameData::Bgra {
+                buffer: buffer.upgrade()?,
+                stride: *stride,
+            },
+            WeakVideoFrameData::Rgba { buffer, stride } => VideoFrameData::Rgba {
+                buffer: buffer.upgrade()?,
+                stride: *stride,
+            },
+            WeakVideoFrameData::Rgb { buffer } => VideoFrameData::Rgb {
+                buffer: buffer.upgrade()?,
+            },
+            WeakVideoFrameData::Nv12 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => VideoFrameData::Nv12 {
+                y_plane: y_plane.upgrade()?,
+                uv_plane: uv_plane.upgrade()?,
+                y_stride: *y_stride,
+                uv_stride: *uv_stride,
+            },
+            WeakVideoFrameData::I420 {
+                y,
+                u,
+                v,
+                y_stride,
+                u_stride,
+                v_stride,
+            } => VideoFrameData::I420 {
+                y: y.upgrade()?,
+                u: u.upgrade()?,
+                v: v.upgrade()?,
+                y_stride: *y_stride,
+                u_stride: *u_stride,
+                v_stride: *v_stride,
+            },
+            WeakVideoFrameData::P010 {
+                y_plane,
+                uv_plane,
+                y_stride,
+                uv_stride,
+            } => VideoFrameData::P010 {
+                y_plane: y_plane.upgrade()?,
+                uv_plane: uv_plane.upgrade()?,
+                y_stride: *y_stride,
+                uv_stride: *uv_stride,
+            },
+            WeakVideoFrameData::Yuyv { buffer, stride } => VideoFrameData::Yuyv {
+                buffer: buffer.upgrade()?,
+                stride: *stride,
+            },
+            WeakVideoFrameData::Rgba16 { buffer, float } => VideoFrameData::Rgba16 {
+                buffer: buffer.upgrade()?,
+                float: *float,
+            },
+            #[cfg(target_os = "linux")]
+            WeakVideoFrameData::DmaBuf {
+                fd,
+                fourcc,
+                modifier,
+                stride,
+                offset,
+            } => VideoFrameData::DmaBuf {
+                fd: fd.upgrade()?,
+                fourcc: *fourcc,
+                modifier: *modifier,
+                stride: *stride,
+                offset: *offset,
+            },
+            #[cfg(feature = "ffmpeg")]
+            WeakVideoFrameData::FFmpeg(source) => VideoFrameData::FFmpeg(source.upgrade()?),
+            #[cfg(any(target_os = "macos", target_os = "windows", feature = "wgpu"))]
+            WeakVideoFrameData::Unavailable => return None,
+        };
+        let on_drop = match &self.on_drop {
+            Some(on_drop) => Some(on_drop.upgrade()?),
+            None => None,
+        };
+        Some(VideoFrame {
+            data,
+            width: self.width,
+            height: self.height,
+            pts: self.pts,
+            duration: self.duration,
+            color_space: self.color_space,
+            color_range: self.color_range,
+            alpha_mode: self.alpha_mode,
+            display_rotation: self.display_rotation,
+            metadata: self.metadata.clone(),
+            on_drop,
+            buffer_id: self.buffer_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weak_video_frame() {
+        let frame = VideoFrame::from_nv12(
+            vec![1, 2, 3, 4],
+            vec![5, 6],
+            2,
+            2,
+            2,
+            2,
+            VideoColorSpace::Bt709,
+        )
+        .with_pts(Duration::from_millis(40));
+        let weak = frame.downgrade();
+        let upgraded = weak.upgrade().unwrap();
+        assert_eq!(upgraded, frame);
+        assert_eq!(upgraded.pts(), Some(Duration::from_millis(40)));
+        assert_eq!(upgraded.color_space(), VideoColorSpace::Bt709);
+        assert_eq!(frame.buffer_ref_count(), Some(2));
+
+        drop(upgraded);
+        drop(frame);
+        assert!(weak.upgrade().is_none());
+    }
+}
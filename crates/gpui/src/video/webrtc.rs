@@ -0,0 +1,193 @@
+use super::{ColorRange, Rotation, VideoColorSpace, VideoFrame, VideoFrameError};
+use libwebrtc::{
+    native::yuv_helper::argb_to_i420,
+    video_frame::{I420Buffer, VideoBuffer, VideoFrame as WebRtcFrame, VideoRotation},
+};
+use std::time::Duration;
+
+impl VideoFrame {
+    /// Create a video frame from a WebRTC frame, such as one received from a
+    /// LiveKit remote video track.
+    ///
+    /// I420 and NV12 buffers keep their planes and strides, and are limited
+    /// range BT.601 as in libwebrtc. On macOS, native buffers wrap their
+    /// CoreVideo pixel buffer without copying. libwebrtc doesn't expose the
+    /// texture behind native buffers on other platforms, so they're converted
+    /// to I420 like any other buffer. The frame's rotation becomes the
+    /// [display rotation](Self::with_display_rotation), and its timestamp the
+    /// PTS.
+    pub fn from_webrtc<T: AsRef<dyn VideoBuffer>>(
+        frame: &WebRtcFrame<T>,
+    ) -> Result<VideoFrame, VideoFrameError> {
+        let converted = from_webrtc_buffer(frame.buffer.as_ref())?.with_display_rotation(
+            match frame.rotation {
+                VideoRotation::VideoRotation0 => Rotation::None,
+                VideoRotation::VideoRotation90 => Rotation::Cw90,
+                VideoRotation::VideoRotation180 => Rotation::Cw180,
+                VideoRotation::VideoRotation270 => Rotation::Cw270,
+            },
+        );
+        Ok(match u64::try_from(frame.timestamp_us) {
+            Ok(timestamp) => converted.with_pts(Duration::from_micros(timestamp)),
+            Err(_) => converted,
+        })
+    }
+
+    /// Convert this frame into an I420 WebRTC frame, such as to send it over a
+    /// LiveKit video track for screen sharing.
+    ///
+    /// I420 frames are copied plane by plane; any other frame is read back as
+    /// BGRA and converted. The display rotation becomes the frame's rotation,
+    /// and the PTS its timestamp.
+    pub fn to_webrtc_i420(&self) -> WebRtcFrame<I420Buffer> {
+        let (width, height) = self.size();
+        let mut buffer = I420Buffer::new(width, height);
+        let (stride_y, stride_u, stride_v) = buffer.strides();
+        let (data_y, data_u, data_v) = buffer.data_mut();
+        if let Some((y, u, v, y_stride, u_stride, v_stride)) = self.i420_planes() {
+            let chroma_width = width.div_ceil(2) as usize;
+            copy_plane(y, y_stride, data_y, stride_y, width as usize);
+            copy_plane(u, u_stride, data_u, stride_u, chroma_width);
+            copy_plane(v, v_stride, data_v, stride_v, chroma_width);
+        } else {
+            let bgra = self.to_bgra();
+            if let Some(pixels) = bgra.as_bytes() {
+                argb_to_i420(
+                    pixels,
+                    bgra.bytes_per_row(),
+                    data_y,
+                    stride_y,
+                    data_u,
+                    stride_u,
+                    data_v,
+                    stride_v,
+                    width as i32,
+                    height as i32,
+                );
+            }
+        }
+        WebRtcFrame {
+            rotation: match self.display_rotation() {
+                Rotation::None => VideoRotation::VideoRotation0,
+                Rotation::Cw90 => VideoRotation::VideoRotation90,
+                Rotation::Cw180 => VideoRotation::VideoRotation180,
+                Rotation::Cw270 => VideoRotation::VideoRotation270,
+            },
+            timestamp_us: self
+                .pts()
+                .map_or(0, |pts| i64::try_from(pts.as_micros()).unwrap_or(i64::MAX)),
+            buffer,
+        }
+    }
+}
+
+fn from_webrtc_buffer(buffer: &dyn VideoBuffer) -> Result<VideoFrame, VideoFrameError> {
+    #[cfg(target_os = "macos")]
+    if let Some(native) = buffer.as_native() {
+        use core_foundation::base::TCFType as _;
+        use core_video::pixel_buffer::CVPixelBuffer;
+
+        let pixel_buffer = native.get_cv_pixel_buffer();
+        if pixel_buffer.is_null() {
+            return Err(VideoFrameError::SourceUnavailable(
+                "native WebRTC buffer has no pixel buffer".into(),
+            ));
+        }
+        // SAFETY: the native buffer holds a reference to the pixel buffer,
+        // which the frame retains before the native buffer is dropped.
+        let pixel_buffer = unsafe { CVPixelBuffer::wrap_under_get_rule(pixel_buffer as _) };
+        return VideoFrame::from_cv_pixel_buffer(pixel_buffer);
+    }
+
+    if let Some(nv12) = buffer.as_nv12() {
+        let (y_plane, uv_plane) = nv12.data();
+        let (y_stride, uv_stride) = nv12.strides();
+        return Ok(VideoFrame::try_from_nv12(
+            y_plane.to_vec(),
+            uv_plane.to_vec(),
+            y_stride,
+            uv_stride,
+            nv12.width(),
+            nv12.height(),
+            VideoColorSpace::Bt601,
+        )?
+        .with_color_range(ColorRange::Limited));
+    }
+
+    let converted;
+    let i420 = match buffer.as_i420() {
+        Some(i420) => i420,
+        None => {
+            converted = buffer.to_i420();
+            &converted
+        }
+    };
+    let (y, u, v) = i420.data();
+    let (y_stride, u_stride, v_stride) = i420.strides();
+    Ok(VideoFrame::try_from_i420(
+        y.to_vec(),
+        u.to_vec(),
+        v.to_vec(),
+        y_stride,
+        u_stride,
+        v_stride,
+        i420.width(),
+        i420.height(),
+        VideoColorSpace::Bt601,
+    )?
+    .with_color_range(ColorRange::Limited))
+}
+
+/// Copy the visible `row_len` bytes of each row from one plane to another.
+fn copy_plane(
+    source: &[u8],
+    source_stride: u32,
+    destination: &mut [u8],
+    destination_stride: u32,
+    row_len: usize,
+) {
+    for (source_row, destination_row) in source
+        .chunks(source_stride as usize)
+        .zip(destination.chunks_mut(destination_stride as usize))
+    {
+        let row_len = row_len.min(source_row.len()).min(destination_row.len());
+        destination_row[..row_len].copy_from_slice(&source_row[..row_len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_i420_round_trip() {
+        let frame = VideoFrame::from_i420(
+            vec![16, 64, 128, 235, 32, 96, 160, 224],
+            vec![90, 110],
+            vec![240, 16],
+            4,
+            2,
+            2,
+            4,
+            2,
+            VideoColorSpace::Bt601,
+        )
+        .with_color_range(ColorRange::Limited)
+        .with_display_rotation(Rotation::Cw90)
+        .with_pts(Duration::from_millis(40));
+
+        let webrtc = frame.to_webrtc_i420();
+        assert!(matches!(webrtc.rotation, VideoRotation::VideoRotation90));
+        assert_eq!(webrtc.timestamp_us, 40_000);
+
+        let round_tripped = VideoFrame::from_webrtc(&webrtc).unwrap();
+        assert_eq!(round_tripped.size(), (4, 2));
+        assert_eq!(round_tripped.display_rotation(), Rotation::Cw90);
+        assert_eq!(round_tripped.pts(), Some(Duration::from_millis(40)));
+        assert_eq!(round_tripped.color_range(), ColorRange::Limited);
+        assert_eq!(
+            round_tripped.to_bgra().as_bytes(),
+            frame.to_bgra().as_bytes()
+        );
+    }
+}
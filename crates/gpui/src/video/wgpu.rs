@@ -0,0 +1,150 @@
+use super::{PixelFormat, VideoFrame, VideoFrameData, VideoFrameError, validate_dimensions};
+use util::ResultExt as _;
+
+impl VideoFrame {
+    /// Create a video frame that wraps a wgpu texture without copying, such as
+    /// the output of a custom render pipeline.
+    ///
+    /// The frame shows the `width` by `height` region at the texture's origin,
+    /// which must fit within the texture. `Bgra8Unorm`, `Rgba8Unorm`, their
+    /// sRGB variants, `Rgba16Unorm`, `Rgba16Float`, `NV12` and `P010` textures
+    /// are supported; other formats return
+    /// [`VideoFrameError::UnsupportedSourceFormat`].
+    ///
+    /// The texture doesn't carry the device it was created on, so gpui can't
+    /// paint or read back these frames: painting them draws nothing, and
+    /// [`Self::to_bgra`] gives a black frame. They're meant to be passed to
+    /// other wgpu pipelines through [`Self::upload_to_wgpu`].
+    pub fn from_wgpu_texture(
+        texture: ::wgpu::Texture,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, VideoFrameError> {
+        validate_dimensions(width, height)?;
+        if width > texture.width() || height > texture.height() {
+            return Err(VideoFrameError::OutOfBounds);
+        }
+        if wgpu_pixel_format(texture.format()).is_none() {
+            return Err(VideoFrameError::UnsupportedSourceFormat(format!(
+                "wgpu {:?}",
+                texture.format()
+            )));
+        }
+        Ok(Self::new(VideoFrameData::Wgpu(texture), width, height))
+    }
+
+    /// Upload this frame to a new texture on `device`, which can be sampled
+    /// and copied from.
+    ///
+    /// RGBA frames are uploaded as `Rgba8Unorm`. Other frames are converted as
+    /// in [`Self::to_bgra`], including any hardware readback, and uploaded as
+    /// `Bgra8Unorm`. Rows are padded to wgpu's 256-byte `bytes_per_row`
+    /// alignment as described in [`Self::to_aligned_rows`]. The pixels are
+    /// uploaded as they are, in the frame's [`Self::alpha_mode`].
+    ///
+    /// Frames created with [`Self::from_wgpu_texture`] return their texture
+    /// without copying.
+    pub fn upload_to_wgpu(
+        &self,
+        device: &::wgpu::Device,
+        queue: &::wgpu::Queue,
+    ) -> ::wgpu::Texture {
+        let (frame, format) = match &self.data {
+            VideoFrameData::Wgpu(texture) => return texture.clone(),
+            VideoFrameData::Rgba { .. } => (self.clone(), ::wgpu::TextureFormat::Rgba8Unorm),
+            _ => (self.to_bgra(), ::wgpu::TextureFormat::Bgra8Unorm),
+        };
+
+        let size = ::wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&::wgpu::TextureDescriptor {
+            label: Some("video frame"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: ::wgpu::TextureDimension::D2,
+            format,
+            usage: ::wgpu::TextureUsages::TEXTURE_BINDING
+                | ::wgpu::TextureUsages::COPY_SRC
+                | ::wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        if let Some((pixels, stride)) = frame
+            .to_aligned_rows(::wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            .log_err()
+        {
+            queue.write_texture(
+                ::wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: ::wgpu::Origin3d::ZERO,
+                    aspect: ::wgpu::TextureAspect::All,
+                },
+                &pixels,
+                ::wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(stride),
+                    rows_per_image: Some(self.height),
+                },
+                size,
+            );
+        }
+        texture
+    }
+}
+
+/// The pixel format of the wgpu texture formats that frames can wrap.
+pub(super) fn wgpu_pixel_format(format: ::wgpu::TextureFormat) -> Option<PixelFormat> {
+    use ::wgpu::TextureFormat;
+
+    match format {
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => Some(PixelFormat::Bgra8),
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => Some(PixelFormat::Rgba8),
+        TextureFormat::Rgba16Unorm => Some(PixelFormat::Rgba16Unorm),
+        TextureFormat::Rgba16Float => Some(PixelFormat::Rgba16Float),
+        TextureFormat::NV12 => Some(PixelFormat::Nv12),
+        TextureFormat::P010 => Some(PixelFormat::P010),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VideoFrameBackend;
+
+    #[test]
+    fn test_wgpu_round_trip() {
+        let (device, queue) = ::wgpu::Device::noop(&Default::default());
+
+        let frame = VideoFrame::from_rgba(vec![255; 3 * 2 * 4], 3, 2);
+        let texture = frame.upload_to_wgpu(&device, &queue);
+        assert_eq!(texture.format(), ::wgpu::TextureFormat::Rgba8Unorm);
+        assert_eq!((texture.width(), texture.height()), (3, 2));
+
+        let nv12 = VideoFrame::from_nv12(
+            vec![16; 4 * 2],
+            vec![128; 4],
+            4,
+            4,
+            4,
+            2,
+            crate::VideoColorSpace::Bt709,
+        );
+        let texture = nv12.upload_to_wgpu(&device, &queue);
+        assert_eq!(texture.format(), ::wgpu::TextureFormat::Bgra8Unorm);
+
+        let wrapped = VideoFrame::from_wgpu_texture(texture.clone(), 2, 2).unwrap();
+        assert_eq!(wrapped.backend(), VideoFrameBackend::Wgpu);
+        assert_eq!(wrapped.pixel_format(), PixelFormat::Bgra8);
+        assert!(wrapped.is_hardware_backed());
+        assert_eq!(wrapped.upload_to_wgpu(&device, &queue), texture);
+        assert_eq!(
+            VideoFrame::from_wgpu_texture(texture, 5, 2).unwrap_err(),
+            VideoFrameError::OutOfBounds
+        );
+    }
+}
@@ -1950,6 +1950,18 @@ impl Window {
         self.scale_factor
     }
 
+    /// Capture what the window last rendered as a BGRA video frame, such as for
+    /// saving a screenshot or sharing the window's contents.
+    ///
+    /// The frame has the window's size in device pixels, so it includes the
+    /// scale factor. The last rendered scene is drawn again without being
+    /// presented and read back, which blocks until the GPU is done. Platforms
+    /// without a GPU renderer, such as the test platform, return an error.
+    pub fn capture_frame(&self) -> anyhow::Result<crate::VideoFrame> {
+        self.platform_window
+            .capture_frame(&self.rendered_frame.scene)
+    }
+
     /// The size of an em for the base font of the application. Adjusting this value allows the
     /// UI to scale, just like zooming a web page.
     pub fn rem_size(&self) -> Pixels {
@@ -3346,11 +3358,18 @@ impl Window {
         let scale_factor = self.scale_factor();
         let bounds = bounds.scale(scale_factor);
         let content_mask = self.content_mask().scale(scale_factor);
+        let color_range = crate::video::cv_pixel_buffer_color_range(&image_buffer);
         self.next_frame.scene.insert_primitive(PaintSurface {
             order: 0,
             bounds,
             content_mask,
-            frame_data: PaintSurfaceData::CoreVideo(image_buffer),
+            corner_radii: Default::default(),
+            rotation: Default::default(),
+            alpha_mode: crate::AlphaMode::Opaque,
+            frame_data: PaintSurfaceData::CoreVideo {
+                buffer: image_buffer,
+                color_range,
+            },
         });
     }
 
@@ -3359,8 +3378,30 @@ impl Window {
     /// This is the cross-platform entry point for video rendering. Use this method
     /// when you have a `VideoFrame` from a video decoder or player.
     ///
+    /// The frame is clipped to `corner_radii`, matching how images are clipped,
+    /// and rotated by its [display rotation](crate::VideoFrame::display_rotation)
+    /// to fill `bounds`, which should have the rotated aspect ratio.
+    ///
     /// This method should only be called as part of the paint phase of element drawing.
-    pub fn paint_video_surface(&mut self, bounds: Bounds<Pixels>, frame: crate::VideoFrame) {
+    pub fn paint_video_surface(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        corner_radii: Corners<Pixels>,
+        frame: crate::VideoFrame,
+    ) {
+        self.paint_video_surface_with_partial_upload(bounds, corner_radii, frame, None);
+    }
+
+    /// Paint a video frame as in [`Self::paint_video_surface`], letting the
+    /// renderer update the upload of the frame painted in its place before
+    /// when only a region of it has changed.
+    pub(crate) fn paint_video_surface_with_partial_upload(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        corner_radii: Corners<Pixels>,
+        frame: crate::VideoFrame,
+        partial_upload: Option<crate::video::PartialUpload>,
+    ) {
         use crate::{PaintSurface, PaintSurfaceData, video::VideoFrameData};
 
         self.invalidator.debug_assert_paint();
@@ -3368,29 +3409,65 @@ impl Window {
         let scale_factor = self.scale_factor();
         let bounds = bounds.scale(scale_factor);
         let content_mask = self.content_mask().scale(scale_factor);
+        let corner_radii = corner_radii.scale(scale_factor);
+        let rotation = frame.display_rotation();
+        let partial_upload = partial_upload.filter(|partial_upload| {
+            partial_upload.width > 0
+                && partial_upload.height > 0
+                && partial_upload.x.saturating_add(partial_upload.width) <= frame.width
+                && partial_upload.y.saturating_add(partial_upload.height) <= frame.height
+        });
 
-        let frame_data = match frame.data {
-            VideoFrameData::Bgra(buffer) => PaintSurfaceData::Bgra {
-                buffer,
-                width: frame.width,
-                height: frame.height,
-            },
+        let frame_data = match &frame.data {
             #[cfg(target_os = "macos")]
-            VideoFrameData::CoreVideo(cv_buffer) => PaintSurfaceData::CoreVideo(cv_buffer),
+            VideoFrameData::CoreVideo(cv_buffer) => PaintSurfaceData::CoreVideo {
+                buffer: cv_buffer.as_raw().clone(),
+                color_range: frame.color_range(),
+            },
             #[cfg(target_os = "windows")]
             VideoFrameData::D3D11 {
                 texture,
                 subresource_index,
             } => PaintSurfaceData::D3D11 {
-                texture,
-                subresource_index,
+                texture: texture.as_raw().clone(),
+                subresource_index: *subresource_index,
+            },
+            // Renderers convert the frame for upload only when they haven't
+            // cached its upload already.
+            VideoFrameData::Bgra { .. }
+            | VideoFrameData::Rgba { .. }
+            | VideoFrameData::Rgb { .. }
+            | VideoFrameData::Nv12 { .. }
+            | VideoFrameData::I420 { .. }
+            | VideoFrameData::P010 { .. }
+            | VideoFrameData::Yuyv { .. }
+            | VideoFrameData::Rgba16 { .. } => PaintSurfaceData::Cpu {
+                frame: frame.clone(),
+                partial_upload,
             },
+            #[cfg(target_os = "linux")]
+            VideoFrameData::DmaBuf { .. } => PaintSurfaceData::Cpu {
+                frame: frame.clone(),
+                partial_upload,
+            },
+            #[cfg(feature = "ffmpeg")]
+            VideoFrameData::FFmpeg(_) => PaintSurfaceData::Cpu {
+                frame: frame.clone(),
+                partial_upload,
+            },
+            // The texture belongs to the application's own wgpu device, which
+            // the renderer can't read from.
+            #[cfg(feature = "wgpu")]
+            VideoFrameData::Wgpu(_) => return,
         };
 
         self.next_frame.scene.insert_primitive(PaintSurface {
             order: 0,
             bounds,
             content_mask,
+            corner_radii,
+            rotation,
+            alpha_mode: frame.alpha_mode(),
             frame_data,
         });
     }